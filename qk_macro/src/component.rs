@@ -1,5 +1,5 @@
 use proc_macro2::{Ident, TokenStream};
-use quote::{quote, ToTokens};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::parse::Parse;
 use syn::visit::Visit;
 use syn::visit_mut::VisitMut;
@@ -20,6 +20,8 @@ pub struct Component {
     pub rsx: Elements,
     pub fn_item: ItemFn,
     pub prop_items: Vec<Prop>,
+    /// Validators for the whole props struct from `#[validate(path::to::fn)]`
+    pub validators: Vec<syn::Path>,
 }
 
 impl Component {
@@ -46,6 +48,31 @@ impl Component {
         }
     }
 
+    /// The states that hold the reactive props. They are registered before the states in the body
+    fn prop_states(&self) -> &[State] {
+        let reactive = self
+            .prop_items
+            .iter()
+            .filter(|prop| !prop.is_static())
+            .count();
+        &self.states[..reactive]
+    }
+
+    /// The reactive props that no memo or listener reads. Nothing checks their dirty tracking slot, so they may as well be static
+    fn unused_props(&self) -> Vec<&State> {
+        let listened: Vec<usize> = self
+            .rsx
+            .roots
+            .iter()
+            .flat_map(|root| &root.dynamic_nodes)
+            .flat_map(|dyn_node| dyn_node.listened_states())
+            .collect();
+        self.prop_states()
+            .iter()
+            .filter(|state| state.subscribers.is_empty() && !listened.contains(&state.id))
+            .collect()
+    }
+
     fn comp_name(&self) -> Ident {
         Ident::new(&format!("{}State", self.type_name), self.type_name.span())
     }
@@ -87,6 +114,11 @@ impl ToTokens for Component {
                 root.dynamic_nodes
                     .iter()
                     .map(|dyn_node| dyn_node.type_def())
+            }))
+            .chain(self.rsx.roots.iter().flat_map(|root| {
+                root.dynamic_nodes
+                    .iter()
+                    .filter_map(|dyn_node| dyn_node.element_ref().map(|(field, _)| field))
             }));
 
         let create_comp = self
@@ -117,6 +149,12 @@ impl ToTokens for Component {
                         #name
                     }
                 })
+            }))
+            // the ids are known once the rsx is created, so the refs are written when the component is built
+            .chain(self.rsx.roots.iter().flat_map(|root| {
+                root.dynamic_nodes
+                    .iter()
+                    .filter_map(|dyn_node| dyn_node.element_ref().map(|(_, init)| init))
             }));
 
         let ident_init = self
@@ -152,6 +190,8 @@ impl ToTokens for Component {
                 })
             }));
 
+        let init_props = self.prop_states().iter().map(|state| state.construct());
+
         let roots = self.rsx.roots.iter().map(|root| {
             let name = root.root_ident();
             quote! {
@@ -177,17 +217,103 @@ impl ToTokens for Component {
 
         let prop_name = self.prop_name();
         let props_struct = self.props_struct();
-        let props = self.prop_items.iter().map(|state| {
-            let name = &state.name;
+        // Used by both create and update
+        let props: Vec<_> = self
+            .prop_items
+            .iter()
+            .map(|state| {
+                let name = &state.name;
 
+                quote! {
+                    #name
+                }
+            })
+            .collect();
+        let type_name = self.type_name.to_string();
+        let validate_struct: Vec<_> = self
+            .validators
+            .iter()
+            .map(|validator| {
+                quote! {
+                    qk::component::validate_props(#type_name, #validator(&self));
+                }
+            })
+            .collect();
+        let validate_props: Vec<_> = self
+            .prop_items
+            .iter()
+            .flat_map(|prop| {
+                let name = &prop.name;
+                let name_str = name.to_string();
+                let type_name = &type_name;
+                prop.validators().map(move |validator| {
+                    quote! {
+                        qk::component::validate_prop(#type_name, #name_str, #validator(&#name));
+                    }
+                })
+            })
+            .collect();
+
+        // Only write the props that changed so only the memos subscribed to them rerun. Props that aren't PartialEq are always written
+        let update_props = self.prop_states().iter().map(|state| {
+            let name = &state.name;
+            let with_fn = Ident::new(&format!("with_{name}"), proc_macro2::Span::call_site());
             quote! {
-                #name
+                if (&qk::component::PropChange(&comp.#name, &#name)).changed() {
+                    comp.#with_fn(|mut value| *value = #name);
+                }
+            }
+        });
+        // Proc macros can't emit warnings on stable, so each unused prop uses a deprecated constant to get a warning pointing at the prop
+        let unused_props = self.unused_props().into_iter().map(|state| {
+            let name = &state.name;
+            let note = format!(
+                "the reactive prop `{name}` of `{type_name}` is never read by the rsx, a listener or an rx closure. Mark it `#[prop(static)]` if it doesn't need to be tracked"
+            );
+            let marker = Ident::new(&format!("__unused_prop_{name}"), name.span());
+            quote_spanned! {name.span()=>
+                #[deprecated(note = #note)]
+                #[allow(non_upper_case_globals)]
+                const #marker: () = ();
+                let _ = #marker;
             }
         });
+        let static_props = self
+            .prop_items
+            .iter()
+            .filter(|prop| prop.is_static())
+            .map(|prop| {
+                let name = &prop.name;
+                quote! {
+                    let _ = #name;
+                }
+            });
+
+        // Seed the scope heuristics with the states the component is known to own
+        // The arguments of a memo's closure don't change its size, so they are left out because they may refer to R
+        let owned_types: Vec<_> = self
+            .states
+            .iter()
+            .map(|state| state.ty.to_token_stream())
+            .chain(
+                self.memos
+                    .iter()
+                    .filter(|memo| !memo.runs_once())
+                    .map(|memo| {
+                        let ty = &memo.ty;
+                        quote! {
+                            Effect<Box<dyn Fn()>, #ty>
+                        }
+                    }),
+            )
+            .collect();
+        let owned = owned_types.len();
 
         tokens.extend(quote! {
             #props_struct
 
+            qk::impl_hyristics!(#prop_name, #owned, 0 #(+ std::mem::size_of::<#owned_types>())*);
+
             struct #comp_name<R: qk::renderer::Renderer<R> + qk::events::PlatformEvents> {
                 tracking: DirtyTrackSet<u8, u8>,
                 ui: R,
@@ -201,9 +327,13 @@ impl ToTokens for Component {
                 type State = std::rc::Rc<std::cell::RefCell<#comp_name<R>>>;
                 
                 fn create(self, ui: &mut R) -> Self::State {
+                    #(#unused_props)*
+                    #(#validate_struct)*
                     let Self { #(#props,)* } = self;
+                    #(#validate_props)*
                     let tracking: DirtyTrackSet<u8, u8> = DirtyTrackSet::default();
                     #(#ident_init)*
+                    #(#init_props)*
                     #(#body)*
                     let mut comp = #comp_name {
                         tracking,
@@ -217,6 +347,17 @@ impl ToTokens for Component {
 
                     comp
                 }
+
+                fn update(self, state: &mut Self::State) {
+                    #(#validate_struct)*
+                    let Self { #(#props,)* } = self;
+                    #(#validate_props)*
+                    #(#static_props)*
+                    let mut comp = state.borrow_mut();
+                    #[allow(unused_imports)]
+                    use qk::component::{ComparedProp as _, UncomparedProp as _};
+                    #(#update_props)*
+                }
             }
 
             impl<R: qk::renderer::Renderer<R> + qk::events::PlatformEvents> qk::component::ComponentState<R, R> for #comp_name<R> {
@@ -236,12 +377,14 @@ impl Parse for Component {
         let mut visitor = ComponentBuilder {
             states: Default::default(),
             memos: Default::default(),
+            prop_items: Default::default(),
             rsx: None,
             fn_item: f.clone(),
             type_name,
             in_reactive: false,
         };
 
+        visitor.props()?;
         visitor.visit_item_fn(&f);
 
         let mut myself = visitor.build()?;
@@ -249,7 +392,7 @@ impl Parse for Component {
         ComponentVisitorMut {
             component: &myself,
             memo_idx: 0,
-            state_idx: 0,
+            state_idx: myself.prop_states().len(),
         }
         .visit_item_fn_mut(&mut f);
 
@@ -258,3 +401,194 @@ impl Parse for Component {
         Ok(myself)
     }
 }
+
+#[test]
+fn prop_validators() {
+    let component: Component = syn::parse2(quote! {
+        #[validate(check_range)]
+        fn Progress(cx: Scope, #[prop(validate = percent)] value: u8, max: u8) {
+            rsx! {
+                <div>"progress"</div>
+            }
+        }
+    })
+    .unwrap();
+    let tokens = component.to_token_stream().to_string();
+
+    let validate_value = quote! {
+        qk::component::validate_prop("Progress", "value", percent(&value));
+    };
+    assert!(tokens.contains(&validate_value.to_string()));
+    let validate_struct = quote! {
+        qk::component::validate_props("Progress", check_range(&self));
+    };
+    assert!(tokens.contains(&validate_struct.to_string()));
+    // once in create and once in update
+    assert_eq!(tokens.matches("validate_prop (").count(), 2);
+}
+
+#[test]
+fn capacity_hints() {
+    let component: Component = syn::parse2(quote! {
+        fn Counter(cx: Scope) {
+            let count: Rx<i32> = 0;
+            let step: Rx<u8> = 1;
+            rsx! {
+                <div>"count: {count}"</div>
+            }
+        }
+    })
+    .unwrap();
+    let tokens = component.to_token_stream().to_string();
+
+    let hints = quote! {
+        qk::impl_hyristics!(
+            Counter,
+            3usize,
+            0 + std::mem::size_of::<i32>()
+                + std::mem::size_of::<u8>()
+                + std::mem::size_of::<Effect<Box<dyn Fn()>, ()> >()
+        );
+    };
+    assert!(tokens.contains(&hints.to_string()));
+}
+
+#[test]
+fn static_props() {
+    let component: Component = syn::parse2(quote! {
+        fn Label(
+            cx: Scope,
+            #[prop(static)] id: u32,
+            #[prop(static)] class: String,
+            #[prop(static)] title: String,
+            text: String,
+        ) {
+            rsx! {
+                <div>"{text}"</div>
+            }
+        }
+    })
+    .unwrap();
+
+    // Only the reactive prop takes a dirty tracking slot
+    assert_eq!(component.states.len(), 1);
+    assert_eq!(component.states[0].name, "text");
+    assert_eq!(component.prop_states().len(), 1);
+    assert_eq!(
+        component
+            .prop_items
+            .iter()
+            .filter(|prop| prop.is_static())
+            .count(),
+        3
+    );
+
+    let tokens = component.to_token_stream().to_string();
+    let init = quote! {
+        __text = text;
+    };
+    assert!(tokens.contains(&init.to_string()));
+    assert!(tokens.contains("track (0u8)"));
+    assert!(!tokens.contains("track (1u8)"));
+}
+
+#[test]
+fn unused_reactive_props_warn() {
+    let component: Component = syn::parse2(quote! {
+        fn Badge(cx: Scope, label: String, loud: bool, color: String, #[prop(static)] id: u32) {
+            let clicks: Rx<u32> = 0;
+            rsx! {
+                <div title={if loud { "!" } else { "" }}>"{label}"</div>
+            }
+        }
+    })
+    .unwrap();
+
+    // a prop read in one branch of the rsx is still read
+    let unused: Vec<_> = component
+        .unused_props()
+        .iter()
+        .map(|state| state.name.to_string())
+        .collect();
+    assert_eq!(unused, ["color"]);
+
+    let tokens = component.to_token_stream().to_string();
+    assert!(tokens.contains("const __unused_prop_color : () = () ;"));
+    assert!(tokens.contains("the reactive prop `color` of `Badge` is never read"));
+    assert_eq!(tokens.matches("# [deprecated").count(), 1);
+}
+
+#[test]
+fn attribute_transform_subscribes() {
+    let component: Component = syn::parse2(quote! {
+        fn Counter(cx: Scope) {
+            let count: Rx<i32> = 0;
+            rsx! {
+                <div data-count={count => count.to_string()}></div>
+            }
+        }
+    })
+    .unwrap();
+
+    // the attribute's memo reruns the transform whenever count is written
+    let memo = &component.memos[0];
+    assert!(memo.subscriptions.contains(&0));
+    assert!(component.states[0].subscribers.contains(&memo.id));
+}
+
+#[test]
+fn computed_attribute_subscribes_to_every_signal() {
+    let component: Component = syn::parse2(quote! {
+        fn Draggable(cx: Scope) {
+            let x: Rx<i32> = 0;
+            let y: Rx<i32> = 0;
+            let snapped: Rx<bool> = false;
+            rsx! {
+                <div style:transform={move || if *snapped { String::new() } else { format!("translate({x}px,{}px)", y) }}></div>
+            }
+        }
+    })
+    .unwrap();
+
+    // a write to either coordinate reruns the attribute, even though they are only read in one branch of a format string
+    let memo = &component.memos[0];
+    assert_eq!(memo.subscriptions.len(), 3);
+    for state in &component.states {
+        assert!(state.subscribers.contains(&memo.id));
+    }
+}
+
+#[test]
+fn update_writes_changed_props() {
+    let component: Component = syn::parse2(quote! {
+        fn Point(cx: Scope, x: i32, y: i32, z: i32) {
+            rsx! {
+                <div x={x.to_string()}></div>
+                <div y={y.to_string()}></div>
+                <div z={z.to_string()}></div>
+            }
+        }
+    })
+    .unwrap();
+
+    let tokens = component.to_token_stream().to_string();
+    for (id, name) in ["x", "y", "z"].into_iter().enumerate() {
+        let prop = Ident::new(name, proc_macro2::Span::call_site());
+        let with_fn = Ident::new(&format!("with_{name}"), proc_macro2::Span::call_site());
+        // each prop is only written if it changed
+        let update = quote! {
+            if (&qk::component::PropChange(&comp.#prop, &#prop)).changed() {
+                comp.#with_fn(|mut value| *value = #prop);
+            }
+        };
+        assert!(tokens.contains(&update.to_string()));
+
+        // and writing it only reruns the memo that reads it
+        let state = &component.states[id];
+        assert_eq!(state.subscribers.len(), 1);
+        let memo = *state.subscribers.iter().next().unwrap();
+        for other in component.states.iter().filter(|other| other.id != id) {
+            assert!(!other.subscribers.contains(&memo));
+        }
+    }
+}
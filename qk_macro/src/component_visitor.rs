@@ -1,18 +1,20 @@
 use crate::component::Component;
 use crate::memo::Memo;
+use crate::prop::Prop;
 use crate::rsx::Elements;
 use crate::state::State;
-use proc_macro2::Ident;
+use proc_macro2::{Ident, TokenStream, TokenTree};
 use quote::ToTokens;
 use syn::visit::{self, Visit};
 use syn::ItemFn;
-use syn::{parse2, Expr, Token, Type};
+use syn::{parse2, Expr, LitStr, Token, Type};
 use syn::{ExprPath, Pat, PathArguments, PathSegment, TypeTuple};
 
 #[derive(Debug)]
 pub struct ComponentBuilder {
     pub states: Vec<State>,
     pub memos: Vec<Memo>,
+    pub prop_items: Vec<Prop>,
     pub rsx: Option<Result<Elements, syn::Error>>,
     pub fn_item: ItemFn,
     pub type_name: Ident,
@@ -30,6 +32,29 @@ impl ComponentBuilder {
         })
     }
 
+    /// Parse the props of the component. Props that are not static are tracked like a state, so they must be registered before the body is visited
+    pub fn props(&mut self) -> Result<(), syn::Error> {
+        let prop_items: Vec<Prop> = self
+            .fn_item
+            .sig
+            .inputs
+            .iter()
+            .skip(1)
+            .filter_map(|item| match item {
+                syn::FnArg::Typed(item) => Some(item.clone().try_into()),
+                syn::FnArg::Receiver(_) => None,
+            })
+            .collect::<Result<_, _>>()?;
+
+        for prop in prop_items.iter().filter(|prop| !prop.is_static()) {
+            let name = &prop.name;
+            self.state(name.clone(), prop.ty.clone(), syn::parse_quote!(#name));
+        }
+        self.prop_items = prop_items;
+
+        Ok(())
+    }
+
     pub fn memo(
         &mut self,
         ty: Option<Type>,
@@ -57,6 +82,7 @@ impl ComponentBuilder {
             subscriptions: visitor.subscribed.into_iter().collect(),
             subscribers: Default::default(),
             raw_params,
+            setup: TokenStream::new(),
         });
 
         id
@@ -66,6 +92,7 @@ impl ComponentBuilder {
         let Self {
             mut states,
             mut memos,
+            prop_items,
             rsx,
             fn_item,
             type_name,
@@ -95,16 +122,12 @@ impl ComponentBuilder {
             state.subscribers = subscribers.into_iter().collect();
         }
 
-        let prop_items = fn_item
-            .sig
-            .inputs
+        let validators = fn_item
+            .attrs
             .iter()
-            .skip(1)
-            .filter_map(|item| match item {
-                syn::FnArg::Typed(item) => Some(item.clone().into()),
-                syn::FnArg::Receiver(_) => None,
-            })
-            .collect();
+            .filter(|attr| attr.path.is_ident("validate"))
+            .map(|attr| attr.parse_args())
+            .collect::<Result<_, _>>()?;
 
         Ok(Component {
             type_name,
@@ -113,6 +136,7 @@ impl ComponentBuilder {
             rsx,
             fn_item,
             prop_items,
+            validators,
         })
     }
 }
@@ -189,14 +213,49 @@ pub struct SubscriptionVisitor<'a> {
     pub subscribed: Vec<usize>,
 }
 
-impl<'a, 'b> Visit<'a> for SubscriptionVisitor<'b> {
-    fn visit_ident(&mut self, i: &'a Ident) {
+impl SubscriptionVisitor<'_> {
+    fn subscribe(&mut self, i: &Ident) {
         if let Some(name) = self.states.iter().find(|s| &s.name == i) {
             if !self.subscribed.contains(&name.id) {
                 self.subscribed.push(name.id);
             }
         }
+    }
+
+    fn subscribe_tokens(&mut self, tokens: TokenStream) {
+        for token in tokens {
+            match token {
+                TokenTree::Ident(ident) => self.subscribe(&ident),
+                TokenTree::Group(group) => self.subscribe_tokens(group.stream()),
+                TokenTree::Literal(literal) => {
+                    // names captured by a format string like "{count}"
+                    let Ok(literal) = parse2::<LitStr>(TokenTree::Literal(literal).into()) else {
+                        continue;
+                    };
+                    for captured in literal.value().replace("{{", "").split('{').skip(1) {
+                        let name = captured.split([':', '}']).next().unwrap_or_default();
+                        if let Ok(name) = syn::parse_str::<Ident>(name) {
+                            self.subscribe(&name);
+                        }
+                    }
+                }
+                TokenTree::Punct(_) => {}
+            }
+        }
+    }
+}
+
+impl<'a, 'b> Visit<'a> for SubscriptionVisitor<'b> {
+    fn visit_ident(&mut self, i: &'a Ident) {
+        self.subscribe(i);
 
         syn::visit::visit_ident(self, i);
     }
+
+    // The arguments of a macro like `format!` aren't parsed, so any state named in them is treated as read
+    fn visit_macro(&mut self, mac: &'a syn::Macro) {
+        self.subscribe_tokens(mac.tokens.clone());
+
+        syn::visit::visit_macro(self, mac);
+    }
 }
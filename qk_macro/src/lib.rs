@@ -8,12 +8,14 @@ mod node;
 mod prop;
 mod read_builder;
 mod rsx;
+mod signal;
 mod state;
 
 use component::Component;
 use proc_macro::TokenStream;
 use quote::quote;
 use rsx::Elements;
+use signal::Signal;
 use syn::parse_macro_input;
 
 #[proc_macro_attribute]
@@ -33,3 +35,13 @@ pub fn rsx(input: TokenStream) -> TokenStream {
         #input
     })
 }
+
+/// Implement `Readable` for a struct with a single `State<T>` or `Memo<T>` field, and `Writable` if the field is a `State<T>`, so the wrapper can be passed to generic reactive code
+#[proc_macro_derive(Signal)]
+pub fn signal(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as Signal);
+
+    TokenStream::from(quote! {
+        #input
+    })
+}
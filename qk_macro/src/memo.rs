@@ -16,6 +16,8 @@ pub struct Memo {
     pub subscriptions: HashSet<usize>,
     pub subscribers: HashSet<usize>,
     pub raw_params: Vec<(Ident, Type)>,
+    /// Statements run once before the closure is created. The closure captures what they declare, so it can keep values between runs
+    pub setup: TokenStream,
 }
 
 impl std::fmt::Debug for Memo {
@@ -103,7 +105,14 @@ impl Memo {
     pub fn construct(&self, component: &Component) -> Stmt {
         if self.runs_once() {
             let closure = &self.closure;
-            return parse_quote!(#closure);
+            let setup = &self.setup;
+            if setup.is_empty() {
+                return parse_quote!(#closure);
+            }
+            return parse_quote!({
+                #setup
+                #closure
+            });
         }
 
         let states = &component.states;
@@ -127,15 +136,25 @@ impl Memo {
         });
 
         let movability = &self.capture;
+        let mut boxed = quote! {
+            Box::new(#movability |#parameters| {
+                #closure
+            }) as Box<dyn Fn(#types) -> #ty>
+        };
+        if !self.setup.is_empty() {
+            let setup = &self.setup;
+            boxed = quote!({
+                #setup
+                #boxed
+            });
+        }
 
         parse_quote! {
             #private_name = {
                 tracking.reset_read();
                 #( #rw_tracks )*
                 #[allow(clippy::unused_unit)]
-                let #private_name = Box::new(#movability |#parameters| {
-                    #closure
-                }) as Box<dyn Fn(#types) -> #ty>;
+                let #private_name = #boxed;
                 let current = #private_name(
                     #(
                         #subscribers,
@@ -184,19 +203,26 @@ impl Memo {
             .collect();
 
         let subscriptions_update = self.subscriptions.iter().map(|id| states[*id].update_fn());
+        let render_name = format!("{}::{}", component.type_name, ident_name);
 
         quote! {
             fn #update_fn_name(&mut self) {
                 self.tracking.reset_write();
                 let old = self.#ident_name.current.clone();
-                #(
-                    #subscriptions_setup
-                )*
-                self.#ident_name.current = (self.#ident_name.rx)(
+                // a memo that writes a state it reads is rerun once with the new value instead of looping
+                for _ in 0..2 {
                     #(
-                        #subscriptions,
+                        #subscriptions_setup
                     )*
-                );
+                    self.#ident_name.current = (self.#ident_name.rx)(
+                        #(
+                            #subscriptions,
+                        )*
+                    );
+                    if self.tracking.guard_render(#render_name, self.#ident_name.rx_subscriptions).is_none() {
+                        break;
+                    }
+                }
                 // if old != self.#ident_name.current {
                 //     todo!("handle memo returns");
                 // }
@@ -205,3 +231,51 @@ impl Memo {
         }
     }
 }
+
+#[test]
+fn memo_update_guards_render_writes() {
+    let component: Component = syn::parse2(quote! {
+        fn Counter(cx: Scope) {
+            let count: Rx<i32> = 0;
+            rsx! {
+                <div>"count: {count}"</div>
+            }
+        }
+    })
+    .unwrap();
+    let update = component.memos[0].update(&component).to_string();
+
+    let guard = quote! {
+        if self.tracking.guard_render("Counter::memo_0", self.memo_0.rx_subscriptions).is_none() {
+            break;
+        }
+    };
+    assert!(update.contains(&guard.to_string()));
+    assert!(update.contains("for _ in 0 .. 2"));
+}
+
+#[test]
+fn memo_setup_is_captured_by_the_closure() {
+    let component: Component = syn::parse2(quote! {
+        fn Field(cx: Scope) {
+            let invalid: Rx<bool> = false;
+            rsx! {
+                <input autofocus_when={invalid}/>
+            }
+        }
+    })
+    .unwrap();
+    let memo = component
+        .memos
+        .iter()
+        .find(|memo| memo.setup.to_string().contains("AutoFocus"))
+        .unwrap();
+    let construct = memo.construct(&component);
+    let construct = quote!(#construct).to_string();
+
+    // the setup runs once, before the closure that moves it in is created
+    let setup = construct.find("AutoFocus :: default ()").unwrap();
+    let closure = construct.find("Box :: new (move").unwrap();
+    assert!(setup < closure);
+    assert!(construct[closure..].contains(". rose ("));
+}
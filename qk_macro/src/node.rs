@@ -34,6 +34,26 @@ impl DynamicNode {
         }
     }
 
+    /// The field of the component that keeps the element ref from `ref={state}` alive, and its initializer
+    pub fn element_ref(&self) -> Option<(TokenStream, TokenStream)> {
+        let DynamicNodeType::Element(DynElement {
+            node_ref: Some(state),
+            ..
+        }) = &self.node
+        else {
+            return None;
+        };
+        let id = self.ident();
+        let name = Ident::new(
+            &format!("__ref_{}_{}", self.root_id, self.id),
+            proc_macro2::Span::call_site(),
+        );
+        Some((
+            quote! { #name: qk::component::ElementRef },
+            quote! { #name: qk::component::ElementRef::new(#state, #id) },
+        ))
+    }
+
     pub fn complete_listeners(&mut self, states: &Vec<State>) {
         if let DynamicNodeType::Element(element) = &mut self.node {
             for listener in &mut element.listeners {
@@ -48,6 +68,17 @@ impl DynamicNode {
         }
     }
 
+    /// The states the listeners of the element write or read
+    pub fn listened_states(&self) -> impl Iterator<Item = usize> + '_ {
+        let listeners = match &self.node {
+            DynamicNodeType::Element(element) => &element.listeners[..],
+            _ => &[],
+        };
+        listeners
+            .iter()
+            .flat_map(|listener| listener.states_used.iter().copied())
+    }
+
     pub fn listeners(&self, states: &[State], ty: &Ident) -> Option<Expr> {
         let id = self.ident();
         match &self.node {
@@ -70,36 +101,46 @@ impl DynamicNode {
                     } = &listener.value;
                     let inputs=inputs.iter();
 
-                    key.strip_prefix("on").map(|event| {
+                    // events get a listener, synced attributes an observer and bound scroll positions a scroll observer, all call the closure with the component borrowed
+                    let (register, target) = if let Some(event) = key.strip_prefix("on") {
                         let as_ident = Ident::new(event, proc_macro2::Span::call_site());
-                        let rw_tracks = listener
-                            .states_used
-                            .iter()
-                            .map(|id| {
-                                let state=&states[*id];
-                                let state_name = &state.name;
-                                state.construct_tracked(parse_quote!(#state_name))
-                            });
-                        let rw_names=listener.states_used.iter().map(|id| {
+                        (quote!(add_listener), Some(quote!(qk::events::#as_ident)))
+                    } else if let Some(attribute) = key.strip_prefix("sync:") {
+                        (quote!(observe_attribute), Some(quote!(#attribute)))
+                    } else if key == "bind:scroll_top" {
+                        (quote!(observe_scroll), None)
+                    } else {
+                        return None;
+                    };
+                    let target = target.into_iter();
+
+                    let rw_tracks = listener
+                        .states_used
+                        .iter()
+                        .map(|id| {
                             let state=&states[*id];
-                            &state.name
+                            let state_name = &state.name;
+                            state.construct_tracked(parse_quote!(#state_name))
                         });
-
-                        let update_maybe_writes = listener.states_used.iter().map(|id| states[*id].update_fn());
-
-                        quote! {
-                            ui.add_listener(#id, qk::events::#as_ident, Box::new({
-                                let comp = comp.clone();
-                                #(#attrs)* move #asyncness #capture #or1_token #(#inputs,)* #or2_token #output {
-                                    let mut comp = comp.borrow_mut();
-                                    let #ty{#(#rw_names,)* tracking, ui, ..} = &mut *comp;
-                                    #(#rw_tracks)*
-                                    #body;
-                                    #(comp.#update_maybe_writes();)*
-                                    comp.ui.flush();
-                                }
-                            }));
-                        }
+                    let rw_names=listener.states_used.iter().map(|id| {
+                        let state=&states[*id];
+                        &state.name
+                    });
+
+                    let update_maybe_writes = listener.states_used.iter().map(|id| states[*id].update_fn());
+
+                    Some(quote! {
+                        ui.#register(#id, #(#target,)* Box::new({
+                            let comp = comp.clone();
+                            #(#attrs)* move #asyncness #capture #or1_token #(#inputs,)* #or2_token #output {
+                                let mut comp = comp.borrow_mut();
+                                let #ty{#(#rw_names,)* tracking, ui, ..} = &mut *comp;
+                                #(#rw_tracks)*
+                                #body;
+                                #(comp.#update_maybe_writes();)*
+                                comp.ui.flush();
+                            }
+                        }));
                     })
                 });
 
@@ -113,6 +154,25 @@ impl DynamicNode {
         }
     }
 
+    /// Values the update keeps between runs, declared once before the update closure is created
+    pub fn setup(&self) -> TokenStream {
+        let id = self.ident();
+        match &self.node {
+            DynamicNodeType::Element(element) => element
+                .attributes
+                .iter()
+                .filter(|attribute| attribute.kind == AttributeKind::Focus)
+                .map(|_| {
+                    let focus = focus_ident(&id);
+                    quote! {
+                        let #focus = qk::renderer::AutoFocus::default();
+                    }
+                })
+                .collect(),
+            _ => TokenStream::new(),
+        }
+    }
+
     pub fn update(&self) -> Option<Expr> {
         let id = self.ident();
         match &self.node {
@@ -121,13 +181,10 @@ impl DynamicNode {
                     return None;
                 }
 
-                let attributes = element.attributes.iter().map(|attribute| {
-                    let key = &attribute.key;
-                    let value = &attribute.value;
-                    quote! {
-                        ui.set_attribute(#id, #key, &#value);
-                    }
-                });
+                let attributes = element
+                    .attributes
+                    .iter()
+                    .map(|attribute| attribute.update(&id));
 
                 Some(parse_quote! {
                     {
@@ -143,6 +200,8 @@ impl DynamicNode {
                     }
                 })
             }
+            // the node is built once and then left to the closure that built it
+            DynamicNodeType::Raw(_) => None,
             DynamicNodeType::Fragment(_) => {
                 todo!()
             }
@@ -154,6 +213,8 @@ impl DynamicNode {
 pub enum DynamicNodeType {
     Element(DynElement),
     Text(DynText),
+    /// A node built by a closure that takes the renderer: `{|ui| build(ui)}`, or the element of a named slot: `{slot!(slots, header)}`
+    Raw(DynRaw),
     Fragment(DynFragment),
 }
 
@@ -168,6 +229,8 @@ pub struct DynElement {
     pub attributes: Vec<DynamicAttribute>,
     pub listeners: Vec<Listener>,
     pub children: Vec<DynamicNode>,
+    /// The state from `ref={state}` that the id of the element is written to
+    pub node_ref: Option<Expr>,
 }
 
 #[derive(Debug)]
@@ -181,6 +244,76 @@ pub struct Listener {
 pub struct DynamicAttribute {
     pub key: String,
     pub value: Expr,
+    pub kind: AttributeKind,
+}
+
+impl DynamicAttribute {
+    fn update(&self, id: &Ident) -> TokenStream {
+        let key = &self.key;
+        let value = &self.value;
+        match self.kind {
+            // numbers are formatted into a reused buffer instead of a new String
+            AttributeKind::Attribute => quote! {
+                qk::renderer::AttributeValue::with_attribute(&#value, |value| ui.set_attribute(#id, #key, value));
+            },
+            AttributeKind::Value => quote! {
+                qk::renderer::AttributeValue::with_attribute(&#value, |value| ui.set_value(#id, value));
+            },
+            AttributeKind::Tag => quote! {
+                qk::renderer::AttributeValue::with_attribute(&#value, |tag| ui.set_tag(#id, tag));
+            },
+            AttributeKind::Selected => quote! {
+                ui.set_selected(#id, &(#value).iter().map(|value| value.as_str()).collect::<Vec<_>>());
+            },
+            AttributeKind::Style => quote! {
+                qk::renderer::AttributeValue::with_attribute(&#value, |value| ui.set_style(#id, #key, value));
+            },
+            AttributeKind::ScrollTop => quote! {
+                ui.set_scroll_top(#id, f64::clone(&#value));
+            },
+            AttributeKind::Focus => {
+                let focus = focus_ident(id);
+                quote! {
+                    if #focus.rose(bool::clone(&#value)) {
+                        ui.focus(#id);
+                    }
+                }
+            }
+            AttributeKind::Leave => quote! {
+                ui.set_leave_transition(#id, qk::renderer::Leave::from(#value));
+            },
+            AttributeKind::Server => quote! {
+                qk::renderer::AttributeValue::with_attribute(&#value, |value| ui.set_server_attribute(#id, #key, value));
+            },
+        }
+    }
+}
+
+/// The `qk::renderer::AutoFocus` that remembers the last value of the element's `autofocus_when` signal
+fn focus_ident(id: &Ident) -> Ident {
+    Ident::new(&format!("{id}_focus"), id.span())
+}
+
+/// How a dynamic attribute is applied to the element
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttributeKind {
+    Attribute,
+    /// The value property of an input, textarea or select
+    Value,
+    /// The selected options of a select with the multiple attribute
+    Selected,
+    /// The tag of an element written as `<{tag}>...</>`
+    Tag,
+    /// A style property written as `style:name`
+    Style,
+    /// The scroll position of an element bound with `bind:scroll_top`
+    ScrollTop,
+    /// An attribute written as `ssr:name` that is only rendered on the server
+    Server,
+    /// The exit animation of an element written as `transition:leave`
+    Leave,
+    /// Focus the element whenever the signal bound with `autofocus_when` becomes true
+    Focus,
 }
 
 #[derive(Debug)]
@@ -188,6 +321,12 @@ pub struct DynText {
     pub text: FormattedText,
 }
 
+#[derive(Debug)]
+pub struct DynRaw {
+    /// Evaluates to a closure that takes the renderer and returns the node
+    pub build: Expr,
+}
+
 #[derive(Debug)]
 pub struct DynFragment {
     pub children: NodeValueExpr,
@@ -316,15 +455,42 @@ pub fn update_dyn_nodes(roots: &[Root]) -> proc_macro2::TokenStream {
             .unwrap();
 
         quote! {
-            ui.clone_node(unsafe{tmpl.get_unchecked(#i).load(std::sync::atomic::Ordering::Relaxed)}, #root_name);
+            #root_name = ui.instantiate_template(qk::renderer::TemplateId(unsafe{tmpl.get_unchecked(#i).load(std::sync::atomic::Ordering::Relaxed)}));
         }
     });
 
+    // the roots get their ids when the template is instantiated
+    let inner_ids = roots.iter().map(|root| {
+        root.dynamic_nodes
+            .iter()
+            .filter(|node| !node.path.is_empty())
+            .map(|node| node.ident())
+            .collect::<Vec<_>>()
+    });
+
+    // built nodes replace their placeholder once the tree is traversed, and take over its id so they are removed with the rest of the tree
+    let build_raw = roots.iter().flat_map(|root| {
+        root.dynamic_nodes.iter().filter_map(|node| {
+            let DynamicNodeType::Raw(DynRaw { build }) = &node.node else {
+                return None;
+            };
+            let id = node.ident();
+            Some(quote! {
+                {
+                    let built: u32 = (#build)(&mut *ui);
+                    ui.replace_with(#id, built);
+                    ui.return_node(#id);
+                    #id = built;
+                }
+            })
+        })
+    });
+
     quote! {
         // initialize all the variables
         #(
             #(
-                #ids = ui.node();
+                #inner_ids = ui.node();
             )*
         )*
 
@@ -334,5 +500,7 @@ pub fn update_dyn_nodes(roots: &[Root]) -> proc_macro2::TokenStream {
 
         // traverse the tree
         #(#traverse_roots)*
+
+        #(#build_raw)*
     }
 }
@@ -1,4 +1,4 @@
-use syn::{parse::Parse, Ident, Pat, PatType, Type};
+use syn::{parse::Parse, Ident, Pat, PatType, Path, Token, Type};
 
 #[derive(Debug)]
 pub struct Prop {
@@ -7,16 +7,42 @@ pub struct Prop {
     pub(crate) options: Vec<PropOption>,
 }
 
-impl From<PatType> for Prop {
-    fn from(arg: PatType) -> Self {
-        Self {
+impl Prop {
+    /// The validators that should run when the prop is passed to the component
+    pub fn validators(&self) -> impl Iterator<Item = &Path> {
+        self.options.iter().filter_map(|option| match option {
+            PropOption::Validate(path) => Some(path),
+            PropOption::Static => None,
+        })
+    }
+
+    /// Static props are set once when the component is created and never take a dirty tracking slot
+    pub fn is_static(&self) -> bool {
+        self.options
+            .iter()
+            .any(|option| matches!(option, PropOption::Static))
+    }
+}
+
+impl TryFrom<PatType> for Prop {
+    type Error = syn::Error;
+
+    fn try_from(arg: PatType) -> syn::Result<Self> {
+        let mut options = Vec::new();
+        for attr in &arg.attrs {
+            if attr.path.is_ident("prop") {
+                options.push(attr.parse_args()?);
+            }
+        }
+
+        Ok(Self {
             name: match *arg.pat {
                 Pat::Ident(ref pat) => pat.ident.clone(),
                 _ => todo!(),
             },
             ty: *arg.ty,
-            options: Default::default(),
-        }
+            options,
+        })
     }
 }
 
@@ -24,12 +50,33 @@ impl Parse for Prop {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let arg: syn::FnArg = input.parse()?;
         if let syn::FnArg::Typed(arg) = arg {
-            Ok(arg.into())
+            arg.try_into()
         } else {
             Err(syn::Error::new_spanned(arg, "expected typed argument"))
         }
     }
 }
 
+/// An option passed to a prop with `#[prop(...)]`
 #[derive(Debug)]
-pub enum PropOption {}
+pub enum PropOption {
+    /// `#[prop(validate = path::to::fn)]` where the function takes `&T` and returns `Result<(), impl Display>`
+    Validate(Path),
+    /// `#[prop(static)]` marks a prop that never changes after the component is created
+    Static,
+}
+
+impl Parse for PropOption {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if input.parse::<Option<Token![static]>>()?.is_some() {
+            return Ok(PropOption::Static);
+        }
+        let key: Ident = input.parse()?;
+        if key == "validate" {
+            input.parse::<Token![=]>()?;
+            Ok(PropOption::Validate(input.parse()?))
+        } else {
+            Err(syn::Error::new_spanned(key, "unknown prop option"))
+        }
+    }
+}
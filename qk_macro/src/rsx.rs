@@ -5,15 +5,15 @@ use crate::{
     component_visitor::ComponentBuilder,
     format::{FormattedSegment, FormattedText, Segment},
     node::{
-        self, update_dyn_nodes, DynElement, DynText, DynamicAttribute, DynamicNode, Listener,
-        TraverseOperation,
+        self, update_dyn_nodes, AttributeKind, DynElement, DynRaw, DynText, DynamicAttribute,
+        DynamicNode, Listener, TraverseOperation,
     },
 };
-use proc_macro2::{Ident, TokenStream};
+use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, TokenStream, TokenTree};
 use quote::{quote, ToTokens};
 use slotmap::{DefaultKey, Key, SlotMap};
 use syn::{parse::Parse, parse_quote, Expr, ExprLit, Lit, Stmt};
-use syn_rsx::{Node, NodeAttribute, NodeElement, NodeText, ParserConfig};
+use syn_rsx::{Node, NodeAttribute, NodeElement, NodeName, NodeText, ParserConfig};
 
 #[derive(Debug)]
 pub struct Elements {
@@ -22,16 +22,100 @@ pub struct Elements {
     creation: proc_macro2::TokenStream,
     current_path: Vec<TraverseOperation>,
     memo_ids: Vec<usize>,
+    preserve_whitespace: bool,
+    namespace: Option<&'static str>,
 }
 
+const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+
 impl Parse for Elements {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let elements = syn_rsx::Parser::new(ParserConfig::default()).parse(input)?;
+        let tokens = attribute_transforms(close_tag_names(input.parse()?));
+        let elements = syn_rsx::parse2_with_config(tokens, ParserConfig::default())?;
 
         Ok(Elements::new(&elements))
     }
 }
 
+/// Rewrite attribute values written as `{count => count.to_string()}` into the closure `{|count| count.to_string()}` so they parse as rust
+fn attribute_transforms(tokens: TokenStream) -> TokenStream {
+    let mut after_eq = false;
+    tokens
+        .into_iter()
+        .map(|token| {
+            let is_attribute_value = after_eq;
+            after_eq = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '=');
+            match token {
+                TokenTree::Group(group)
+                    if is_attribute_value && group.delimiter() == Delimiter::Brace =>
+                {
+                    let inner: Vec<_> = group.stream().into_iter().collect();
+                    match inner.as_slice() {
+                        [TokenTree::Ident(signal), TokenTree::Punct(eq), TokenTree::Punct(gt), transform @ ..]
+                            if eq.as_char() == '='
+                                && eq.spacing() == Spacing::Joint
+                                && gt.as_char() == '>' =>
+                        {
+                            let bar = Punct::new('|', Spacing::Alone);
+                            let closure = quote! { #bar #signal #bar #(#transform)* };
+                            let mut transformed = Group::new(Delimiter::Brace, closure);
+                            transformed.set_span(group.span());
+                            TokenTree::Group(transformed)
+                        }
+                        _ => TokenTree::Group(group),
+                    }
+                }
+                token => token,
+            }
+        })
+        .collect()
+}
+
+/// Fill in the name of `</>` close tags from the element they close, so an element with a dynamic tag like `<{tag}>...</>` doesn't have to repeat the tag expression
+fn close_tag_names(tokens: TokenStream) -> TokenStream {
+    fn is_punct(token: Option<&TokenTree>, char: char) -> bool {
+        matches!(token, Some(TokenTree::Punct(punct)) if punct.as_char() == char)
+    }
+
+    let tokens: Vec<_> = tokens.into_iter().collect();
+    let mut output = Vec::with_capacity(tokens.len());
+    // the names of the open elements, None for elements named with an identifier
+    let mut open: Vec<Option<Group>> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        output.push(token.clone());
+        i += 1;
+        if !is_punct(Some(token), '<') {
+            continue;
+        }
+        if is_punct(tokens.get(i), '/') {
+            if let Some(Some(name)) = open.pop() {
+                if is_punct(tokens.get(i + 1), '>') {
+                    output.push(tokens[i].clone());
+                    output.push(TokenTree::Group(name));
+                    i += 1;
+                }
+            }
+            continue;
+        }
+        // the open tag ends at the first `>`, and is self closing if it ends with `/>`
+        let self_closing = tokens[i..]
+            .iter()
+            .position(|token| is_punct(Some(token), '>'))
+            .is_some_and(|end| is_punct(tokens.get(i + end - 1), '/'));
+        if !self_closing {
+            open.push(match tokens.get(i) {
+                Some(TokenTree::Group(name)) if name.delimiter() == Delimiter::Brace => {
+                    Some(name.clone())
+                }
+                _ => None,
+            });
+        }
+    }
+    output.into_iter().collect()
+}
+
 impl ToTokens for Elements {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let get_template_fn = self.get_template_fn();
@@ -52,11 +136,16 @@ impl Elements {
             roots: Default::default(),
             current_path: Default::default(),
             memo_ids: Default::default(),
+            preserve_whitespace: false,
+            namespace: None,
         };
 
-        for (idx, element) in elements.iter().enumerate() {
+        for element in elements {
+            if myself.is_insignificant_whitespace(element) {
+                continue;
+            }
             let mut root = Root {
-                idx,
+                idx: myself.roots.len(),
                 dynamic_nodes: Default::default(),
                 root_name: None,
             };
@@ -84,6 +173,7 @@ impl Elements {
                         Some(parse_quote! { move }),
                         vec![(parse_quote!(ui), parse_quote!(&mut R))],
                     );
+                    builder.memos[memo_id].setup = dyn_node.setup();
                     self.memo_ids.push(memo_id);
                 }
             }
@@ -131,7 +221,7 @@ impl Elements {
                     // Safety: Checked index at compile time
                     unsafe {
                         #(
-                            TEMPLATE.get_unchecked(#tuple_idxs).store(#return_roots, std::sync::atomic::Ordering::Relaxed);
+                            TEMPLATE.get_unchecked(#tuple_idxs).store(ui.create_template(|_| #return_roots).0, std::sync::atomic::Ordering::Relaxed);
                         )*
                     }
                 }
@@ -141,7 +231,27 @@ impl Elements {
         }
     }
 
+    /// Text that only contains whitespace is removed unless it is inside of a `pre`, a `code` or an element with the `preserve_whitespace` attribute. Other text is always kept verbatim
+    fn is_insignificant_whitespace(&self, node: &Node) -> bool {
+        if self.preserve_whitespace {
+            return false;
+        }
+        match node {
+            Node::Text(text) => matches!(
+                text.value.as_ref(),
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(lit_str),
+                    ..
+                }) if lit_str.value().trim().is_empty()
+            ),
+            _ => false,
+        }
+    }
+
     fn build_node(&mut self, root: &mut Root, node: &Node, force_dyn: bool) -> Vec<QkNode> {
+        if self.is_insignificant_whitespace(node) {
+            return Vec::new();
+        }
         match node {
             Node::Element(el) => vec![QkNode::Static(self.build_element(root, el, force_dyn))],
             Node::Attribute(_) => todo!(),
@@ -150,7 +260,10 @@ impl Elements {
             }
             Node::Comment(_) => todo!(),
             Node::Doctype(_) => todo!(),
-            Node::Block(_) => todo!(),
+            Node::Block(block) => match raw_builder(block.value.as_ref()) {
+                Some(build) => vec![QkNode::Static(self.build_raw(root, build))],
+                None => vec![QkNode::Static(self.build_expr(root, block.value.as_ref()))],
+            },
             Node::Fragment(_) => todo!(),
         }
     }
@@ -167,18 +280,74 @@ impl Elements {
             children,
         } = element;
 
-        let name = name.to_string();
+        // Elements with a dynamic tag are created as a div in the template and switched to their tag on the first update
+        let tag = match name {
+            NodeName::Block(tag) => Some(tag),
+            _ => None,
+        };
+        let name = match tag {
+            Some(_) => "div".to_string(),
+            None => name.to_string(),
+        };
 
         let id = self.slots.insert(());
         let ident = node_ident(id);
 
-        self.creation.extend(quote! {
-            let #ident = ui.node();
-            ui.create_element(#ident, #name);
-        });
+        // Elements inside of an svg are created in the svg namespace until a foreignObject switches back to html
+        let namespace = if name == "svg" {
+            Some(SVG_NAMESPACE)
+        } else {
+            self.namespace
+        };
+        let children_namespace = if name == "foreignObject" {
+            None
+        } else {
+            namespace
+        };
 
-        let mut dyn_attributes = Vec::new();
+        match namespace {
+            Some(namespace) => self.creation.extend(quote! {
+                let #ident = ui.node();
+                ui.create_element_ns(#ident, #name, #namespace);
+            }),
+            None => self.creation.extend(quote! {
+                let #ident = ui.node();
+                ui.create_element(#ident, #name);
+            }),
+        }
+
+        let mut dyn_attributes: Vec<_> = tag
+            .map(|tag| DynamicAttribute {
+                key: "tag".to_string(),
+                value: tag.clone(),
+                kind: AttributeKind::Tag,
+            })
+            .into_iter()
+            .collect();
+        let mut static_attributes = Vec::new();
         let mut listeners = Vec::new();
+        let mut node_ref = None;
+        // whitespace is part of the content of preformatted text
+        let mut preserve_whitespace =
+            self.preserve_whitespace || matches!(name.as_str(), "pre" | "code");
+
+        let is_form_control = matches!(name.as_str(), "input" | "textarea" | "select");
+        let multiple = attributes.iter().any(|attr| {
+            matches!(attr, Node::Attribute(NodeAttribute { key, .. }) if key.to_string() == "multiple")
+        });
+        // Inputs that only accept numbers bind to a number instead of a string
+        let numeric = name == "input"
+            && attributes.iter().any(|attr| match attr {
+                Node::Attribute(NodeAttribute {
+                    key,
+                    value: Some(value),
+                }) if key.to_string() == "type" => matches!(
+                    value.as_ref(),
+                    Expr::Lit(ExprLit { lit: Lit::Str(lit_str), .. })
+                        if matches!(lit_str.value().as_str(), "number" | "range")
+                ),
+                _ => false,
+            });
 
         for attr in attributes {
             let Node::Attribute(attr) = attr else {
@@ -188,9 +357,148 @@ impl Elements {
             let NodeAttribute { key, value } = attr;
 
             let key = key.to_string();
-            let value = value.as_ref().unwrap().as_ref();
 
-            if key.starts_with("on") {
+            if key == "preserve_whitespace" {
+                preserve_whitespace = true;
+                continue;
+            }
+            let Some(value) = value.as_ref() else {
+                // boolean attributes like `multiple` or `disabled`
+                static_attributes.push(quote! { (#key, "") });
+                continue;
+            };
+            let value = value.as_ref();
+
+            if key == "ref" {
+                node_ref = Some(value.clone());
+                continue;
+            }
+
+            if key == "bind:value" {
+                // two way binding between the value of the element and a state
+                let (kind, event, write) = match (name.as_str(), multiple) {
+                    ("select", true) => (
+                        AttributeKind::Selected,
+                        "onchange",
+                        quote!(*#value = qk::web::event_selected(&event);),
+                    ),
+                    ("select", false) => (
+                        AttributeKind::Value,
+                        "onchange",
+                        quote!(*#value = qk::web::event_value(&event);),
+                    ),
+                    _ if numeric => (
+                        AttributeKind::Value,
+                        "oninput",
+                        quote!(qk::web::update_number(&qk::web::event_value(&event), &mut #value);),
+                    ),
+                    _ => (
+                        AttributeKind::Value,
+                        "oninput",
+                        quote!(*#value = qk::web::event_value(&event);),
+                    ),
+                };
+                listeners.push(Listener {
+                    key: event.to_string(),
+                    value: parse_quote!(|event| {
+                        #write
+                    }),
+                    states_used: Default::default(),
+                });
+                dyn_attributes.push(DynamicAttribute {
+                    key: "value".to_string(),
+                    value: value.clone(),
+                    kind,
+                });
+                continue;
+            }
+
+            if key == "bind:scroll_top" {
+                // the element is scrolled to the state, and scrolling writes the position back
+                // only positions that differ from the state are written, so scrolling to the state doesn't loop back
+                listeners.push(Listener {
+                    key: key.clone(),
+                    value: parse_quote!(|scrolled: f64| {
+                        if *#value != scrolled {
+                            *#value = scrolled;
+                        }
+                    }),
+                    states_used: Default::default(),
+                });
+                dyn_attributes.push(DynamicAttribute {
+                    key: "scroll_top".to_string(),
+                    value: value.clone(),
+                    kind: AttributeKind::ScrollTop,
+                });
+                continue;
+            }
+
+            if let Some(attribute) = key.strip_prefix("sync:") {
+                // the attribute is written from the state, and changes made outside of qk are written back
+                // only values that differ from the state are written, so our own writes don't loop back
+                listeners.push(Listener {
+                    key: key.clone(),
+                    value: parse_quote!(|synced: String| {
+                        if *#value != synced {
+                            *#value = synced;
+                        }
+                    }),
+                    states_used: Default::default(),
+                });
+                dyn_attributes.push(DynamicAttribute {
+                    key: attribute.to_string(),
+                    value: value.clone(),
+                    kind: AttributeKind::Attribute,
+                });
+                continue;
+            }
+
+            if key == "autofocus_when" {
+                dyn_attributes.push(DynamicAttribute {
+                    key,
+                    value: value.clone(),
+                    kind: AttributeKind::Focus,
+                });
+                continue;
+            }
+
+            if key == "transition:leave" {
+                // a class like "fade-out" or a number of milliseconds to wait before the element is removed
+                dyn_attributes.push(DynamicAttribute {
+                    key,
+                    value: value.clone(),
+                    kind: AttributeKind::Leave,
+                });
+                continue;
+            }
+
+            if let Some(attribute) = key.strip_prefix("ssr:") {
+                // set on every render so the server writes it, but never part of the template the client clones
+                dyn_attributes.push(DynamicAttribute {
+                    key: attribute.to_string(),
+                    value: match value {
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Str(lit_str),
+                            ..
+                        }) => {
+                            let text = FormattedText::from_str(&lit_str.value()).unwrap();
+                            parse_quote! {#text}
+                        }
+                        value => transformed_attribute(value).unwrap_or_else(|| value.clone()),
+                    },
+                    kind: AttributeKind::Server,
+                });
+                continue;
+            }
+
+            // `style:name` binds a single style property
+            let (key, kind) = match key.strip_prefix("style:") {
+                Some(property) => (property.to_string(), AttributeKind::Style),
+                None if is_form_control && key == "value" => (key, AttributeKind::Value),
+                None => (key, AttributeKind::Attribute),
+            };
+
+            if kind != AttributeKind::Style && key.starts_with("on") {
                 listeners.push(Listener {
                     key,
                     value: parse_quote!(#value),
@@ -207,21 +515,66 @@ impl Elements {
                     dyn_attributes.push(DynamicAttribute {
                         key,
                         value: parse_quote! {#value},
+                        kind,
                     });
-                } else {
+                } else if kind == AttributeKind::Value {
+                    self.creation.extend(quote! {
+                        ui.set_value(#ident, #str_value);
+                    });
+                } else if kind == AttributeKind::Style {
                     self.creation.extend(quote! {
-                        ui.set_attribute(#ident, #key, #str_value);
+                        ui.set_style(#ident, #key, #str_value);
                     });
+                } else {
+                    static_attributes.push(quote! { (#key, #str_value) });
                 }
+            } else if let Some(value) = transformed_attribute(value) {
+                dyn_attributes.push(DynamicAttribute { key, value, kind });
             } else {
                 dyn_attributes.push(DynamicAttribute {
                     key,
                     value: value.clone(),
+                    kind,
                 });
             }
         }
 
-        if !dyn_attributes.is_empty() || !listeners.is_empty() || force_dyn {
+        // The text of a textarea is only its initial value, so dynamic text is bound to the value instead
+        let mut children = children.as_slice();
+        if name == "textarea" {
+            if let [child] = children {
+                let expr = match child {
+                    Node::Block(block) => Some(block.value.as_ref().clone()),
+                    Node::Text(text) => match text.value.as_ref() {
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Str(lit_str),
+                            ..
+                        }) => {
+                            let text = FormattedText::from_str(&lit_str.value()).unwrap();
+                            text.is_dynamic().then(|| parse_quote!(#text))
+                        }
+                        expr => Some(expr.clone()),
+                    },
+                    _ => None,
+                };
+                if let Some(expr) = expr {
+                    dyn_attributes.push(DynamicAttribute {
+                        key: "value".to_string(),
+                        value: parse_quote!(format!("{}", #expr)),
+                        kind: AttributeKind::Value,
+                    });
+                    children = &[];
+                }
+            }
+        }
+
+        if !static_attributes.is_empty() {
+            self.creation.extend(quote! {
+                ui.set_attributes(#ident, &[#(#static_attributes,)*]);
+            });
+        }
+
+        if !dyn_attributes.is_empty() || !listeners.is_empty() || node_ref.is_some() || force_dyn {
             let id = root.dynamic_nodes.len();
             root.dynamic_nodes.push(DynamicNode {
                 root_id: root.idx,
@@ -231,16 +584,24 @@ impl Elements {
                     attributes: dyn_attributes,
                     listeners,
                     children: Default::default(),
+                    node_ref,
                 }),
             });
         }
 
         let prev_path = self.current_path.clone();
+        let prev_preserve_whitespace = self.preserve_whitespace;
+        let prev_namespace = self.namespace;
 
         self.current_path.push(TraverseOperation::FirstChild);
+        self.preserve_whitespace = preserve_whitespace;
+        self.namespace = children_namespace;
 
         for child in children {
             let children = self.build_node(root, child, false);
+            if children.is_empty() {
+                continue;
+            }
             for child in children {
                 self.creation.extend(child.append_children(&ident));
             }
@@ -248,6 +609,8 @@ impl Elements {
         }
 
         self.current_path = prev_path;
+        self.preserve_whitespace = prev_preserve_whitespace;
+        self.namespace = prev_namespace;
 
         id
     }
@@ -296,31 +659,62 @@ impl Elements {
                 });
             }
         } else {
-            let id = root.dynamic_nodes.len();
-            root.dynamic_nodes.push(DynamicNode {
-                root_id: root.idx,
-                id,
-                path: self.current_path.clone(),
-                node: node::DynamicNodeType::Text(DynText {
-                    text: FormattedText {
-                        source: None,
-                        segments: vec![Segment::Formatted(FormattedSegment {
-                            segment: value.clone(),
-                            format_args: String::new(),
-                        })],
-                    },
-                }),
-            });
-
-            // create a placeholder
-            self.creation.extend(quote! {
-                let #ident = ui.node();
-                ui.create_text(#ident, " ");
-            });
+            self.build_expr_text(root, ident, value);
         }
 
         id
     }
+
+    /// Create a text node that displays the value of an expression and updates when the expression changes
+    fn build_expr(&mut self, root: &mut Root, expr: &Expr) -> DefaultKey {
+        let id = self.slots.insert(());
+        let ident = node_ident(id);
+        self.build_expr_text(root, ident, expr);
+        id
+    }
+
+    /// Hold the place of a node built by a closure. The closure runs when the rsx is created and its node replaces the placeholder
+    fn build_raw(&mut self, root: &mut Root, build: Expr) -> DefaultKey {
+        let id = self.slots.insert(());
+        let ident = node_ident(id);
+        let dyn_id = root.dynamic_nodes.len();
+        root.dynamic_nodes.push(DynamicNode {
+            root_id: root.idx,
+            id: dyn_id,
+            path: self.current_path.clone(),
+            node: node::DynamicNodeType::Raw(DynRaw { build }),
+        });
+
+        self.creation.extend(quote! {
+            let #ident = ui.node();
+            ui.create_text(#ident, " ");
+        });
+        id
+    }
+
+    fn build_expr_text(&mut self, root: &mut Root, ident: Ident, expr: &Expr) {
+        let id = root.dynamic_nodes.len();
+        root.dynamic_nodes.push(DynamicNode {
+            root_id: root.idx,
+            id,
+            path: self.current_path.clone(),
+            node: node::DynamicNodeType::Text(DynText {
+                text: FormattedText {
+                    source: None,
+                    segments: vec![Segment::Formatted(FormattedSegment {
+                        segment: expr.clone(),
+                        format_args: String::new(),
+                    })],
+                },
+            }),
+        });
+
+        // create a placeholder
+        self.creation.extend(quote! {
+            let #ident = ui.node();
+            ui.create_text(#ident, " ");
+        });
+    }
 }
 
 #[derive(Debug)]
@@ -367,3 +761,518 @@ fn node_ident(id: DefaultKey) -> proc_macro2::Ident {
     let id = id.data().as_ffi();
     proc_macro2::Ident::new(&format!("__n_{id}"), proc_macro2::Span::call_site())
 }
+
+/// A child written as `{|ui| build(ui)}` that builds its node with the renderer directly, or `{slot!(slots, name)}` which expands to such a closure
+fn raw_builder(value: &Expr) -> Option<Expr> {
+    let Expr::Block(block) = value else {
+        return None;
+    };
+    match block.block.stmts.as_slice() {
+        [Stmt::Expr(Expr::Closure(closure))] if closure.inputs.len() == 1 => {
+            Some(Expr::Closure(closure.clone()))
+        }
+        [Stmt::Expr(Expr::Macro(slot))] if slot.mac.path.is_ident("slot") => {
+            Some(Expr::Macro(slot.clone()))
+        }
+        [Stmt::Item(syn::Item::Macro(slot))] if slot.mac.path.is_ident("slot") => {
+            let mac = &slot.mac;
+            Some(parse_quote!(#mac))
+        }
+        _ => None,
+    }
+}
+
+/// An attribute bound to a signal through a transform: `{|count| count.to_string()}`, or computed from any number of signals: `{move || format!("{x},{y}")}`. The closure is inlined into the attribute's memo, so it subscribes to every signal it names, including ones only read in some branches, and can only capture what the memo can
+fn transformed_attribute(value: &Expr) -> Option<Expr> {
+    let Expr::Block(block) = value else {
+        return None;
+    };
+    let [Stmt::Expr(Expr::Closure(closure))] = block.block.stmts.as_slice() else {
+        return None;
+    };
+    let transform = &closure.body;
+    match closure.inputs.iter().collect::<Vec<_>>().as_slice() {
+        [] => Some(parse_quote! {
+            {
+                #transform
+            }
+        }),
+        [syn::Pat::Ident(signal)] => {
+            let signal = &signal.ident;
+            Some(parse_quote! {
+                {
+                    let #signal = &*#signal;
+                    #transform
+                }
+            })
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn whitespace_between_elements_is_removed() {
+    let elements: Elements = syn::parse2(quote! {
+        "   "
+        <div>
+            "\n    "
+            <span>" hello  world "</span>
+            "  "
+            <pre preserve_whitespace>"    "</pre>
+        </div>
+        "\n"
+    })
+    .unwrap();
+    let creation = elements.creation.to_string();
+
+    assert_eq!(elements.roots.len(), 1);
+    // the text in the span and the text in the pre
+    assert_eq!(creation.matches("create_text").count(), 2);
+    assert!(creation.contains("\" hello  world \""));
+    assert!(creation.contains("\"    \""));
+    assert!(!creation.contains("preserve_whitespace"));
+}
+
+#[test]
+fn preformatted_text_keeps_whitespace() {
+    let elements: Elements = syn::parse2(quote! {
+        <div>
+            <pre>"let x = 1;\n"<code>"    "</code>"\nx"</pre>
+            "  "
+        </div>
+    })
+    .unwrap();
+    let creation = elements.creation.to_string();
+
+    assert_eq!(creation.matches("create_text").count(), 3);
+    assert!(creation.contains(r#""let x = 1;\n""#));
+    assert!(creation.contains(r#""    ""#));
+    assert!(creation.contains(r#""\nx""#));
+}
+
+#[test]
+fn svg_namespace() {
+    let elements: Elements = syn::parse2(quote! {
+        <svg>
+            <circle></circle>
+            <foreignObject>
+                <div></div>
+            </foreignObject>
+        </svg>
+    })
+    .unwrap();
+    let creation = elements.creation.to_string();
+
+    assert!(creation.contains("\"svg\" , \"http://www.w3.org/2000/svg\""));
+    assert!(creation.contains("\"circle\" , \"http://www.w3.org/2000/svg\""));
+    assert!(creation.contains("\"foreignObject\" , \"http://www.w3.org/2000/svg\""));
+    assert!(creation.contains("\"div\""));
+    assert!(!creation.contains("\"div\" ,"));
+}
+
+#[test]
+fn expression_children() {
+    let elements: Elements = syn::parse2(quote! {
+        <span>"count: "{count}" items"</span>
+    })
+    .unwrap();
+    let creation = elements.creation.to_string();
+    let dynamic_nodes = &elements.roots[0].dynamic_nodes;
+
+    // the span and the expression
+    assert_eq!(dynamic_nodes.len(), 2);
+    assert_eq!(
+        dynamic_nodes[1].path,
+        [
+            TraverseOperation::FirstChild,
+            TraverseOperation::NextSibling
+        ]
+    );
+    // updating the expression only sets the text
+    let update = dynamic_nodes[1]
+        .update()
+        .unwrap()
+        .to_token_stream()
+        .to_string();
+    assert!(update.contains("set_text"));
+    assert!(!update.contains("create_element"));
+    // the static text is still created in the template
+    assert!(creation.contains("\"count: \""));
+    assert!(creation.contains("\" items\""));
+}
+
+#[cfg(test)]
+fn element(elements: &Elements) -> &DynElement {
+    match &elements.roots[0].dynamic_nodes[0].node {
+        node::DynamicNodeType::Element(element) => element,
+        _ => panic!("expected an element"),
+    }
+}
+
+#[test]
+fn textarea_text_is_bound_to_value() {
+    let elements: Elements = syn::parse2(quote! {
+        <textarea>{content}</textarea>
+    })
+    .unwrap();
+    let creation = elements.creation.to_string();
+    let update = elements.roots[0].dynamic_nodes[0]
+        .update()
+        .unwrap()
+        .to_token_stream()
+        .to_string();
+
+    assert!(!creation.contains("create_text"));
+    assert!(update.contains("set_value"));
+    assert!(!update.contains("set_text"));
+}
+
+#[test]
+fn select_bind_value() {
+    let elements: Elements = syn::parse2(quote! {
+        <select bind:value=choice>
+            <option value="a">"A"</option>
+            <option value="b">"B"</option>
+        </select>
+    })
+    .unwrap();
+    let creation = elements.creation.to_string();
+    let update = elements.roots[0].dynamic_nodes[0]
+        .update()
+        .unwrap()
+        .to_token_stream()
+        .to_string();
+    let element = element(&elements);
+
+    assert!(update.contains("set_value"));
+    assert_eq!(element.listeners[0].key, "onchange");
+    assert!(element.listeners[0]
+        .value
+        .to_token_stream()
+        .to_string()
+        .contains("event_value"));
+    // options keep their value attribute
+    assert!(creation.contains("(\"value\" , \"a\")"));
+}
+
+#[test]
+fn number_bind_value() {
+    let elements: Elements = syn::parse2(quote! {
+        <input type="number" bind:value=count/>
+    })
+    .unwrap();
+    let update = elements.roots[0].dynamic_nodes[0]
+        .update()
+        .unwrap()
+        .to_token_stream()
+        .to_string();
+    let element = element(&elements);
+
+    // the number is formatted when it is set and parsed on input
+    let set_value = quote! {
+        qk::renderer::AttributeValue::with_attribute(&count, |value| ui.set_value(__dyn_n_0_0, value));
+    };
+    assert!(update.contains(&set_value.to_string()));
+    assert_eq!(element.listeners[0].key, "oninput");
+    let update_number = quote! {
+        qk::web::update_number(&qk::web::event_value(&event), &mut count);
+    };
+    assert!(element.listeners[0]
+        .value
+        .to_token_stream()
+        .to_string()
+        .contains(&update_number.to_string()));
+}
+
+#[test]
+fn multiple_select_bind_value() {
+    let elements: Elements = syn::parse2(quote! {
+        <select multiple bind:value=choices></select>
+    })
+    .unwrap();
+    let update = elements.roots[0].dynamic_nodes[0]
+        .update()
+        .unwrap()
+        .to_token_stream()
+        .to_string();
+    let element = element(&elements);
+
+    assert!(update.contains("set_selected"));
+    assert!(element.listeners[0]
+        .value
+        .to_token_stream()
+        .to_string()
+        .contains("event_selected"));
+}
+
+#[test]
+fn attribute_transform() {
+    let elements: Elements = syn::parse2(quote! {
+        <div data-count={count => count.to_string()} title={|count| format!("{count} items")}></div>
+    })
+    .unwrap();
+    let dynamic_nodes = &elements.roots[0].dynamic_nodes;
+    let update = dynamic_nodes[0]
+        .update()
+        .unwrap()
+        .to_token_stream()
+        .to_string();
+
+    let data_count = quote! {
+        qk::renderer::AttributeValue::with_attribute(&{
+            let count = &*count;
+            count.to_string()
+        }, |value| ui.set_attribute(__dyn_n_0_0, "data-count", value));
+    };
+    assert!(update.contains(&data_count.to_string()));
+    let title = quote! {
+        let count = &*count;
+        format!("{count} items")
+    };
+    assert!(update.contains(&title.to_string()));
+}
+
+#[test]
+fn computed_style() {
+    let elements: Elements = syn::parse2(quote! {
+        <div style:transform={move || format!("translate({}px,{}px)", x, y)} style:color="red"></div>
+    })
+    .unwrap();
+    let element = element(&elements);
+    assert_eq!(element.attributes.len(), 1);
+    assert_eq!(element.attributes[0].kind, AttributeKind::Style);
+    // static properties are set when the element is created
+    let creation = elements.creation.to_string();
+    assert!(creation.contains("ui . set_style (") && creation.contains("\"color\" , \"red\""));
+
+    let update = elements.roots[0].dynamic_nodes[0]
+        .update()
+        .unwrap()
+        .to_token_stream()
+        .to_string();
+    let transform = quote! {
+        qk::renderer::AttributeValue::with_attribute(&{
+            format!("translate({}px,{}px)", x, y)
+        }, |value| ui.set_style(__dyn_n_0_0, "transform", value));
+    };
+    assert!(update.contains(&transform.to_string()));
+}
+
+#[test]
+fn raw_renderer_block() {
+    let elements: Elements = syn::parse2(quote! {
+        <div>"before"{|ui| custom_build(ui)}{count}</div>
+    })
+    .unwrap();
+    let dynamic_nodes = &elements.roots[0].dynamic_nodes;
+    assert_eq!(dynamic_nodes.len(), 3);
+    // the built node takes the place of the second child
+    assert_eq!(
+        dynamic_nodes[1].path,
+        [
+            TraverseOperation::FirstChild,
+            TraverseOperation::NextSibling
+        ]
+    );
+    assert!(dynamic_nodes[1].update().is_none());
+    assert!(dynamic_nodes[2].update().is_some());
+
+    let tokens = elements.to_token_stream().to_string();
+    let build = quote! {
+        let built: u32 = (|ui| custom_build(ui))(&mut *ui);
+        ui.replace_with(__dyn_n_0_1, built);
+        ui.return_node(__dyn_n_0_1);
+        __dyn_n_0_1 = built;
+    };
+    assert!(tokens.contains(&build.to_string()));
+    // the closure is only called after the placeholder was found
+    assert!(tokens.find("next_sibling").unwrap() < tokens.find("custom_build").unwrap());
+}
+
+#[test]
+fn slot_in_layout() {
+    let elements: Elements = syn::parse2(quote! {
+        <div>{slot!(slots, header)}<main>{slot!(slots, content)}</main></div>
+    })
+    .unwrap();
+    let tokens = elements.to_token_stream().to_string();
+    for build in [
+        quote! { let built: u32 = (slot!(slots, header))(&mut *ui); },
+        quote! { let built: u32 = (slot!(slots, content))(&mut *ui); },
+    ] {
+        assert!(tokens.contains(&build.to_string()));
+    }
+}
+
+#[test]
+fn server_only_attribute() {
+    let elements: Elements = syn::parse2(quote! {
+        <script ssr:nonce={nonce} src="app.js"></script>
+    })
+    .unwrap();
+    // the attribute is never part of the template
+    let creation = elements.creation.to_string();
+    assert!(creation.contains("\"src\""));
+    assert!(!creation.contains("nonce"));
+
+    let update = elements.roots[0].dynamic_nodes[0]
+        .update()
+        .unwrap()
+        .to_token_stream()
+        .to_string();
+    assert!(update.contains("set_server_attribute"));
+    assert!(update.contains("\"nonce\""));
+}
+
+#[test]
+fn leave_transition() {
+    let elements: Elements = syn::parse2(quote! {
+        <ul><li transition:leave="fade-out">"a"</li><li transition:leave={300}>"b"</li></ul>
+    })
+    .unwrap();
+    assert!(!elements.creation.to_string().contains("transition"));
+    let updates: Vec<_> = elements.roots[0]
+        .dynamic_nodes
+        .iter()
+        .filter_map(|node| Some(node.update()?.to_token_stream().to_string()))
+        .collect();
+    assert_eq!(updates.len(), 2, "{updates:#?}");
+    assert!(updates
+        .iter()
+        .all(|update| update.contains("set_leave_transition")));
+    assert!(updates[0].contains(&quote!(qk::renderer::Leave::from("fade-out")).to_string()));
+    assert!(updates[1].contains(&quote!(qk::renderer::Leave::from({ 300 })).to_string()));
+}
+
+#[test]
+fn autofocus_when() {
+    let elements: Elements = syn::parse2(quote! {
+        <input autofocus_when={invalid}/>
+    })
+    .unwrap();
+    assert!(!elements.creation.to_string().contains("autofocus_when"));
+    let update = elements.roots[0].dynamic_nodes[0]
+        .update()
+        .unwrap()
+        .to_token_stream()
+        .to_string();
+    let setup = elements.roots[0].dynamic_nodes[0].setup().to_string();
+    assert!(setup.contains("qk :: renderer :: AutoFocus :: default ()"));
+    // the update focuses on the change to true, using the value the setup declared
+    let focus = setup.split_whitespace().nth(1).unwrap();
+    assert!(update.contains(&format!(
+        "if {focus} . rose (bool :: clone (& {{ invalid }}))"
+    )));
+    assert!(update.contains("ui . focus"));
+}
+
+#[test]
+fn dynamic_tag() {
+    let elements: Elements = syn::parse2(quote! {
+        <{tag}><span>"hi"</span></>
+    })
+    .unwrap();
+    let creation = elements.creation.to_string();
+    let update = elements.roots[0].dynamic_nodes[0]
+        .update()
+        .unwrap()
+        .to_token_stream()
+        .to_string();
+
+    // the children are created in the template and moved along when the tag changes
+    assert!(creation.contains("\"div\""));
+    assert!(creation.contains("\"span\""));
+    let set_tag = quote! {
+        qk::renderer::AttributeValue::with_attribute(&{tag}, |tag| ui.set_tag(__dyn_n_0_0, tag));
+    };
+    assert!(update.contains(&set_tag.to_string()));
+}
+
+#[test]
+fn element_ref() {
+    let elements: Elements = syn::parse2(quote! {
+        <div><input ref={input} /></div>
+    })
+    .unwrap();
+    let refs: Vec<_> = elements.roots[0]
+        .dynamic_nodes
+        .iter()
+        .filter_map(|node| node.element_ref().map(|field| (node, field)))
+        .collect();
+    let [(input, (_, init))] = refs.as_slice() else {
+        panic!("expected one element ref");
+    };
+
+    // a ref only records the id, so there is nothing to update
+    assert!(input.update().is_none());
+    let init = init.to_string();
+    assert!(init.contains("ElementRef :: new"));
+    assert!(init.contains(&quote!({ input }).to_string()));
+}
+
+#[test]
+fn repeated_listeners_are_kept_in_order() {
+    let elements: Elements = syn::parse2(quote! {
+        <input bind:value=text oninput=|_| *changed += 1 />
+    })
+    .unwrap();
+    let element = element(&elements);
+
+    let keys: Vec<_> = element
+        .listeners
+        .iter()
+        .map(|listener| &listener.key)
+        .collect();
+    assert_eq!(keys, ["oninput", "oninput"]);
+    assert!(element.listeners[1]
+        .value
+        .to_token_stream()
+        .to_string()
+        .contains("changed"));
+}
+
+#[test]
+fn bind_scroll_top() {
+    let elements: Elements = syn::parse2(quote! {
+        <div bind:scroll_top=offset></div>
+    })
+    .unwrap();
+    let node = &elements.roots[0].dynamic_nodes[0];
+    let element = element(&elements);
+    assert_eq!(element.listeners[0].key, "bind:scroll_top");
+    assert_eq!(element.attributes[0].kind, AttributeKind::ScrollTop);
+
+    let update = node.update().unwrap().to_token_stream().to_string();
+    assert!(update.contains("ui . set_scroll_top (__dyn_n_0_0 , f64 :: clone (& offset))"));
+
+    let ty = syn::Ident::new("Comp", proc_macro2::Span::call_site());
+    let listeners = node
+        .listeners(&[], &ty)
+        .unwrap()
+        .to_token_stream()
+        .to_string();
+    assert!(listeners.contains("ui . observe_scroll (__dyn_n_0_0 , Box :: new"));
+    assert!(listeners.contains(&quote!(if *offset != scrolled).to_string()));
+}
+
+#[test]
+fn sync_attribute() {
+    let elements: Elements = syn::parse2(quote! {
+        <div sync:title=label></div>
+    })
+    .unwrap();
+    let node = &elements.roots[0].dynamic_nodes[0];
+    let element = element(&elements);
+    assert_eq!(element.listeners[0].key, "sync:title");
+    assert_eq!(element.attributes[0].key, "title");
+
+    let ty = syn::Ident::new("Comp", proc_macro2::Span::call_site());
+    let listeners = node
+        .listeners(&[], &ty)
+        .unwrap()
+        .to_token_stream()
+        .to_string();
+    assert!(listeners.contains("ui . observe_attribute (__dyn_n_0_0 , \"title\" ,"));
+    // writing the state back only when it changed keeps our own writes from looping
+    assert!(listeners.contains(&quote!(if *label != synced).to_string()));
+}
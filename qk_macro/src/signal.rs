@@ -0,0 +1,145 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{parse::Parse, Data, DeriveInput, Fields, GenericArgument, Member, PathArguments, Type};
+
+/// A newtype around a signal that forwards the signal traits to the field it wraps
+pub struct Signal {
+    input: DeriveInput,
+    field: Member,
+    value: Type,
+    writable: bool,
+}
+
+impl Parse for Signal {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let input: DeriveInput = input.parse()?;
+        let Data::Struct(data) = &input.data else {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "Signal can only be derived for structs",
+            ));
+        };
+        let field = match &data.fields {
+            Fields::Named(fields) if fields.named.len() == 1 => {
+                Member::Named(fields.named[0].ident.clone().unwrap())
+            }
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => Member::Unnamed(0.into()),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "Signal can only be derived for a struct with exactly one field",
+                ))
+            }
+        };
+        let ty = &data.fields.iter().next().unwrap().ty;
+
+        // Memos can only be read, so a wrapper around one only gets Readable
+        let signal = match ty {
+            Type::Path(path) => path.path.segments.last(),
+            _ => None,
+        };
+        let (writable, arguments) = match signal {
+            Some(segment) if segment.ident == "State" => (true, &segment.arguments),
+            Some(segment) if segment.ident == "Memo" => (false, &segment.arguments),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "Signal can only wrap a State<T> or a Memo<T>",
+                ))
+            }
+        };
+        let value = match arguments {
+            PathArguments::AngleBracketed(arguments) => {
+                arguments.args.iter().find_map(|argument| match argument {
+                    GenericArgument::Type(ty) => Some(ty.clone()),
+                    _ => None,
+                })
+            }
+            _ => None,
+        }
+        .ok_or_else(|| syn::Error::new_spanned(ty, "expected the type of the signal's value"))?;
+
+        Ok(Self {
+            field,
+            value,
+            writable,
+            input,
+        })
+    }
+}
+
+impl ToTokens for Signal {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Self {
+            input,
+            field,
+            value,
+            writable,
+        } = self;
+        let name = &input.ident;
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+        tokens.extend(quote! {
+            impl #impl_generics qk::copy::Readable<#value> for #name #ty_generics #where_clause {
+                fn with<U: 'static, F: FnOnce(&#value) -> U>(&self, f: F) -> U {
+                    qk::copy::Readable::with(&self.#field, f)
+                }
+
+                fn peek<U: 'static, F: FnOnce(&#value) -> U>(&self, f: F) -> U {
+                    qk::copy::Readable::peek(&self.#field, f)
+                }
+            }
+        });
+        if *writable {
+            tokens.extend(quote! {
+                impl #impl_generics qk::copy::Writable<#value> for #name #ty_generics #where_clause {
+                    fn with_mut<F: FnOnce(&mut #value) -> O, O>(&self, f: F) -> O {
+                        qk::copy::Writable::with_mut(&self.#field, f)
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[test]
+fn state_wrappers_are_read_and_written() {
+    let signal: Signal = syn::parse2(quote! {
+        struct Celsius(State<f64>);
+    })
+    .unwrap();
+    let tokens = signal.to_token_stream().to_string();
+
+    let readable = quote! {
+        impl qk::copy::Readable<f64> for Celsius
+    };
+    let writable = quote! {
+        impl qk::copy::Writable<f64> for Celsius
+    };
+    assert!(tokens.contains(&readable.to_string()));
+    assert!(tokens.contains(&writable.to_string()));
+    assert!(tokens.contains(&quote!(Readable::with(&self.0, f)).to_string()));
+}
+
+#[test]
+fn memo_wrappers_are_read_only() {
+    let signal: Signal = syn::parse2(quote! {
+        struct Total<T: 'static> {
+            sum: qk::copy::Memo<T>,
+        }
+    })
+    .unwrap();
+    let tokens = signal.to_token_stream().to_string();
+
+    let readable = quote! {
+        impl<T: 'static> qk::copy::Readable<T> for Total<T>
+    };
+    assert!(tokens.contains(&readable.to_string()));
+    assert!(tokens.contains(&quote!(Readable::peek(&self.sum, f)).to_string()));
+    assert!(!tokens.contains("Writable"));
+
+    assert!(syn::parse2::<Signal>(quote! {
+        struct Pair(State<i32>, State<i32>);
+    })
+    .is_err());
+}
@@ -0,0 +1,124 @@
+use std::cell::RefCell;
+use std::fmt::Display;
+
+use crate::copy::{Readable, Scope, State, Writable};
+use crate::prelude::{PlatformEvents, Renderer};
+
+/// A value shown as the text of several nodes. A single effect writes every target, so one write to the value updates all of them in the same flush without rerunning anything else. Create one with [`Scope::bind_text`]
+pub struct TextBinding {
+    targets: State<Vec<u32>>,
+}
+
+impl Clone for TextBinding {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl Copy for TextBinding {}
+
+impl TextBinding {
+    /// Show the value in the node `id` until the returned target is dropped. Keep the target alive as long as the node is
+    pub fn target(&self, id: u32) -> BoundTarget {
+        self.targets.with_mut(|targets| targets.push(id));
+        BoundTarget {
+            targets: self.targets,
+            id,
+        }
+    }
+
+    /// The nodes the value is currently shown in
+    pub fn targets(&self) -> Vec<u32> {
+        self.targets.cloned()
+    }
+}
+
+/// A node showing a [`TextBinding`]. Dropping it, for example with the component that holds the node, stops updating only that node
+pub struct BoundTarget {
+    targets: State<Vec<u32>>,
+    id: u32,
+}
+
+impl Drop for BoundTarget {
+    fn drop(&mut self) {
+        let id = self.id;
+        // the scope that owns the binding may have been dropped first
+        let _ = self.targets.try_with_mut(|targets| {
+            if let Some(index) = targets.iter().position(|target| *target == id) {
+                targets.remove(index);
+            }
+        });
+    }
+}
+
+impl Scope {
+    /// Show `value` in every target of the returned binding. A target is written when it is added and when the text changes
+    pub fn bind_text<T: Display + 'static, R: Renderer<R> + PlatformEvents + 'static>(
+        &self,
+        value: impl Readable<T> + 'static,
+        ui: R,
+    ) -> TextBinding {
+        let targets = self.state(Vec::new());
+        let ui = RefCell::new(ui);
+        // the text last written and the targets that hold it
+        let written: RefCell<(Option<String>, Vec<u32>)> = RefCell::new((None, Vec::new()));
+        self.effect(move || {
+            let text = value.with(|value| value.to_string());
+            let mut ui = ui.borrow_mut();
+            let (written_text, written_to) = &mut *written.borrow_mut();
+            if written_text.as_ref() != Some(&text) {
+                written_to.clear();
+            }
+            targets.with(|targets| {
+                written_to.retain(|id| targets.contains(id));
+                for &id in targets {
+                    if !written_to.contains(&id) {
+                        ui.set_text(id, &text);
+                        written_to.push(id);
+                    }
+                }
+            });
+            *written_text = Some(text);
+        });
+        TextBinding { targets }
+    }
+}
+
+#[test]
+fn one_write_updates_every_target() {
+    use crate::copy::{claim_rt, Runtime};
+    use crate::mock::{MockOp, MockRenderer};
+
+    let runtime = claim_rt();
+    let cx = crate::scope!(runtime);
+    let count = cx.state(0);
+    let mut ui = MockRenderer::default();
+    let [header, footer] = [ui.node(), ui.node()];
+    ui.create_text(header, "");
+    ui.create_text(footer, "");
+    let binding = cx.bind_text(count, ui.clone());
+    let _header = binding.target(header);
+    let footer_target = binding.target(footer);
+    Runtime::flush(runtime);
+    assert_eq!(ui.text_of(header).as_deref(), Some("0"));
+    assert_eq!(ui.text_of(footer).as_deref(), Some("0"));
+
+    ui.take_ops();
+    count.set(1);
+    Runtime::flush(runtime);
+    assert_eq!(
+        ui.take_ops(),
+        [header, footer].map(|id| MockOp::SetText {
+            id,
+            text: "1".to_string()
+        })
+    );
+
+    // removing one node only stops updating that node
+    drop(footer_target);
+    count.set(2);
+    Runtime::flush(runtime);
+    assert_eq!(binding.targets(), [header]);
+    assert_eq!(ui.text_of(header).as_deref(), Some("2"));
+    assert_eq!(ui.text_of(footer).as_deref(), Some("1"));
+}
@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+
+use crate::copy::{Readable, Scope, State, Writable};
+
+// The ring shared by both ends of a channel
+struct Ring<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+    overflowed: bool,
+}
+
+/// The sending end of a bounded channel created with [`Scope::channel`]
+pub struct Sender<T: 'static> {
+    ring: State<Ring<T>>,
+}
+
+impl<T: 'static> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: 'static> Copy for Sender<T> {}
+
+impl<T: 'static> Sender<T> {
+    /// Queue a value and notify the readers of the receiver. If the channel is full the oldest value is dropped to make room and the overflow flag is set
+    pub fn send(&self, value: T) {
+        self.ring.with_mut(|ring| {
+            if ring.items.len() == ring.capacity {
+                ring.items.pop_front();
+                ring.overflowed = true;
+            }
+            ring.items.push_back(value);
+        })
+    }
+}
+
+/// The receiving end of a bounded channel created with [`Scope::channel`]. Reading it subscribes to new values, oldest first
+pub struct ReceiverSignal<T: 'static> {
+    ring: State<Ring<T>>,
+}
+
+impl<T: 'static> Clone for ReceiverSignal<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: 'static> Copy for ReceiverSignal<T> {}
+
+impl<T: 'static> ReceiverSignal<T> {
+    /// Take every queued value and clear the overflow flag. An empty channel is not written, so an effect can drain the receiver it reads without rerunning forever
+    pub fn drain(&self) -> Vec<T> {
+        if self
+            .ring
+            .peek(|ring| ring.items.is_empty() && !ring.overflowed)
+        {
+            return Vec::new();
+        }
+        self.ring.with_mut(|ring| {
+            ring.overflowed = false;
+            ring.items.drain(..).collect()
+        })
+    }
+
+    /// Whether a value was dropped because the channel was full since it was last drained
+    pub fn overflowed(&self) -> bool {
+        self.ring.with(|ring| ring.overflowed)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.ring.peek(|ring| ring.capacity)
+    }
+}
+
+impl<T: 'static> Readable<VecDeque<T>> for ReceiverSignal<T> {
+    fn with<U: 'static, F: FnOnce(&VecDeque<T>) -> U>(&self, f: F) -> U {
+        self.ring.with(|ring| f(&ring.items))
+    }
+
+    fn peek<U: 'static, F: FnOnce(&VecDeque<T>) -> U>(&self, f: F) -> U {
+        self.ring.peek(|ring| f(&ring.items))
+    }
+}
+
+impl Scope {
+    /// Create a channel that holds at most `capacity` values. Bursts of events can be sent faster than they are handled, and only the newest `capacity` are kept
+    ///
+    /// Panics if `capacity` is 0
+    pub fn channel<T: 'static>(&self, capacity: usize) -> (Sender<T>, ReceiverSignal<T>) {
+        assert!(capacity > 0, "a channel needs room for at least one value");
+        let ring = self.state(Ring {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+            overflowed: false,
+        });
+        (Sender { ring }, ReceiverSignal { ring })
+    }
+}
+
+#[test]
+fn overflow_drops_the_oldest() {
+    use crate::copy::{claim_rt, Runtime};
+    use std::{cell::RefCell, rc::Rc};
+
+    let runtime = claim_rt();
+    let cx = crate::scope!(runtime);
+    let (keys, receiver) = cx.channel(3);
+    let handled = Rc::new(RefCell::new(Vec::new()));
+    let runs = Rc::new(RefCell::new(0));
+    cx.effect({
+        let handled = handled.clone();
+        let runs = runs.clone();
+        move || {
+            *runs.borrow_mut() += 1;
+            receiver.with(|_| ());
+            handled.borrow_mut().extend(receiver.drain());
+        }
+    });
+    Runtime::flush(runtime);
+    let runs_before = *runs.borrow();
+
+    for key in ["a", "b", "c", "d", "e"] {
+        keys.send(key);
+    }
+    assert!(receiver.overflowed());
+    assert_eq!(receiver.cloned(), ["c", "d", "e"]);
+
+    Runtime::flush(runtime);
+    assert_eq!(*handled.borrow(), ["c", "d", "e"]);
+    assert!(!receiver.overflowed());
+    // the drain reruns the effect once, which finds the channel empty
+    assert_eq!(*runs.borrow(), runs_before + 2);
+}
@@ -0,0 +1,136 @@
+use std::{cell::RefCell, collections::HashSet};
+
+use crate::copy::{Readable, Scope, State, Writable};
+use crate::prelude::{PlatformEvents, Renderer};
+
+/// A set of classes that can be changed imperatively. Create one with [`Scope::class_list`] and keep an element's class attribute in sync with [`ClassList::bind`]
+pub struct ClassList {
+    classes: State<HashSet<String>>,
+}
+
+impl Clone for ClassList {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl Copy for ClassList {}
+
+impl ClassList {
+    /// Add a class. Adding a class that is already in the list doesn't notify subscribers
+    pub fn add(&self, class: &str) {
+        if !self.classes.peek(|classes| classes.contains(class)) {
+            self.classes
+                .with_mut(|classes| classes.insert(class.to_string()));
+        }
+    }
+
+    /// Remove a class. Removing a class that isn't in the list doesn't notify subscribers
+    pub fn remove(&self, class: &str) {
+        if self.classes.peek(|classes| classes.contains(class)) {
+            self.classes.with_mut(|classes| classes.remove(class));
+        }
+    }
+
+    /// Add the class if it is missing or remove it if it is present. Returns whether the class is in the list afterwards
+    pub fn toggle(&self, class: &str) -> bool {
+        self.classes.with_mut(|classes| {
+            if classes.remove(class) {
+                false
+            } else {
+                classes.insert(class.to_string())
+            }
+        })
+    }
+
+    pub fn contains(&self, class: &str) -> bool {
+        self.classes.with(|classes| classes.contains(class))
+    }
+
+    /// The classes sorted and joined with spaces, the format of the class attribute
+    pub fn to_attribute(&self) -> String {
+        self.classes.with(|classes| {
+            let mut classes: Vec<_> = classes.iter().map(String::as_str).collect();
+            classes.sort_unstable();
+            classes.join(" ")
+        })
+    }
+
+    /// Write the classes to the class attribute of the element now and whenever the list changes. The attribute is only written when the classes it holds change
+    pub fn bind<R: Renderer<R> + PlatformEvents + 'static>(&self, cx: &Scope, ui: R, id: u32) {
+        let list = *self;
+        let ui = RefCell::new(ui);
+        let written = RefCell::new(None);
+        cx.effect(move || {
+            let class = list.to_attribute();
+            let mut written = written.borrow_mut();
+            if written.as_ref() != Some(&class) {
+                ui.borrow_mut().set_attribute(id, "class", &class);
+                *written = Some(class);
+            }
+        });
+    }
+}
+
+impl Readable<HashSet<String>> for ClassList {
+    fn with<U: 'static, F: FnOnce(&HashSet<String>) -> U>(&self, f: F) -> U {
+        self.classes.with(f)
+    }
+
+    fn peek<U: 'static, F: FnOnce(&HashSet<String>) -> U>(&self, f: F) -> U {
+        self.classes.peek(f)
+    }
+}
+
+impl Scope {
+    /// Create a class list that starts with `classes`
+    pub fn class_list<C: Into<String>>(&self, classes: impl IntoIterator<Item = C>) -> ClassList {
+        ClassList {
+            classes: self.state(classes.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+#[test]
+fn class_attribute_follows_the_list() {
+    use crate::copy::{claim_rt, Runtime};
+    use crate::mock::{MockOp, MockRenderer};
+
+    let runtime = claim_rt();
+    let cx = crate::scope!(runtime);
+    let mut ui = MockRenderer::default();
+    let button = ui.node();
+    ui.create_element(button, "button");
+    let classes = cx.class_list(["button"]);
+    classes.bind(&cx, ui.clone(), button);
+    assert_eq!(ui.attribute_of(button, "class").as_deref(), Some("button"));
+
+    classes.add("primary");
+    classes.add("active");
+    Runtime::flush(runtime);
+    assert_eq!(
+        ui.attribute_of(button, "class").as_deref(),
+        Some("active button primary")
+    );
+
+    classes.remove("active");
+    assert!(!classes.toggle("primary"));
+    assert!(classes.toggle("disabled"));
+    Runtime::flush(runtime);
+    assert!(classes.contains("disabled") && !classes.contains("primary"));
+    assert_eq!(
+        ui.attribute_of(button, "class").as_deref(),
+        Some("button disabled")
+    );
+
+    // putting back the same classes doesn't touch the element
+    ui.take_ops();
+    classes.toggle("disabled");
+    classes.add("disabled");
+    classes.remove("missing");
+    Runtime::flush(runtime);
+    assert!(!ui
+        .ops()
+        .iter()
+        .any(|op| matches!(op, MockOp::SetAttribute { .. })));
+}
@@ -1,5 +1,6 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, fmt::Display, rc::Rc};
 
+use crate::copy::{State, Writable};
 use crate::prelude::{PlatformEvents, Renderer};
 
 pub trait Component<R, P>
@@ -10,6 +11,9 @@ where
     type State: ComponentState<R, P>;
 
     fn create(self, ui: &mut R) -> Self::State;
+
+    /// Pass new props to a component that was already created. Only the props that changed are written, so only the parts of the component that read them update
+    fn update(self, state: &mut Self::State);
 }
 
 pub trait ComponentState<R, P>
@@ -17,8 +21,14 @@ where
     R: Renderer<P>,
     P: PlatformEvents,
 {
+    /// The nodes currently at the top level of the component. This may change when the component contains conditionals or loops
     fn roots(&self) -> Vec<u32>;
 
+    /// A counter that changes whenever the set of roots changes, so parents can detect structural changes without comparing [`ComponentState::roots`]
+    fn roots_version(&self) -> usize {
+        0
+    }
+
     fn remove(&self, ui: &mut R) {
         for root in self.roots() {
             ui.remove(root);
@@ -35,6 +45,10 @@ where
     fn roots(&self) -> Vec<u32> {
         self.borrow().roots()
     }
+
+    fn roots_version(&self) -> usize {
+        self.borrow().roots_version()
+    }
 }
 
 pub struct DynComponentState<R, P>
@@ -65,4 +79,141 @@ where
     fn roots(&self) -> Vec<u32> {
         self.inner.roots()
     }
+
+    fn roots_version(&self) -> usize {
+        self.inner.roots_version()
+    }
+}
+
+/// Keeps the id of an element in a state while the element exists. `ref={state}` in `rsx!` creates one, and the component holds it so the state is set back to None when the component is dropped after its element was removed
+pub struct ElementRef {
+    state: State<Option<u32>>,
+}
+
+impl ElementRef {
+    pub fn new(state: State<Option<u32>>, id: u32) -> Self {
+        state.set(Some(id));
+        Self { state }
+    }
+}
+
+impl Drop for ElementRef {
+    fn drop(&mut self) {
+        // the scope that owns the state may have been dropped first
+        let _ = self.state.try_set(None);
+    }
+}
+
+/// Report the result of a prop validator. Invalid props panic in debug builds and are logged in release builds.
+pub fn validate_prop<E: Display>(component: &str, prop: &str, result: Result<(), E>) {
+    if let Err(err) = result {
+        invalid_props(format!(
+            "invalid prop `{prop}` passed to `{component}`: {err}"
+        ));
+    }
+}
+
+/// Report the result of a validator that checks all of the props of a component together.
+pub fn validate_props<E: Display>(component: &str, result: Result<(), E>) {
+    if let Err(err) = result {
+        invalid_props(format!("invalid props passed to `{component}`: {err}"));
+    }
+}
+
+/// The old and new value of a prop, compared by the `update` generated for a component. Calling `changed` on a reference to it compares the values when their type is `PartialEq`, and otherwise counts the prop as changed
+#[doc(hidden)]
+pub struct PropChange<'a, T>(pub &'a T, pub &'a T);
+
+#[doc(hidden)]
+pub trait ComparedProp {
+    fn changed(&self) -> bool;
+}
+
+impl<T: PartialEq> ComparedProp for PropChange<'_, T> {
+    fn changed(&self) -> bool {
+        self.0 != self.1
+    }
+}
+
+// Method resolution only reaches this impl through an extra reference, so it is used when `ComparedProp` doesn't apply
+#[doc(hidden)]
+pub trait UncomparedProp {
+    fn changed(&self) -> bool;
+}
+
+impl<T> UncomparedProp for &PropChange<'_, T> {
+    fn changed(&self) -> bool {
+        true
+    }
+}
+
+fn invalid_props(message: String) {
+    if cfg!(debug_assertions) {
+        panic!("{message}");
+    } else {
+        web_sys::console::warn_1(&message.into());
+    }
+}
+
+#[test]
+#[should_panic(expected = "invalid prop `percent` passed to `Progress`: 150 is not a percentage")]
+fn invalid_prop_panics() {
+    fn percent(value: &u8) -> Result<(), String> {
+        if *value <= 100 {
+            Ok(())
+        } else {
+            Err(format!("{value} is not a percentage"))
+        }
+    }
+
+    validate_prop("Progress", "percent", percent(&50));
+    validate_prop("Progress", "percent", percent(&150));
+}
+
+#[test]
+fn props_without_partial_eq_always_change() {
+    use self::{ComparedProp as _, UncomparedProp as _};
+
+    struct Opaque;
+
+    assert!(!PropChange(&1, &1).changed());
+    assert!(PropChange(&1, &2).changed());
+    assert!((&PropChange(&Opaque, &Opaque)).changed());
+}
+
+#[test]
+fn element_ref_is_cleared_on_removal() {
+    use crate::copy::{claim_rt, Readable};
+    use crate::fragment::Fragment;
+    use crate::mock::MockRenderer;
+
+    struct Input {
+        id: u32,
+        _element: ElementRef,
+    }
+
+    impl ComponentState<MockRenderer, MockRenderer> for Input {
+        fn roots(&self) -> Vec<u32> {
+            vec![self.id]
+        }
+    }
+
+    let cx = crate::scope!(claim_rt());
+    let input = cx.state(None);
+    let mut ui = MockRenderer::default();
+    let id = ui.node();
+    ui.create_element(id, "input");
+    let mut shown: Fragment<MockRenderer, MockRenderer> = Fragment::new(Vec::new());
+    shown.update(
+        std::iter::once(DynComponentState::new(Input {
+            id,
+            _element: ElementRef::new(input, id),
+        })),
+        0,
+        &mut ui,
+    );
+    assert_eq!(input.get(), Some(id));
+
+    shown.update(std::iter::empty(), 0, &mut ui);
+    assert_eq!(input.get(), None);
 }
@@ -1,23 +1,32 @@
 use std::{
-    cell::RefCell,
+    any::{Any, TypeId},
+    cell::{Cell, RefCell},
     fmt::{Debug, Display},
     marker::PhantomData,
     ptr::NonNull,
+    rc::Rc,
 };
 
-use crate::copy_ll::{NodeData, NodeRef, Queue};
+pub use crate::copy_ll::NodeRef;
+use crate::copy_ll::{NodeData, Queue};
+use crate::error::{QkError, QkResult};
+use crate::events::PlatformEvents;
+use crate::renderer::Renderer;
 
 #[cfg(not(feature = "ssr"))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
 pub struct RuntimeId;
 
 #[cfg(feature = "ssr")]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
-pub struct RuntimeId(usize);
+slotmap::new_key_type! {
+    pub struct RuntimeId;
+}
 
 #[cfg(feature = "ssr")]
 thread_local! {
-    static RUNTIMES: RefCell<slotmap::SlotMap<RuntimeId, Runtime>> = RefCell::new(SlotMap::default());
+    static RUNTIMES: RefCell<slotmap::SlotMap<RuntimeId, Runtime>> = RefCell::new(slotmap::SlotMap::default());
+    // Runtimes that were dropped. Their queues keep the nodes they allocated so they can be reused
+    static RETIRED: RefCell<Vec<Runtime>> = const { RefCell::new(Vec::new()) };
 }
 
 #[cfg(not(feature = "ssr"))]
@@ -30,7 +39,7 @@ impl RuntimeId {
         #[cfg(feature = "ssr")]
         return RUNTIMES.with(|runtimes| {
             let mut runtimes = runtimes.borrow_mut();
-            runtimes.insert(Runtime::new())
+            runtimes.insert(Runtime::reuse_or_new())
         });
         #[cfg(not(feature = "ssr"))]
         return RuntimeId;
@@ -38,18 +47,20 @@ impl RuntimeId {
 }
 
 pub(crate) fn with_rt<O>(runtime_id: RuntimeId, f: impl FnOnce(&Runtime) -> O) -> O {
+    try_with_rt(runtime_id, f).unwrap_or_else(|err| panic!("{err}"))
+}
+
+pub(crate) fn try_with_rt<O>(runtime_id: RuntimeId, f: impl FnOnce(&Runtime) -> O) -> QkResult<O> {
     #[cfg(not(feature = "ssr"))]
     {
         let _ = runtime_id;
-        RUNTIME.with(f)
+        Ok(RUNTIME.with(f))
     }
     #[cfg(feature = "ssr")]
     RUNTIMES.with(|runtimes| {
         let runtimes = runtimes.borrow();
-        let runtime = runtimes
-            .get(runtime_id)
-            .expect("tried to get a runtime that was dropped");
-        f(runtime)
+        let runtime = runtimes.get(runtime_id).ok_or(QkError::RuntimeGone)?;
+        Ok(f(runtime))
     })
 }
 
@@ -60,30 +71,516 @@ pub fn claim_rt() -> RuntimeId {
     #[cfg(not(feature = "ssr"))]
     return RuntimeId;
     #[cfg(feature = "ssr")]
-    RUNTIMES.with(|runtimes| runtimes.borrow_mut().insert(Runtime::new()))
+    RUNTIMES.with(|runtimes| runtimes.borrow_mut().insert(Runtime::reuse_or_new()))
 }
 
 /// Removes the runtime from the thread local storage
-/// This will drop all signals and effects
+/// This will drop all signals and effects, even ones owned by scopes that are still alive. Those scopes can still be dropped, but their states can't be used
+///
+/// The runtime's allocations are kept around for the next call to [`claim_rt`]. Without the `ssr` feature there is one runtime per thread and this does nothing
 pub fn drop_rt(runtime_id: RuntimeId) {
     #[cfg(not(feature = "ssr"))]
     let _ = runtime_id;
     #[cfg(feature = "ssr")]
-    RUNTIMES.with(|runtimes| {
-        runtimes.borrow_mut().remove(runtime_id);
-    });
+    {
+        let removed = RUNTIMES.with(|runtimes| runtimes.borrow_mut().remove(runtime_id));
+        if let Some(runtime) = removed {
+            runtime.root_scopes.borrow_mut().clear();
+            runtime.effects.borrow_mut().clear();
+            runtime.memos.borrow_mut().clear();
+            runtime.renderers.borrow_mut().clear();
+            runtime.idle.borrow_mut().clear();
+            runtime.suspense.borrow_mut().clear();
+            runtime.check_effect_states.set(false);
+            runtime.stats.take();
+            runtime.client.set(false);
+            runtime.client_only.borrow_mut().clear();
+            runtime.paused.set(0);
+            runtime.next_scope_id.set(0);
+            // the runtime is out of the thread local, so drops that reach for it see it as gone
+            unsafe { runtime.states.remove_all() };
+            RETIRED.with(|retired| retired.borrow_mut().push(runtime));
+        }
+    }
 }
 
+/// Updates a memo, returning true if its value changed
+type MemoUpdate = Rc<dyn Fn() -> bool>;
+
 pub struct Runtime {
     pub(crate) states: Queue,
+    next_scope_id: Cell<usize>,
+    root_scopes: RefCell<Vec<ScopeId>>,
+    // Highest priority first, then in the order they were created
+    effects: RefCell<Vec<State<EffectData>>>,
+    memos: RefCell<Vec<(NodeRef, MemoUpdate)>>,
+    renderers: RefCell<Vec<Box<dyn FnMut()>>>,
+    // Run once no effects are dirty
+    idle: RefCell<Vec<Box<dyn FnOnce()>>>,
+    // The pending resource count of each suspense boundary whose content is being created, innermost last
+    pub(crate) suspense: RefCell<Vec<State<usize>>>,
+    check_effect_states: Cell<bool>,
+    running_effect: Cell<bool>,
+    stats: Cell<ReactiveStats>,
+    // False while rendering on the server, until the runtime is hydrated
+    client: Cell<bool>,
+    // Client only effects created before hydration, in the order they were created
+    client_only: RefCell<Vec<State<EffectData>>>,
+    // How many pauses are waiting for a resume
+    paused: Cell<usize>,
+}
+
+/// Counts of the reactive work a runtime has done since it was created or [`Runtime::reset_stats`] was called
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReactiveStats {
+    /// Every time an effect ran, including the first run when it was created
+    pub effect_runs: usize,
+    /// Every time a memo recomputed its value
+    pub memo_recomputes: usize,
+    /// Recomputes that produced a value equal to the old one, so nothing reading the memo was notified
+    pub memo_unchanged: usize,
 }
 
 impl Runtime {
     fn new() -> Self {
         Self {
             states: Queue::default(),
+            next_scope_id: Cell::new(0),
+            root_scopes: Default::default(),
+            effects: Default::default(),
+            memos: Default::default(),
+            renderers: Default::default(),
+            idle: Default::default(),
+            suspense: Default::default(),
+            check_effect_states: Cell::new(false),
+            running_effect: Cell::new(false),
+            stats: Default::default(),
+            client: Cell::new(cfg!(not(feature = "ssr"))),
+            client_only: Default::default(),
+            paused: Cell::new(0),
+        }
+    }
+
+    /// The reactive work done so far, to find effects and memos that run more often than expected
+    pub fn stats(runtime: RuntimeId) -> ReactiveStats {
+        with_rt(runtime, |runtime| runtime.stats.get())
+    }
+
+    pub fn reset_stats(runtime: RuntimeId) {
+        with_rt(runtime, |runtime| runtime.stats.take());
+    }
+
+    fn count(&self, f: impl FnOnce(&mut ReactiveStats)) {
+        let mut stats = self.stats.get();
+        f(&mut stats);
+        self.stats.set(stats);
+    }
+
+    /// Whether effects created with [`Scope::effect_client_only`] run. Without the `ssr` feature runtimes are always on the client, with it they render on the server until they are hydrated
+    pub fn is_client(runtime: RuntimeId) -> bool {
+        with_rt(runtime, |runtime| runtime.client.get())
+    }
+
+    /// Mark the runtime as running on the client after the server rendered html was hydrated. Client only effects from scopes that are still alive run now, in the order they were created
+    pub fn hydrate(runtime: RuntimeId) {
+        let pending = with_rt(runtime, |runtime| {
+            runtime.client.set(true);
+            runtime.client_only.take()
+        });
+        for effect in pending {
+            if !effect.raw.alive() {
+                continue;
+            }
+            let run = effect.peek(|effect| effect.run.clone());
+            let sources = Self::run_effect(runtime, &*run);
+            effect.with_mut(|effect| effect.sources = sources);
+        }
+    }
+
+    /// Panic in debug builds when a state is created while an effect is running. States created in an effect are created again every time it reruns
+    pub fn check_states_in_effects(runtime: RuntimeId, enabled: bool) {
+        with_rt(runtime, |runtime| runtime.check_effect_states.set(enabled));
+    }
+
+    fn run_effect(runtime: RuntimeId, run: &dyn Fn()) -> Sources {
+        let prev = with_rt(runtime, |runtime| {
+            runtime.count(|stats| stats.effect_runs += 1);
+            runtime.running_effect.replace(true)
+        });
+        let ((), sources) = track_sources(run);
+        with_rt(runtime, |runtime| runtime.running_effect.set(prev));
+        sources
+    }
+
+    // Insert the effect after every live effect with the same or a higher priority
+    fn add_effect(&self, effect: State<EffectData>) {
+        let priority = effect.peek(|effect| effect.priority);
+        let mut effects = self.effects.borrow_mut();
+        let index = effects
+            .iter()
+            .rposition(|other| other.raw.alive() && other.peek(|other| other.priority) >= priority)
+            .map_or(0, |index| index + 1);
+        effects.insert(index, effect);
+    }
+
+    fn assert_not_in_effect(&self) {
+        debug_assert!(
+            !(self.check_effect_states.get() && self.running_effect.get()),
+            "signal created inside an effect; did you mean to create it outside?"
+        );
+    }
+
+    /// Flush the renderer whenever the runtime is flushed
+    pub fn add_renderer<P: PlatformEvents, R: Renderer<P> + 'static>(
+        runtime: RuntimeId,
+        mut ui: R,
+    ) {
+        with_rt(runtime, |runtime| {
+            runtime
+                .renderers
+                .borrow_mut()
+                .push(Box::new(move || ui.flush()))
+        });
+    }
+
+    /// Stop [`Runtime::flush`] from doing anything until [`Runtime::resume`] is called. Writes made while paused are kept and applied by a single flush on resume, so bulk changes spread over several ticks only rerun each effect once. Pauses nest, and the runtime resumes when every pause is matched by a resume
+    pub fn pause(runtime: RuntimeId) {
+        with_rt(runtime, |runtime| {
+            runtime.paused.set(runtime.paused.get() + 1)
+        });
+    }
+
+    /// Undo one [`Runtime::pause`]. Resuming the last pause flushes the runtime
+    ///
+    /// Panics if the runtime isn't paused
+    pub fn resume(runtime: RuntimeId) {
+        let paused = with_rt(runtime, |runtime| {
+            let paused = runtime.paused.get();
+            assert!(paused > 0, "resumed a runtime that was not paused");
+            runtime.paused.set(paused - 1);
+            paused - 1
+        });
+        if paused == 0 {
+            Self::flush(runtime);
+        }
+    }
+
+    pub fn paused(runtime: RuntimeId) -> bool {
+        with_rt(runtime, |runtime| runtime.paused.get() > 0)
+    }
+
+    /// Apply all pending reactive work. Memos are updated and effects that read a written state are rerun by priority, then in the order they were created, until nothing changes, then the renderers are flushed. Does nothing while the runtime is paused
+    pub fn flush(runtime: RuntimeId) {
+        const MAX_PASSES: usize = 100;
+
+        if Self::paused(runtime) {
+            return;
+        }
+
+        for _ in 0..MAX_PASSES {
+            let (memos, effects) = with_rt(runtime, |runtime| {
+                let mut memos = runtime.memos.borrow_mut();
+                memos.retain(|(raw, _)| raw.alive());
+                let mut effects = runtime.effects.borrow_mut();
+                effects.retain(|effect| effect.raw.alive());
+                let memos: Vec<_> = memos.iter().map(|(_, update)| update.clone()).collect();
+                (memos, effects.clone())
+            });
+
+            for update in memos {
+                update();
+            }
+
+            let mut ran = false;
+            #[cfg_attr(not(feature = "debug-signals"), allow(unused_variables))]
+            for (i, effect) in effects.into_iter().enumerate() {
+                // an earlier effect may have dropped the scope that owns this one
+                if !effect.raw.alive() || !effect.peek(EffectData::dirty) {
+                    continue;
+                }
+                let run = effect.peek(|effect| effect.run.clone());
+                #[cfg(feature = "debug-signals")]
+                let prev = CONTEXT.with(|context| {
+                    let sources = effect.peek(EffectData::source_names);
+                    context.replace(Some(format!(
+                        "while running effect {i} that last read {sources}"
+                    )))
+                });
+                let sources = Self::run_effect(runtime, &*run);
+                #[cfg(feature = "debug-signals")]
+                CONTEXT.with(|context| context.replace(prev));
+                effect.with_mut(|effect| effect.sources = sources);
+                ran = true;
+            }
+
+            if !ran {
+                let mut renderers = with_rt(runtime, |runtime| runtime.renderers.take());
+                for flush in &mut renderers {
+                    flush();
+                }
+                with_rt(runtime, |runtime| {
+                    let mut added = runtime.renderers.borrow_mut();
+                    renderers.append(&mut added);
+                    *added = renderers;
+                });
+
+                let idle = with_rt(runtime, |runtime| runtime.idle.take());
+                if idle.is_empty() {
+                    return;
+                }
+                // the callbacks may write states, so keep flushing until nothing changes
+                for f in idle {
+                    f();
+                }
+            }
         }
+
+        panic!("effects did not settle after {MAX_PASSES} passes");
+    }
+
+    /// Run `f` once no effects are waiting to rerun. If nothing is pending `f` runs immediately, otherwise it runs at the end of the next [`Runtime::flush`] after the renderers are flushed. Effects made dirty by `f` rerun before that flush returns
+    pub fn on_idle(runtime: RuntimeId, f: impl FnOnce() + 'static) {
+        let pending = with_rt(runtime, |runtime| {
+            runtime.running_effect.get()
+                || runtime.paused.get() > 0
+                || runtime
+                    .effects
+                    .borrow()
+                    .iter()
+                    .any(|effect| effect.raw.alive() && effect.peek(EffectData::dirty))
+        });
+        if pending {
+            with_rt(runtime, |runtime| {
+                runtime.idle.borrow_mut().push(Box::new(f))
+            });
+        } else {
+            f();
+        }
+    }
+
+    /// Describe each effect and the states it read, one effect per line. States are shown by the tag given to [`State::named`]
+    #[cfg(feature = "debug-signals")]
+    pub fn dump_graph(runtime: RuntimeId) -> String {
+        let effects = with_rt(runtime, |runtime| runtime.effects.borrow().clone());
+        effects
+            .iter()
+            .filter(|effect| effect.raw.alive())
+            .enumerate()
+            .map(|(i, effect)| {
+                let sources = effect.peek(EffectData::source_names);
+                format!("effect {i} reads {sources}\n")
+            })
+            .collect()
+    }
+
+    /// The number of live effects that read the state in their last run. Memos that read the state aren't counted
+    pub fn subscriber_count(runtime: RuntimeId, node: NodeRef) -> usize {
+        let effects = with_rt(runtime, |runtime| runtime.effects.borrow().clone());
+        effects
+            .iter()
+            .filter(|effect| effect.raw.alive())
+            .filter(|effect| {
+                effect.peek(|effect| effect.sources.iter().any(|(raw, _)| *raw == node))
+            })
+            .count()
+    }
+
+    /// The number of states currently alive in the runtime
+    pub fn live_states(runtime: RuntimeId) -> usize {
+        with_rt(runtime, |runtime| runtime.states.live())
+    }
+
+    /// The number of live states of each type in the runtime, in the order the types were first stored. Types without live states are left out
+    pub fn alloc_stats(runtime: RuntimeId) -> Vec<(TypeId, usize)> {
+        with_rt(runtime, |runtime| runtime.states.live_types())
+    }
+
+    /// The scopes without a parent that are currently alive in the runtime
+    pub fn root_scopes(runtime: RuntimeId) -> Vec<ScopeId> {
+        with_rt(runtime, |runtime| runtime.root_scopes.borrow().clone())
+    }
+
+    fn scope_id(&self) -> ScopeId {
+        let id = self.next_scope_id.get();
+        self.next_scope_id.set(id + 1);
+        ScopeId(id)
+    }
+
+    fn root_scope_id(&self) -> ScopeId {
+        let id = self.scope_id();
+        self.root_scopes.borrow_mut().push(id);
+        id
+    }
+
+    #[cfg(feature = "ssr")]
+    fn reuse_or_new() -> Self {
+        RETIRED
+            .with(|retired| retired.borrow_mut().pop())
+            .unwrap_or_else(Self::new)
+    }
+}
+
+#[test]
+fn root_scopes() {
+    let runtime = claim_rt();
+    let first = crate::scope!(runtime);
+    let second = crate::scope!(runtime);
+    crate::child_scope!(first, |_| {});
+
+    let roots = Runtime::root_scopes(runtime);
+    assert!(roots.contains(&first.id()));
+    assert!(roots.contains(&second.id()));
+
+    let first_id = first.id();
+    drop(first);
+    let roots = Runtime::root_scopes(runtime);
+    assert!(!roots.contains(&first_id));
+    assert!(roots.contains(&second.id()));
+}
+
+#[cfg(feature = "ssr")]
+#[test]
+fn reuses_dropped_runtimes() {
+    let runtime = claim_rt();
+    {
+        let scope = crate::scope!(runtime);
+        scope.state(0);
+        scope.state(1);
+    }
+    assert_eq!(with_rt(runtime, |rt| rt.states.free_nodes()), 2);
+    drop_rt(runtime);
+
+    let runtime = claim_rt();
+    assert_eq!(with_rt(runtime, |rt| rt.states.free_nodes()), 2);
+    {
+        let scope = crate::scope!(runtime);
+        scope.state(0);
+        assert_eq!(with_rt(runtime, |rt| rt.states.free_nodes()), 1);
+    }
+    drop_rt(runtime);
+}
+
+// A state value that counts how many times it was dropped
+#[cfg(test)]
+struct Counted(Rc<Cell<usize>>);
+
+#[cfg(test)]
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+#[cfg(feature = "ssr")]
+#[test]
+fn drop_rt_drops_live_states() {
+    let runtime = claim_rt();
+    let dropped = Rc::new(Cell::new(0));
+    let scope = crate::scope!(runtime);
+    let state = scope.state(Counted(dropped.clone()));
+    crate::child_scope!(scope, |cx| cx.state(Counted(dropped.clone())));
+    scope.effect(move || {
+        state.with(|_| ());
+    });
+    drop_rt(runtime);
+    assert_eq!(dropped.get(), 2);
+    // dropping the scope afterwards doesn't drop the states again
+    drop(scope);
+    assert_eq!(dropped.get(), 2);
+
+    let runtime = claim_rt();
+    assert_eq!(Runtime::live_states(runtime), 0);
+    assert_eq!(with_rt(runtime, |rt| rt.scope_id()), ScopeId(0));
+    drop_rt(runtime);
+}
+
+#[test]
+fn remove_many_states() {
+    let runtime = claim_rt();
+    let dropped = Rc::new(Cell::new(0));
+    let live = Runtime::live_states(runtime);
+    let scope = crate::scope!(runtime);
+    let states: Vec<_> = (0..1000)
+        .map(|_| scope.state(Counted(dropped.clone())))
+        .collect();
+    let allocated = with_rt(runtime, |rt| rt.states.free_nodes());
+    assert_eq!(Runtime::live_states(runtime), live + 1000);
+
+    let raw: Vec<_> = states.iter().map(|state| state.raw).collect();
+    unsafe { with_rt(runtime, |rt| rt.states.remove_many(&raw)) };
+    assert_eq!(dropped.get(), 1000);
+    assert_eq!(Runtime::live_states(runtime), live);
+    assert_eq!(
+        with_rt(runtime, |rt| rt.states.free_nodes()),
+        allocated + 1000
+    );
+    assert!(states.iter().all(|state| state.try_with(|_| ()).is_err()));
+
+    // the scope must not remove them again
+    scope.owns.borrow_mut().clear();
+}
+
+// Compares removing a scope's states in one batch with removing them one at a time. Run it with `cargo test --release remove_many_bench -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn remove_many_bench() {
+    use std::time::{Duration, Instant};
+
+    const STATES: usize = 10_000;
+    const ROUNDS: u32 = 20;
+
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let time = |batched: bool| {
+        let mut total = Duration::ZERO;
+        for _ in 0..ROUNDS {
+            let raw: Vec<_> = (0..STATES)
+                .map(|i| scope.state(i.to_string()).raw)
+                .collect();
+            let start = Instant::now();
+            with_rt(runtime, |rt| unsafe {
+                if batched {
+                    rt.states.remove_many(&raw);
+                } else {
+                    for node in &raw {
+                        rt.states.remove_many(std::slice::from_ref(node));
+                    }
+                }
+            });
+            total += start.elapsed();
+            // the scope must not remove them again
+            scope.owns.borrow_mut().clear();
+        }
+        total / ROUNDS
+    };
+    // the first rounds allocate the nodes that the measured rounds reuse
+    time(true);
+    let per_key = time(false);
+    let batched = time(true);
+    println!("removing {STATES} states: {batched:?} batched, {per_key:?} one at a time");
+}
+
+#[test]
+fn states_share_drop_functions() {
+    let runtime = claim_rt();
+    let dropped = Rc::new(Cell::new(0));
+    let thunks = with_rt(runtime, |rt| rt.states.drop_thunks());
+    {
+        let scope = crate::scope!(runtime);
+        for i in 0..100 {
+            scope.state(Counted(dropped.clone()));
+            scope.state(i);
+        }
+        assert_eq!(with_rt(runtime, |rt| rt.states.drop_thunks()), thunks + 2);
+        assert_eq!(dropped.get(), 0);
     }
+    assert_eq!(dropped.get(), 100);
+
+    // the drop function is stored as an index instead of a function pointer
+    assert!(
+        std::mem::size_of::<NodeData>() <= std::mem::size_of::<(NonNull<()>, unsafe fn(*mut ()))>()
+    );
+    drop_rt(runtime);
 }
 
 #[macro_export]
@@ -122,6 +619,77 @@ macro_rules! hyristic2 {
     };
 }
 
+/// Implement the scope heuristics for a type, starting from a known guess instead of zero. Components use this with the number and size of the states they own
+#[cfg(feature = "bump")]
+#[macro_export]
+macro_rules! impl_hyristics {
+    ($ty:ty, $owned:expr, $allocation:expr) => {
+        const _: () = {
+            static GUESS: std::sync::atomic::AtomicUsize =
+                std::sync::atomic::AtomicUsize::new($allocation);
+            static GUESS2: std::sync::atomic::AtomicUsize =
+                std::sync::atomic::AtomicUsize::new($owned);
+
+            impl $crate::copy::ScopeHyristics for $ty {
+                fn guess_allocation() -> usize {
+                    GUESS.load(std::sync::atomic::Ordering::Relaxed)
+                }
+
+                fn update_guess(new: usize) {
+                    GUESS.store(new, std::sync::atomic::Ordering::Relaxed)
+                }
+            }
+
+            impl $crate::copy::ScopeHyristicsOwned for $ty {
+                fn guess_owned() -> usize {
+                    GUESS2.load(std::sync::atomic::Ordering::Relaxed)
+                }
+
+                fn update_owned(new: usize) {
+                    GUESS2.store(new, std::sync::atomic::Ordering::Relaxed)
+                }
+            }
+        };
+    };
+}
+
+/// Implement the scope heuristics for a type, starting from a known guess instead of zero. Components use this with the number and size of the states they own
+#[cfg(not(feature = "bump"))]
+#[macro_export]
+macro_rules! impl_hyristics {
+    ($ty:ty, $owned:expr, $allocation:expr) => {
+        const _: () = {
+            static GUESS2: std::sync::atomic::AtomicUsize =
+                std::sync::atomic::AtomicUsize::new($owned);
+
+            impl $crate::copy::ScopeHyristicsOwned for $ty {
+                fn guess_owned() -> usize {
+                    GUESS2.load(std::sync::atomic::Ordering::Relaxed)
+                }
+
+                fn update_owned(new: usize) {
+                    GUESS2.store(new, std::sync::atomic::Ordering::Relaxed)
+                }
+            }
+        };
+    };
+}
+
+#[test]
+fn known_component_hyristics() {
+    struct Counter;
+    crate::impl_hyristics!(Counter, 3, 3 * std::mem::size_of::<i32>());
+
+    assert_eq!(<Counter as ScopeHyristicsOwned>::guess_owned(), 3);
+
+    #[cfg(all(feature = "heuristics", feature = "bump"))]
+    let scope = Scope::new::<Counter, Counter>(claim_rt());
+    #[cfg(all(feature = "heuristics", not(feature = "bump")))]
+    let scope = Scope::new::<Counter>(claim_rt());
+    #[cfg(feature = "heuristics")]
+    assert!(scope.owns.borrow().capacity() >= 3);
+}
+
 #[cfg(not(feature = "heuristics"))]
 #[macro_export]
 macro_rules! scope {
@@ -191,15 +759,29 @@ pub trait ScopeHyristicsOwned {
     fn update_owned(new: usize);
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ScopeId(usize);
+
+// The values provided to a scope with [`Scope::provide_context`], followed by the contexts of its parent
+#[derive(Default)]
+struct Contexts {
+    values: RefCell<Vec<(TypeId, Box<dyn Any>)>>,
+    parent: Option<Rc<Contexts>>,
+}
+
 pub struct Scope {
-    parent: Option<RuntimeId>,
+    id: ScopeId,
+    // The states owned by the parent scope
+    parent: Option<Rc<RefCell<Vec<NodeRef>>>>,
+    contexts: Rc<Contexts>,
     children: RefCell<Option<Vec<Scope>>>,
     runtime: RuntimeId,
-    owns: RefCell<Vec<NodeRef>>,
+    owns: Rc<RefCell<Vec<NodeRef>>>,
     #[cfg(feature = "heuristics")]
     update_owned: fn(usize),
     #[cfg(all(feature = "bump", feature = "heuristics"))]
     update: fn(usize),
+    // Never reset while the scope is alive because the states it owns point into it
     #[cfg(feature = "bump")]
     allocator: bumpalo::Bump,
 }
@@ -208,10 +790,12 @@ impl Scope {
     #[cfg(not(feature = "heuristics"))]
     pub fn new(runtime: RuntimeId) -> Self {
         Self {
+            id: with_rt(runtime, |rt| rt.root_scope_id()),
             parent: None,
+            contexts: Default::default(),
             children: Default::default(),
             runtime,
-            owns: RefCell::new(Vec::new()),
+            owns: Rc::new(RefCell::new(Vec::new())),
             #[cfg(feature = "bump")]
             allocator: bumpalo::Bump::new(),
         }
@@ -221,10 +805,12 @@ impl Scope {
     #[cfg(feature = "heuristics")]
     pub fn new<H: ScopeHyristics, H2: ScopeHyristicsOwned>(runtime: RuntimeId) -> Self {
         Self {
+            id: with_rt(runtime, |rt| rt.root_scope_id()),
             parent: None,
+            contexts: Default::default(),
             children: Default::default(),
             runtime,
-            owns: RefCell::new(Vec::with_capacity(H2::guess_owned())),
+            owns: Rc::new(RefCell::new(Vec::with_capacity(H2::guess_owned()))),
             update_owned: H2::update_owned,
             #[cfg(feature = "bump")]
             update: H::update_guess,
@@ -237,21 +823,38 @@ impl Scope {
     #[cfg(feature = "heuristics")]
     pub fn new<H: ScopeHyristicsOwned>(runtime: RuntimeId) -> Self {
         Self {
+            id: with_rt(runtime, |rt| rt.root_scope_id()),
             parent: None,
+            contexts: Default::default(),
             children: Default::default(),
             runtime,
-            owns: Default::default(),
+            owns: Rc::new(RefCell::new(Vec::with_capacity(H::guess_owned()))),
             update_owned: H::update_owned,
         }
     }
 
+    /// Run `f` with a new scope that is dropped along with this scope
+    ///
+    /// States created in the child should not be used after this scope is dropped. Create any state that needs to outlive the child in the parent:
+    /// ```rust, ignore
+    /// let count = cx.state(0);
+    /// cx.child(|cx| {
+    ///     let doubled = cx.state(count.get() * 2);
+    ///     // only use `doubled` in here
+    /// });
+    /// ```
     #[cfg(not(feature = "heuristics"))]
     pub fn child<O>(&self, f: impl FnOnce(&Scope) -> O) -> O {
         let scope = Self {
-            parent: Some(self.runtime),
+            id: with_rt(self.runtime, |rt| rt.scope_id()),
+            parent: Some(self.owns.clone()),
+            contexts: Rc::new(Contexts {
+                values: Default::default(),
+                parent: Some(self.contexts.clone()),
+            }),
             children: Default::default(),
             runtime: self.runtime,
-            owns: RefCell::new(Vec::new()),
+            owns: Rc::new(RefCell::new(Vec::new())),
             #[cfg(feature = "bump")]
             allocator: bumpalo::Bump::new(),
         };
@@ -270,10 +873,15 @@ impl Scope {
         f: impl FnOnce(&Scope) -> O,
     ) -> O {
         let scope = Self {
-            parent: Some(self.runtime),
+            id: with_rt(self.runtime, |rt| rt.scope_id()),
+            parent: Some(self.owns.clone()),
+            contexts: Rc::new(Contexts {
+                values: Default::default(),
+                parent: Some(self.contexts.clone()),
+            }),
             children: Default::default(),
             runtime: self.runtime,
-            owns: RefCell::new(Vec::with_capacity(H2::guess_owned())),
+            owns: Rc::new(RefCell::new(Vec::with_capacity(H2::guess_owned()))),
             update_owned: H2::update_owned,
             update: H::update_guess,
             allocator: bumpalo::Bump::with_capacity(H::guess_allocation()),
@@ -292,10 +900,15 @@ impl Scope {
     #[cfg(feature = "heuristics")]
     pub fn child<H: ScopeHyristicsOwned, O>(&self, f: impl FnOnce(&Scope) -> O) -> O {
         let scope = Self {
-            parent: Some(self.runtime),
+            id: with_rt(self.runtime, |rt| rt.scope_id()),
+            parent: Some(self.owns.clone()),
+            contexts: Rc::new(Contexts {
+                values: Default::default(),
+                parent: Some(self.contexts.clone()),
+            }),
             children: Default::default(),
             runtime: self.runtime,
-            owns: RefCell::new(Vec::with_capacity(H::guess_owned())),
+            owns: Rc::new(RefCell::new(Vec::with_capacity(H::guess_owned()))),
             update_owned: H::update_owned,
         };
         let r = f(&scope);
@@ -307,6 +920,140 @@ impl Scope {
         r
     }
 
+    /// A scope that belongs to whatever holds it instead of a parent or the runtime's list of root scopes. A child of `parent` can read its contexts, but isn't dropped with it
+    fn detached(runtime: RuntimeId, parent: Option<&Scope>) -> Self {
+        Self {
+            id: with_rt(runtime, |rt| rt.scope_id()),
+            parent: parent.map(|parent| parent.owns.clone()),
+            contexts: Rc::new(Contexts {
+                values: Default::default(),
+                parent: parent.map(|parent| parent.contexts.clone()),
+            }),
+            children: Default::default(),
+            runtime,
+            owns: Rc::new(RefCell::new(Vec::new())),
+            #[cfg(feature = "heuristics")]
+            update_owned: |_| {},
+            #[cfg(all(feature = "bump", feature = "heuristics"))]
+            update: |_| {},
+            #[cfg(feature = "bump")]
+            allocator: bumpalo::Bump::new(),
+        }
+    }
+
+    /// A scope for states the runtime creates for itself, like the effect of a [`Subscription`]. It isn't a root scope, so it doesn't show up in [`Runtime::root_scopes`]
+    pub(crate) fn internal(runtime: RuntimeId) -> Self {
+        Self::detached(runtime, None)
+    }
+
+    /// A child scope that is dropped by whoever holds it instead of with this scope, for children that come and go while this scope lives
+    pub(crate) fn detached_child(&self) -> Self {
+        Self::detached(self.runtime, Some(self))
+    }
+
+    pub fn id(&self) -> ScopeId {
+        self.id
+    }
+
+    /// The states owned by this scope along with the type of each value, in the order they were created
+    pub fn owned_states(&self) -> Vec<(NodeRef, TypeId)> {
+        with_rt(self.runtime, |runtime| {
+            self.owns
+                .borrow()
+                .iter()
+                .filter(|raw| raw.alive())
+                .map(|raw| (*raw, runtime.states.type_id(*raw)))
+                .collect()
+        })
+    }
+
+    /// The scope and the scopes below it with the number of live states each owns, one scope per line indented by depth:
+    /// ```text
+    /// Scope(states=3)
+    ///   Child(states=1)
+    /// ```
+    /// A scope whose children are being changed while the tree is built is shown as `busy`
+    pub fn debug_tree(&self) -> String {
+        let mut tree = String::new();
+        self.write_tree(0, &mut tree);
+        tree
+    }
+
+    fn write_tree(&self, depth: usize, tree: &mut String) {
+        use std::fmt::Write;
+
+        if depth > 0 {
+            tree.push('\n');
+        }
+        let name = if depth == 0 { "Scope" } else { "Child" };
+        let indent = "  ".repeat(depth);
+        match self.owns.try_borrow() {
+            Ok(owns) => {
+                let states = owns.iter().filter(|raw| raw.alive()).count();
+                let _ = write!(tree, "{indent}{name}(states={states})");
+            }
+            Err(_) => {
+                let _ = write!(tree, "{indent}{name}(busy)");
+            }
+        }
+        match self.children.try_borrow() {
+            Ok(children) => {
+                for child in children.iter().flatten() {
+                    child.write_tree(depth + 1, tree);
+                }
+            }
+            Err(_) => {
+                let _ = write!(tree, "\n{indent}  busy");
+            }
+        }
+    }
+
+    /// The runtime the scope's states are created in
+    pub fn runtime(&self) -> RuntimeId {
+        self.runtime
+    }
+
+    /// Make `value` available to this scope and every scope created below it with [`Scope::use_context`]. Providing another value of the same type in the same scope replaces it
+    pub fn provide_context<T: Clone + 'static>(&self, value: T) {
+        let mut values = self.contexts.values.borrow_mut();
+        let value = Box::new(value);
+        match values.iter_mut().find(|(ty, _)| *ty == TypeId::of::<T>()) {
+            Some((_, old)) => *old = value,
+            None => values.push((TypeId::of::<T>(), value)),
+        }
+    }
+
+    /// The value of type `T` provided by this scope or the closest ancestor that provided one
+    pub fn use_context<T: Clone + 'static>(&self) -> Option<T> {
+        let mut contexts = Some(&self.contexts);
+        while let Some(current) = contexts {
+            let values = current.values.borrow();
+            let value = values
+                .iter()
+                .find(|(ty, _)| *ty == TypeId::of::<T>())
+                .and_then(|(_, value)| value.downcast_ref::<T>());
+            if let Some(value) = value {
+                return Some(value.clone());
+            }
+            contexts = current.parent.as_ref();
+        }
+        None
+    }
+
+    /// A state provided as context with [`Scope::provide_context`]. Reading it subscribes to the state like any other
+    pub fn use_context_signal<T: 'static>(&self) -> Option<State<T>> {
+        self.use_context::<State<T>>()
+    }
+
+    /// Create a state that is only inserted into the runtime the first time it is read or written
+    pub fn lazy_state<T: 'static>(&self, init: impl FnOnce() -> T + 'static) -> LazyState<'_, T> {
+        LazyState {
+            scope: self,
+            init: Cell::new(Some(Box::new(init))),
+            state: Cell::new(None),
+        }
+    }
+
     pub fn state<T: 'static>(&self, value: T) -> State<T> {
         #[cfg(feature = "bump")]
         let non_null: NonNull<T> = self.allocator.alloc(value).into();
@@ -314,26 +1061,55 @@ impl Scope {
         let non_null: NonNull<T> =
             unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(value))) };
         let raw = with_rt(self.runtime, |runtime| {
+            runtime.assert_not_in_effect();
             runtime.states.insert(NodeData {
                 ptr: non_null.cast(),
-                drop: |value: *mut ()| unsafe {
-                    std::ptr::drop_in_place(value as *mut T);
-                },
+                drop: runtime.states.drop_id::<T>(),
             })
         });
         let signal = State {
             raw,
+            runtime: self.runtime,
             phantom: PhantomData,
         };
         self.owns.borrow_mut().push(raw);
         signal
     }
 
+    /// Create a state owned by the parent of this scope. The state is not dropped with this scope, so it can be used after the child scope it was created in is dropped.
+    ///
+    /// If this scope has no parent, the state is owned by this scope
+    pub fn state_in_parent<T: 'static>(&self, value: T) -> State<T> {
+        let Some(parent) = &self.parent else {
+            warn("state_in_parent was called on a scope without a parent; the state is owned by the scope instead");
+            return self.state(value);
+        };
+        // The value can't be allocated in this scope's allocator because it is freed with this scope
+        let non_null: NonNull<T> =
+            unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(value))) };
+        let raw = with_rt(self.runtime, |runtime| {
+            runtime.assert_not_in_effect();
+            runtime.states.insert(NodeData {
+                ptr: non_null.cast(),
+                drop: runtime.states.drop_id::<T>(),
+            })
+        });
+        parent.borrow_mut().push(raw);
+        State {
+            raw,
+            runtime: self.runtime,
+            phantom: PhantomData,
+        }
+    }
+
     pub fn state_with<T: 'static>(&self, constructor: impl FnOnce(State<T>) -> T) -> State<T> {
         let key = with_rt(self.runtime, |runtime| {
+            runtime.assert_not_in_effect();
+            let drop = runtime.states.drop_id::<T>();
             runtime.states.insert_with(|raw| {
                 let signal = State {
                     raw,
+                    runtime: self.runtime,
                     phantom: PhantomData,
                 };
                 let value = constructor(signal);
@@ -345,27 +1121,96 @@ impl Scope {
 
                 NodeData {
                     ptr: non_null.cast(),
-                    drop: |value: *mut ()| unsafe {
-                        std::ptr::drop_in_place(value as *mut T);
-                    },
+                    drop,
                 }
             })
         });
         self.owns.borrow_mut().push(key);
         State {
             raw: key,
+            runtime: self.runtime,
             phantom: PhantomData,
         }
     }
+
+    /// Create a value derived from other states. The value is only recomputed when [`Memo::update`] or [`Runtime::flush`] is called after one of the states it read changed
+    pub fn memo<T: PartialEq + 'static>(&self, f: impl Fn() -> T + 'static) -> Memo<T> {
+        let ((value, upstream), sources) = track_sources(|| track_memos(&f));
+        let value = self.state(value);
+        let compute = self.state(Box::new(f) as Box<dyn Fn() -> T>);
+        let upstream = self.state(upstream);
+        let sources = self.state(sources);
+        let memo = Memo {
+            value,
+            compute,
+            upstream,
+            sources,
+        };
+        with_rt(self.runtime, |runtime| {
+            runtime
+                .memos
+                .borrow_mut()
+                .push((value.raw, Rc::new(move || memo.update())))
+        });
+        memo
+    }
+
+    /// Like [`Scope::effect`], but on a runtime that is rendering on the server `f` doesn't run until [`Runtime::hydrate`] is called. Use it for effects that touch the DOM or other browser APIs
+    pub fn effect_client_only(&self, f: impl Fn() + 'static) {
+        if Runtime::is_client(self.runtime) {
+            return self.effect(f);
+        }
+        // the effect has no sources, so flushing doesn't run it before hydration
+        let effect = self.state(EffectData {
+            run: Rc::new(f),
+            sources: Sources::new(),
+            priority: 0,
+        });
+        with_rt(self.runtime, |runtime| {
+            runtime.add_effect(effect);
+            runtime.client_only.borrow_mut().push(effect);
+        });
+    }
+
+    /// Run `f` now and again during [`Runtime::flush`] whenever a state it read was written. When the scope is dropped its effects are unsubscribed and dropped along with its states, last created first
+    pub fn effect(&self, f: impl Fn() + 'static) {
+        self.effect_with_priority(0, f)
+    }
+
+    /// Like [`Scope::effect`], but when several effects need to rerun in the same flush the ones with a higher priority run first. Effects with the same priority run in the order they were created. [`Scope::effect`] has a priority of 0
+    pub fn effect_with_priority(&self, priority: i32, f: impl Fn() + 'static) {
+        let run: Rc<dyn Fn()> = Rc::new(f);
+        let sources = Runtime::run_effect(self.runtime, &*run);
+        let effect = self.state(EffectData {
+            run,
+            sources,
+            priority,
+        });
+        with_rt(self.runtime, |runtime| runtime.add_effect(effect));
+    }
+
+    /// Run `f` a single time at the end of the next [`Runtime::flush`], after the renderers are flushed. Unlike an effect it doesn't track the states it reads. If the scope is dropped before then `f` never runs
+    pub fn effect_once(&self, f: impl FnOnce() + 'static) {
+        let pending: State<Option<Box<dyn FnOnce()>>> = self.state(Some(Box::new(f)));
+        with_rt(self.runtime, |runtime| {
+            runtime.idle.borrow_mut().push(Box::new(move || {
+                if let Ok(Some(f)) = pending.try_with_mut(Option::take) {
+                    f();
+                }
+            }))
+        });
+    }
 }
 
 impl Drop for Scope {
     fn drop(&mut self) {
-        with_rt(self.runtime, |runtime| {
-            for key in self.owns.borrow().iter() {
-                unsafe {
-                    runtime.states.remove(*key);
-                }
+        // The runtime may already have been dropped with drop_rt
+        let _ = try_with_rt(self.runtime, |runtime| {
+            unsafe {
+                runtime.states.remove_many(&self.owns.borrow());
+            }
+            if self.parent.is_none() {
+                runtime.root_scopes.borrow_mut().retain(|id| *id != self.id);
             }
         });
         #[cfg(feature = "bump")]
@@ -381,15 +1226,67 @@ impl Drop for Scope {
     }
 }
 
-pub struct State<T: ?Sized + 'static> {
-    pub(crate) raw: NodeRef,
-    pub(crate) phantom: std::marker::PhantomData<T>,
+pub(crate) fn warn(message: &str) {
+    // the console is only available in the browser
+    if cfg!(target_arch = "wasm32") {
+        web_sys::console::warn_1(&message.into());
+    } else {
+        eprintln!("{message}");
+    }
 }
 
-impl<T: Display> Display for State<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.with(|x| x.fmt(f))
-    }
+/// Drops the scope and runtime created by [`test_state`]
+#[cfg(any(test, feature = "test-util"))]
+pub struct TestStateGuard {
+    scope: Option<Scope>,
+    runtime: RuntimeId,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Drop for TestStateGuard {
+    fn drop(&mut self) {
+        self.scope.take();
+        drop_rt(self.runtime);
+    }
+}
+
+/// Create a state without setting up a runtime and scope. The state is dropped along with the guard
+#[cfg(any(test, feature = "test-util"))]
+pub fn test_state<T: 'static>(value: T) -> (State<T>, impl Drop) {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let state = scope.state(value);
+    (
+        state,
+        TestStateGuard {
+            scope: Some(scope),
+            runtime,
+        },
+    )
+}
+
+#[test]
+fn test_state_get_set() {
+    let (state, guard) = test_state(1);
+    assert_eq!(state.get(), 1);
+    state.set(2);
+    assert_eq!(state.get(), 2);
+    drop(guard);
+}
+
+/// A reactive value owned by a [`Scope`]
+///
+/// The value is allocated once when the state is created and is never moved afterwards, even when it is written, so it can be pinned or pointed to until the scope that owns it is dropped. With the `bump` feature the value lives in the scope's arena, which is only freed when the scope is dropped
+pub struct State<T: ?Sized + 'static> {
+    pub(crate) raw: NodeRef,
+    pub(crate) runtime: RuntimeId,
+    pub(crate) phantom: std::marker::PhantomData<T>,
+}
+
+impl<T: Display> Display for State<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.with(|x| x.fmt(f))
+    }
 }
 
 impl<T: Debug> Debug for State<T> {
@@ -402,6 +1299,7 @@ impl<T: 'static> Clone for State<T> {
     fn clone(&self) -> Self {
         Self {
             raw: self.raw,
+            runtime: self.runtime,
             phantom: self.phantom,
         }
     }
@@ -410,6 +1308,91 @@ impl<T: 'static> Clone for State<T> {
 impl<T: 'static> Copy for State<T> {}
 
 impl<T: 'static> State<T> {
+    /// Make any write to the state panic until [`State::unfreeze`] is called
+    pub fn freeze(&self) {
+        self.raw.set_frozen(true);
+    }
+
+    pub fn unfreeze(&self) {
+        self.raw.set_frozen(false);
+    }
+
+    /// Tag the state with a human readable name for debugging. The tag is only stored with the `debug-signals` feature and this does nothing otherwise
+    pub fn named(self, tag: &'static str) -> Self {
+        #[cfg(feature = "debug-signals")]
+        self.raw.set_tag(tag);
+        #[cfg(not(feature = "debug-signals"))]
+        let _ = tag;
+        self
+    }
+
+    #[cfg(feature = "debug-signals")]
+    pub fn tag(&self) -> Option<&'static str> {
+        self.raw.tag()
+    }
+
+    /// The name of the value's type, like `alloc::vec::Vec<u32>`, for logging which state caused a problem along with its [`State::named`] tag. The exact text is not stable across compiler versions
+    pub fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+
+    /// A pointer to the value for interop. The value never moves, so the pointer stays valid until the scope that owns the state is dropped
+    ///
+    /// Panics if the scope was already dropped
+    pub fn as_ptr(&self) -> *const T {
+        self.raw.ptr() as *const T
+    }
+
+    /// The runtime the state was created in
+    pub fn runtime(&self) -> RuntimeId {
+        self.runtime
+    }
+
+    /// The untyped node that holds the value, for interop with code that can't name `T`. Turn it back into a state with [`State::from_raw`]
+    pub fn raw(&self) -> NodeRef {
+        self.raw
+    }
+
+    /// Turn a node from [`State::raw`] back into a state
+    ///
+    /// # Safety
+    ///
+    /// `raw` must come from a `State<T>` with the same `T` that was created in `runtime`, and the scope that owns it must still be alive. Debug builds check both, but release builds read the value as `T` without checking
+    pub unsafe fn from_raw(runtime: RuntimeId, raw: NodeRef) -> Self {
+        debug_assert!(
+            with_rt(runtime, |runtime| runtime.states.type_id(raw)) == TypeId::of::<T>(),
+            "the node does not hold a {}",
+            std::any::type_name::<T>()
+        );
+        Self {
+            raw,
+            runtime,
+            phantom: PhantomData,
+        }
+    }
+
+    /// A reference to the state that can be kept after the scope that owns it is dropped, for caches and observers that shouldn't assume the state is alive
+    pub fn downgrade(&self) -> WeakState<T> {
+        WeakState { state: *self }
+    }
+
+    /// Call `f` with the value during [`Runtime::flush`] after the state was written, until the returned subscription is dropped. Unlike an effect `f` isn't called right away, and reading other states in `f` doesn't subscribe to them. Once the scope that owns the state is dropped `f` is never called again
+    pub fn subscribe(&self, f: impl FnMut(&T) + 'static) -> Subscription {
+        let scope = Scope::internal(self.runtime);
+        let state = *self;
+        let f = RefCell::new(f);
+        let subscribed = Cell::new(false);
+        scope.effect(move || {
+            // reading the state is the only thing that subscribes
+            if state.try_with(|_| ()).is_err() || !subscribed.replace(true) {
+                return;
+            }
+            let mut f = f.borrow_mut();
+            track_sources(|| state.peek(|value| f(value)));
+        });
+        Subscription { _scope: scope }
+    }
+
     pub fn map<U: 'static, F: Fn(&T) -> &U, FMut: Fn(&mut T) -> &mut U, Up: Fn()>(
         self,
         f: F,
@@ -424,36 +1407,702 @@ impl<T: 'static> State<T> {
             phantom: PhantomData,
         }
     }
+
+    /// A read only projection of the state. Use [`State::map`] if the projection also needs to be written
+    pub fn map_ref<U: 'static, F: Fn(&T) -> &U>(self, f: F) -> ReadMapped<T, U, F> {
+        ReadMapped {
+            inner: self,
+            f,
+            phantom: PhantomData,
+        }
+    }
+
+    /// A view of the state that keeps the value between `min` and `max`, clamping it after every write. The current value is clamped right away
+    ///
+    /// Panics if `min` is greater than `max`
+    pub fn clamped(self, min: T, max: T) -> ClampedState<T>
+    where
+        T: Ord + Copy,
+    {
+        assert!(min <= max, "clamped state needs min <= max");
+        let clamped = ClampedState {
+            inner: self,
+            min,
+            max,
+        };
+        clamped.with_mut_maybe(|_| (false, ()));
+        clamped
+    }
+}
+
+impl<T: 'static> State<T> {
+    /// Like [`Readable::with`], but returns an error instead of panicking if the state can't be read
+    pub fn try_with<U: 'static, F: FnOnce(&T) -> U>(&self, f: F) -> QkResult<U> {
+        try_with_rt(self.runtime, |_| ())?;
+        let value = unsafe { self.raw.try_borrow::<T>()? };
+        read_source(self.raw);
+        Ok(f(&*value))
+    }
+
+    pub fn try_get(&self) -> QkResult<T>
+    where
+        T: Copy,
+    {
+        self.try_with(|value| *value)
+    }
+
+    /// Like [`Writable::with_mut`], but returns an error instead of panicking if the state can't be written, including when it is [frozen](State::freeze)
+    pub fn try_with_mut<F: FnOnce(&mut T) -> O, O>(&self, f: F) -> QkResult<O> {
+        try_with_rt(self.runtime, |_| ())?;
+        if self.raw.frozen() {
+            return Err(QkError::Frozen);
+        }
+        let mut value = unsafe { self.raw.try_borrow_mut::<T>()? };
+        Ok(f(&mut *value))
+    }
+
+    pub fn try_set(&self, value: T) -> QkResult<()> {
+        self.try_with_mut(|old| *old = value)
+    }
+
+    /// Read the state as `value` while `f` runs, then put the old value back. Neither swap notifies subscribers, so only code that reads the state inside `f` sees the override. Writes made inside `f` are lost when the old value is restored. Overrides can be nested
+    pub fn with_override<O>(&self, value: T, f: impl FnOnce() -> O) -> O {
+        // restores the old value even if f panics
+        struct Restore<T: 'static> {
+            state: State<T>,
+            old: Option<T>,
+        }
+
+        impl<T: 'static> Drop for Restore<T> {
+            fn drop(&mut self) {
+                if let Some(old) = self.old.take() {
+                    // the scope may have been dropped inside f
+                    if self.state.raw.alive() {
+                        *unsafe { self.state.raw.borrow_mut_silent::<T>() } = old;
+                    }
+                }
+            }
+        }
+
+        let old = std::mem::replace(&mut *unsafe { self.raw.borrow_mut_silent::<T>() }, value);
+        let _restore = Restore {
+            state: *self,
+            old: Some(old),
+        };
+        f()
+    }
+}
+
+#[test]
+fn override_is_restored() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let theme = scope.state("light");
+    let runs = Rc::new(Cell::new(0));
+    scope.effect({
+        let runs = runs.clone();
+        move || {
+            theme.with(|_| ());
+            runs.set(runs.get() + 1);
+        }
+    });
+
+    let seen = theme.with_override("dark", || {
+        let inner = theme.with_override("contrast", || theme.get());
+        (theme.get(), inner)
+    });
+    assert_eq!(seen, ("dark", "contrast"));
+    assert_eq!(theme.get(), "light");
+
+    // neither the override nor the restore reran the effect
+    Runtime::flush(runtime);
+    assert_eq!(runs.get(), 1);
+}
+
+#[cfg(feature = "ssr")]
+#[test]
+fn try_get_after_runtime_dropped() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let state = scope.state(1);
+    assert_eq!(state.try_get(), Ok(1));
+
+    drop_rt(runtime);
+    assert!(matches!(state.try_get(), Err(QkError::RuntimeGone)));
+    assert_eq!(state.try_set(2), Err(QkError::RuntimeGone));
+}
+
+#[test]
+fn try_get_errors() {
+    let scope = crate::scope!(claim_rt());
+    let state = scope.state(1);
+    state
+        .try_with_mut(|_| assert_eq!(state.try_get(), Err(QkError::BorrowConflict)))
+        .unwrap();
+
+    state.freeze();
+    assert_eq!(state.try_set(2), Err(QkError::Frozen));
+    assert_eq!(state.try_get(), Ok(1));
+    state.unfreeze();
+
+    let child = crate::child_scope!(scope, |cx| cx.state(0));
+    drop(scope);
+    assert_eq!(child.try_get(), Err(QkError::ScopeDisposed));
+}
+
+#[test]
+fn owned_state_types() {
+    let scope = crate::scope!(claim_rt());
+    let count = scope.state(0u32);
+    scope.state(String::new());
+    scope.state(1u32);
+    scope.state(2u32);
+
+    let owned = scope.owned_states();
+    assert_eq!(owned[0].0, count.raw);
+    assert_eq!(
+        owned.iter().map(|(_, ty)| *ty).collect::<Vec<_>>(),
+        [
+            TypeId::of::<u32>(),
+            TypeId::of::<String>(),
+            TypeId::of::<u32>(),
+            TypeId::of::<u32>()
+        ]
+    );
+}
+
+#[test]
+fn alloc_stats_count_live_states() {
+    struct Width;
+    struct Label;
+    fn count<T: 'static>(runtime: RuntimeId) -> usize {
+        Runtime::alloc_stats(runtime)
+            .into_iter()
+            .find(|(ty, _)| *ty == TypeId::of::<T>())
+            .map_or(0, |(_, count)| count)
+    }
+
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    scope.state(Width);
+    scope.state(Label);
+    crate::child_scope!(scope, |cx| {
+        cx.state(Width);
+        cx.state(Width);
+        cx.state(Label);
+    });
+    assert_eq!(count::<Width>(runtime), 3);
+    assert_eq!(count::<Label>(runtime), 2);
+
+    // disposed states aren't counted, and reused nodes count toward their new type
+    drop(scope.children.take());
+    assert_eq!(count::<Width>(runtime), 1);
+    assert_eq!(count::<Label>(runtime), 1);
+    scope.state(Label);
+    assert_eq!(count::<Label>(runtime), 2);
+
+    drop(scope);
+    assert!(!Runtime::alloc_stats(runtime)
+        .iter()
+        .any(|(ty, _)| *ty == TypeId::of::<Width>() || *ty == TypeId::of::<Label>()));
+}
+
+#[test]
+fn nested_contexts() {
+    let scope = crate::scope!(claim_rt());
+    scope.provide_context("outer");
+    let theme = scope.state("light");
+    scope.provide_context(theme);
+    crate::child_scope!(scope, |cx| {
+        cx.provide_context("inner");
+        assert_eq!(cx.use_context::<&str>(), Some("inner"));
+        assert_eq!(cx.use_context_signal::<&str>().unwrap().get(), "light");
+        assert_eq!(cx.use_context::<u32>(), None);
+    });
+    assert_eq!(scope.use_context::<&str>(), Some("outer"));
+}
+
+#[test]
+fn raw_round_trip() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let count = scope.state(7u32);
+    let raw = count.raw();
+    let same = unsafe { State::<u32>::from_raw(runtime, raw) };
+    same.set(8);
+    assert_eq!(count.get(), 8);
+}
+
+#[test]
+#[should_panic(expected = "the node does not hold a")]
+fn raw_wrong_type() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let raw = scope.state(7u32).raw();
+    unsafe { State::<String>::from_raw(runtime, raw) };
+}
+
+/// A state that may have been dropped, created with [`State::downgrade`]
+pub struct WeakState<T: 'static> {
+    state: State<T>,
+}
+
+impl<T: 'static> Clone for WeakState<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: 'static> Copy for WeakState<T> {}
+
+impl<T: 'static> WeakState<T> {
+    /// The state, or None if the scope that owned it was dropped. The node a dropped state used may hold a new state, so the generation of the node is checked as well
+    pub fn upgrade(&self) -> Option<State<T>> {
+        let runtime_alive = try_with_rt(self.state.runtime, |_| ()).is_ok();
+        (runtime_alive && self.state.raw.alive()).then_some(self.state)
+    }
+}
+
+#[test]
+fn upgrade_after_drop() {
+    let runtime = claim_rt();
+    let owner = crate::scope!(runtime);
+    let weak = owner.state(1).downgrade();
+    assert_eq!(weak.upgrade().map(|state| state.get()), Some(1));
+
+    drop(owner);
+    assert!(weak.upgrade().is_none());
+    // a new state in the same node doesn't bring it back
+    let scope = crate::scope!(runtime);
+    scope.state(2);
+    assert!(weak.upgrade().is_none());
+}
+
+/// Keeps a callback from [`State::subscribe`] subscribed. Dropping it unsubscribes
+pub struct Subscription {
+    // owns the effect that calls the callback
+    _scope: Scope,
+}
+
+#[test]
+fn subscription_stops_when_dropped() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let count = scope.state(0);
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let roots = Runtime::root_scopes(runtime);
+    let subscription = count.subscribe({
+        let seen = seen.clone();
+        move |count| seen.borrow_mut().push(*count)
+    });
+    // the subscription's own scope isn't a root scope
+    assert_eq!(Runtime::root_scopes(runtime), roots);
+    Runtime::flush(runtime);
+    assert!(seen.borrow().is_empty());
+
+    count.set(1);
+    Runtime::flush(runtime);
+    count.set(2);
+    Runtime::flush(runtime);
+    assert_eq!(*seen.borrow(), [1, 2]);
+
+    drop(subscription);
+    count.set(3);
+    Runtime::flush(runtime);
+    assert_eq!(*seen.borrow(), [1, 2]);
+
+    // a subscription that outlives the state is never called again
+    let owner = crate::scope!(runtime);
+    let orphan = owner.state(0);
+    let calls = Rc::new(Cell::new(0));
+    let _subscription = orphan.subscribe({
+        let calls = calls.clone();
+        move |_| calls.set(calls.get() + 1)
+    });
+    drop(owner);
+    scope.state(0).set(1);
+    Runtime::flush(runtime);
+    assert_eq!(calls.get(), 0);
+}
+
+#[test]
+fn pointer_is_stable() {
+    let scope = crate::scope!(claim_rt());
+    let list = scope.state(vec![1]);
+    let ptr = list.as_ptr();
+    list.set(Vec::with_capacity(100));
+    list.with_mut(|list| list.extend(0..100));
+    assert_eq!(list.as_ptr(), ptr);
+    assert_eq!(unsafe { (*ptr).len() }, 100);
+}
+
+#[test]
+fn debug_tree_shows_nesting() {
+    let scope = crate::scope!(claim_rt());
+    scope.state(0);
+    scope.state(1);
+    crate::child_scope!(scope, |cx| {
+        cx.state(2);
+        crate::child_scope!(cx, |cx| {
+            cx.state(3);
+            cx.state(4);
+        });
+        // the child isn't part of the tree until it is built
+        assert_eq!(cx.debug_tree(), "Scope(states=1)\n  Child(states=2)");
+    });
+    crate::child_scope!(scope, |_| ());
+    scope.state(5);
+
+    assert_eq!(
+        scope.debug_tree(),
+        "Scope(states=3)\n  Child(states=1)\n    Child(states=2)\n  Child(states=0)"
+    );
+
+    let children = scope.children.borrow_mut();
+    assert_eq!(scope.debug_tree(), "Scope(states=3)\n  busy");
+    drop(children);
+}
+
+#[test]
+fn state_in_parent_outlives_child() {
+    let scope = crate::scope!(claim_rt());
+    let (promoted, local) = crate::child_scope!(scope, |cx| {
+        (cx.state_in_parent(String::from("kept")), cx.state(0))
+    });
+    // drop the child without dropping the parent
+    drop(scope.children.take());
+    assert_eq!(local.try_get(), Err(QkError::ScopeDisposed));
+    assert_eq!(promoted.cloned(), "kept");
+
+    drop(scope);
+    assert_eq!(promoted.try_with(|_| ()), Err(QkError::ScopeDisposed));
+}
+
+impl State<bool> {
+    /// Flip the value
+    pub fn toggle(&self) {
+        self.with_mut(|value| *value = !*value)
+    }
 }
 
-impl<T: 'static> StateIO<T> for State<T> {
+impl<T: 'static> State<Vec<T>> {
+    /// A memo of the length of the list that only changes when items are added or removed. A state doesn't know the scope that owns it, so the memo is owned by `cx` and is dropped with it
+    pub fn len_signal(&self, cx: &Scope) -> Memo<usize> {
+        let list = *self;
+        cx.memo(move || list.with(|list| list.len()))
+    }
+}
+
+impl<T: 'static> Readable<T> for State<T> {
     fn with<U: 'static, F: FnOnce(&T) -> U>(&self, f: F) -> U {
+        read_source(self.raw);
+        self.peek(f)
+    }
+
+    fn peek<U: 'static, F: FnOnce(&T) -> U>(&self, f: F) -> U {
         unsafe {
             let r = self.raw.borrow::<T>();
             f(&*r)
         }
     }
+}
 
+impl<T: 'static> Writable<T> for State<T> {
     fn with_mut<F: FnOnce(&mut T) -> O, O>(&self, f: F) -> O {
+        assert!(
+            !self.raw.frozen(),
+            "attempted to write a frozen signal during render"
+        );
         unsafe {
             let mut r = self.raw.borrow_mut::<T>();
             f(&mut *r)
         }
     }
+
+    fn with_mut_maybe<F: FnOnce(&mut T) -> (bool, O), O>(&self, f: F) -> O {
+        assert!(
+            !self.raw.frozen(),
+            "attempted to write a frozen signal during render"
+        );
+        let (changed, r) = unsafe {
+            let mut r = self.raw.borrow_mut_silent::<T>();
+            f(&mut *r)
+        };
+        if changed {
+            self.raw.touch();
+        }
+        r
+    }
 }
 
-pub trait StateIO<T: 'static> {
-    fn with<U: 'static, F: FnOnce(&T) -> U>(&self, f: F) -> U;
-    fn with_mut<F: FnOnce(&mut T) -> O, O>(&self, f: F) -> O;
-    fn set(&self, value: T) {
-        self.with_mut(|x| *x = value)
+/// Mutably borrow each state in turn, passing its index in the slice along with the value
+///
+/// Panics if the same state appears more than once
+pub fn with_each_mut<T: 'static>(states: &[State<T>], f: impl Fn(usize, &mut T)) {
+    for (i, state) in states.iter().enumerate() {
+        assert!(
+            states[..i].iter().all(|other| other.raw != state.raw),
+            "the same state was passed to with_each_mut more than once"
+        );
+    }
+    for (i, state) in states.iter().enumerate() {
+        state.with_mut(|value| f(i, value));
     }
+}
+
+#[test]
+fn with_each_mut_updates_all() {
+    let scope = crate::scope!(claim_rt());
+    let states = [scope.state(0), scope.state(10), scope.state(20)];
+    with_each_mut(&states, |i, value| *value += i as i32 + 1);
+    assert_eq!(states.map(|state| state.get()), [1, 12, 23]);
+}
+
+#[test]
+#[should_panic]
+fn with_each_mut_rejects_duplicates() {
+    let scope = crate::scope!(claim_rt());
+    let state = scope.state(0);
+    let other = scope.state(0);
+    with_each_mut(&[state, other, state], |_, value| *value += 1);
+}
+
+#[test]
+#[should_panic(expected = "attempted to write a frozen signal during render")]
+fn write_frozen() {
+    let scope = crate::scope!(claim_rt());
+    let state = scope.state(0);
+    state.freeze();
+    assert_eq!(state.get(), 0);
+    state.set(1);
+}
+
+#[test]
+fn write_unfrozen() {
+    let scope = crate::scope!(claim_rt());
+    let state = scope.state(0);
+    state.freeze();
+    state.unfreeze();
+    state.set(1);
+    assert_eq!(state.get(), 1);
+}
+
+#[test]
+#[should_panic(expected = "tried to use a state after the scope that owns it was dropped")]
+fn use_after_scope_dropped() {
+    let scope = crate::scope!(claim_rt());
+    let state = crate::child_scope!(scope, |cx| cx.state(0));
+    assert_eq!(state.get(), 0);
+    drop(scope);
+    state.get();
+}
+
+/// Anything that can be read: [`State`], [`Mapped`], [`ReadMapped`] and [`Memo`]
+pub trait Readable<T: 'static> {
+    fn with<U: 'static, F: FnOnce(&T) -> U>(&self, f: F) -> U;
+    /// Read the value without making it a dependency of the memo being computed
+    fn peek<U: 'static, F: FnOnce(&T) -> U>(&self, f: F) -> U;
     fn get(&self) -> T
     where
         T: Sized + Copy,
     {
         self.with(|x| *x)
     }
+    fn cloned(&self) -> T
+    where
+        T: Sized + Clone,
+    {
+        self.with(|x| x.clone())
+    }
+}
+
+/// Anything that can be written: [`State`], [`Mapped`] and [`ClampedState`]. [`Memo`]s and [`ReadMapped`]s are read only
+///
+/// ```rust, compile_fail
+/// use qk::copy::{claim_rt, Readable, Scope, Writable};
+///
+/// fn reset<W: Writable<i32>>(w: &W) {
+///     w.set(0);
+/// }
+///
+/// let cx = Scope::new(claim_rt());
+/// let count = cx.state(1);
+/// let doubled = cx.memo(move || count.get() * 2);
+/// reset(&doubled);
+/// ```
+pub trait Writable<T: 'static> {
+    fn with_mut<F: FnOnce(&mut T) -> O, O>(&self, f: F) -> O;
+    /// Like [`Writable::with_mut`], but only notifies subscribers if the closure returns true
+    fn with_mut_maybe<F: FnOnce(&mut T) -> (bool, O), O>(&self, f: F) -> O {
+        self.with_mut(f).1
+    }
+    fn set(&self, value: T) {
+        self.with_mut(|x| *x = value)
+    }
+    fn update(&self, f: impl FnOnce(&mut T)) {
+        self.with_mut(f)
+    }
+}
+
+/// A state that can be both read and written
+pub trait StateIO<T: 'static>: Readable<T> + Writable<T> {
+    /// Exchange the values of two states and notify the subscribers of both
+    ///
+    /// Panics if both are the same state
+    fn swap(&self, other: &impl Writable<T>) {
+        self.with_mut(|value| other.with_mut(|other| std::mem::swap(value, other)))
+    }
+}
+
+impl<T: 'static, S: Readable<T> + Writable<T>> StateIO<T> for S {}
+
+#[test]
+fn swap_states() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let first = scope.state(String::from("first"));
+    let second = scope.state(String::from("second"));
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    for state in [first, second] {
+        let seen = seen.clone();
+        scope.effect(move || seen.borrow_mut().push(state.cloned()));
+    }
+    seen.borrow_mut().clear();
+
+    first.swap(&second);
+    assert_eq!(first.cloned(), "second");
+    assert_eq!(second.cloned(), "first");
+    Runtime::flush(runtime);
+    assert_eq!(*seen.borrow(), ["second", "first"]);
+}
+
+pub trait BoolStateExt: StateIO<bool> {
+    /// Flip the value
+    fn toggle(&self) {
+        self.with_mut(|value| *value = !*value)
+    }
+}
+
+impl<S: StateIO<bool>> BoolStateExt for S {}
+
+#[test]
+fn toggle() {
+    let scope = crate::scope!(claim_rt());
+    let flag = scope.state(false);
+    flag.toggle();
+    assert!(flag.get());
+    flag.toggle();
+    assert!(!flag.get());
+
+    let updates = std::cell::Cell::new(0);
+    let pair = scope.state((true, 0));
+    let mapped = pair.map(
+        |pair| &pair.0,
+        |pair| &mut pair.0,
+        || updates.set(updates.get() + 1),
+    );
+    mapped.toggle();
+    assert!(!mapped.get());
+    assert_eq!(updates.get(), 1);
+    mapped.toggle();
+    assert!(mapped.get());
+    assert_eq!(updates.get(), 2);
+}
+
+pub trait NumStateExt<T: num_traits::Num + Copy + 'static>: StateIO<T> {
+    fn increment(&self) {
+        self.add(T::one())
+    }
+
+    fn decrement(&self) {
+        self.sub(T::one())
+    }
+
+    fn add(&self, n: T) {
+        self.with_mut(|value| *value = *value + n)
+    }
+
+    fn sub(&self, n: T) {
+        self.with_mut(|value| *value = *value - n)
+    }
+}
+
+impl<T: num_traits::Num + Copy + 'static, S: StateIO<T>> NumStateExt<T> for S {}
+
+#[test]
+fn increment() {
+    let scope = crate::scope!(claim_rt());
+    let count = scope.state(0i32);
+    count.increment();
+    count.increment();
+    count.increment();
+    assert_eq!(count.get(), 3);
+    count.decrement();
+    count.add(10);
+    count.sub(4);
+    assert_eq!(count.get(), 8);
+}
+
+/// A state created by [`Scope::lazy_state`]. A lazy state that is never used never takes a slot in the runtime
+pub struct LazyState<'a, T: 'static> {
+    scope: &'a Scope,
+    init: Cell<Option<Box<dyn FnOnce() -> T>>>,
+    state: Cell<Option<State<T>>>,
+}
+
+impl<T: 'static> LazyState<'_, T> {
+    /// The underlying state, creating it if this is the first use
+    pub fn state(&self) -> State<T> {
+        match self.state.get() {
+            Some(state) => state,
+            None => {
+                let init = self.init.take().unwrap();
+                let state = self.scope.state(init());
+                self.state.set(Some(state));
+                state
+            }
+        }
+    }
+
+    /// Returns true if the state has been inserted into the runtime
+    pub fn is_created(&self) -> bool {
+        self.state.get().is_some()
+    }
+}
+
+impl<T: 'static> Readable<T> for LazyState<'_, T> {
+    fn with<U: 'static, F: FnOnce(&T) -> U>(&self, f: F) -> U {
+        self.state().with(f)
+    }
+
+    fn peek<U: 'static, F: FnOnce(&T) -> U>(&self, f: F) -> U {
+        self.state().peek(f)
+    }
+}
+
+impl<T: 'static> Writable<T> for LazyState<'_, T> {
+    fn with_mut<F: FnOnce(&mut T) -> O, O>(&self, f: F) -> O {
+        self.state().with_mut(f)
+    }
+}
+
+#[test]
+fn lazy_state() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let live = Runtime::live_states(runtime);
+
+    let unused = scope.lazy_state(|| vec![0; 100]);
+    let count = scope.lazy_state(|| 1);
+    assert_eq!(Runtime::live_states(runtime), live);
+    assert!(!unused.is_created());
+
+    assert_eq!(count.get(), 1);
+    assert_eq!(Runtime::live_states(runtime), live + 1);
+    count.set(2);
+    assert_eq!(count.get(), 2);
+    assert_eq!(Runtime::live_states(runtime), live + 1);
+    assert!(!unused.is_created());
 }
 
 pub struct Mapped<T: 'static, O: 'static, F, FMut, Up>
@@ -469,7 +2118,7 @@ where
     phantom: PhantomData<O>,
 }
 
-impl<T: 'static, O: 'static, F, FMut, Up> StateIO<O> for Mapped<T, O, F, FMut, Up>
+impl<T: 'static, O: 'static, F, FMut, Up> Readable<O> for Mapped<T, O, F, FMut, Up>
 where
     F: Fn(&T) -> &O,
     FMut: Fn(&mut T) -> &mut O,
@@ -479,9 +2128,893 @@ where
         self.inner.with(|x| f((self.f)(x)))
     }
 
+    fn peek<U: 'static, F2: FnOnce(&O) -> U>(&self, f: F2) -> U {
+        self.inner.peek(|x| f((self.f)(x)))
+    }
+}
+
+impl<T: 'static, O: 'static, F, FMut, Up> Writable<O> for Mapped<T, O, F, FMut, Up>
+where
+    F: Fn(&T) -> &O,
+    FMut: Fn(&mut T) -> &mut O,
+    Up: Fn(),
+{
     fn with_mut<F2: FnOnce(&mut O) -> O2, O2>(&self, f: F2) -> O2 {
         let r = self.inner.with_mut(|x| f((self.f_mut)(x)));
         (self.update)();
         r
     }
+
+    fn with_mut_maybe<F2: FnOnce(&mut O) -> (bool, O2), O2>(&self, f: F2) -> O2 {
+        let (changed, r) = self.inner.with_mut_maybe(|x| {
+            let (changed, r) = f((self.f_mut)(x));
+            (changed, (changed, r))
+        });
+        if changed {
+            (self.update)();
+        }
+        r
+    }
+}
+
+#[test]
+fn with_mut_maybe() {
+    let scope = crate::scope!(claim_rt());
+    let updates = std::cell::Cell::new(0);
+    let pair = scope.state((0, 0));
+    let mapped = pair.map(
+        |pair| &pair.0,
+        |pair| &mut pair.0,
+        || updates.set(updates.get() + 1),
+    );
+
+    let old = mapped.with_mut_maybe(|value| {
+        let old = *value;
+        if *value > 10 {
+            *value = 0;
+        }
+        (old != *value, old)
+    });
+    assert_eq!(old, 0);
+    assert_eq!(updates.get(), 0);
+
+    mapped.with_mut_maybe(|value| {
+        *value += 1;
+        (true, ())
+    });
+    assert_eq!(mapped.get(), 1);
+    assert_eq!(updates.get(), 1);
+}
+
+#[test]
+fn with_mut_maybe_only_reruns_effects_on_change() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let runs = Rc::new(Cell::new(0));
+    let count = scope.state(0);
+    let pair = scope.state((0, 0));
+    let first = pair.map(|pair| &pair.0, |pair| &mut pair.0, || {});
+    scope.effect({
+        let runs = runs.clone();
+        move || {
+            count.get();
+            pair.with(|_| ());
+            runs.set(runs.get() + 1);
+        }
+    });
+    assert_eq!(runs.get(), 1);
+
+    count.with_mut_maybe(|_| (false, ()));
+    first.with_mut_maybe(|_| (false, ()));
+    Runtime::flush(runtime);
+    assert_eq!(runs.get(), 1);
+
+    count.with_mut_maybe(|count| {
+        *count += 1;
+        (true, ())
+    });
+    Runtime::flush(runtime);
+    assert_eq!(runs.get(), 2);
+    first.with_mut_maybe(|first| {
+        *first += 1;
+        (true, ())
+    });
+    Runtime::flush(runtime);
+    assert_eq!(runs.get(), 3);
+}
+
+/// A read only projection created with [`State::map_ref`]
+///
+/// ```rust, compile_fail
+/// use qk::copy::{claim_rt, Scope, Writable};
+///
+/// let cx = Scope::new(claim_rt());
+/// let pair = cx.state((1, 2));
+/// let first = pair.map_ref(|pair| &pair.0);
+/// first.set(0);
+/// ```
+pub struct ReadMapped<T: 'static, O: 'static, F>
+where
+    F: Fn(&T) -> &O,
+{
+    inner: State<T>,
+    f: F,
+    phantom: PhantomData<O>,
+}
+
+impl<T: 'static, O: 'static, F> Readable<O> for ReadMapped<T, O, F>
+where
+    F: Fn(&T) -> &O,
+{
+    fn with<U: 'static, F2: FnOnce(&O) -> U>(&self, f: F2) -> U {
+        self.inner.with(|x| f((self.f)(x)))
+    }
+
+    fn peek<U: 'static, F2: FnOnce(&O) -> U>(&self, f: F2) -> U {
+        self.inner.peek(|x| f((self.f)(x)))
+    }
+}
+
+#[test]
+fn map_ref() {
+    let scope = crate::scope!(claim_rt());
+    let user = scope.state((String::from("qk"), 1));
+    let name = user.map_ref(|user| &user.0);
+    assert_eq!(name.cloned(), "qk");
+    assert_eq!(name.with(|name| name.len()), 2);
+
+    user.with_mut(|user| user.0.push_str("-rs"));
+    assert_eq!(name.cloned(), "qk-rs");
+}
+
+/// A state that stays within a range, created with [`State::clamped`]
+pub struct ClampedState<T: Ord + Copy + 'static> {
+    inner: State<T>,
+    min: T,
+    max: T,
+}
+
+impl<T: Ord + Copy + 'static> Clone for ClampedState<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Ord + Copy + 'static> Copy for ClampedState<T> {}
+
+impl<T: Ord + Copy + 'static> Readable<T> for ClampedState<T> {
+    fn with<U: 'static, F: FnOnce(&T) -> U>(&self, f: F) -> U {
+        self.inner.with(f)
+    }
+
+    fn peek<U: 'static, F: FnOnce(&T) -> U>(&self, f: F) -> U {
+        self.inner.peek(f)
+    }
+}
+
+impl<T: Ord + Copy + 'static> Writable<T> for ClampedState<T> {
+    fn with_mut<F: FnOnce(&mut T) -> O, O>(&self, f: F) -> O {
+        self.inner.with_mut(|value| {
+            let r = f(value);
+            *value = (*value).clamp(self.min, self.max);
+            r
+        })
+    }
+
+    fn with_mut_maybe<F: FnOnce(&mut T) -> (bool, O), O>(&self, f: F) -> O {
+        self.inner.with_mut_maybe(|value| {
+            let old = *value;
+            let (changed, r) = f(value);
+            *value = (*value).clamp(self.min, self.max);
+            (changed || *value != old, r)
+        })
+    }
+}
+
+#[test]
+fn clamped_state_stays_in_range() {
+    let scope = crate::scope!(claim_rt());
+    let volume = scope.state(150).clamped(0, 100);
+    assert_eq!(volume.get(), 100);
+
+    volume.set(40);
+    assert_eq!(volume.get(), 40);
+    volume.set(-5);
+    assert_eq!(volume.get(), 0);
+    volume.update(|volume| *volume += 250);
+    assert_eq!(volume.get(), 100);
+}
+
+#[test]
+fn clamping_an_in_range_state_does_not_notify() {
+    let runtime = claim_rt();
+    let cx = crate::scope!(runtime);
+    let runs = Rc::new(Cell::new(0));
+    let volume = cx.state(50);
+    cx.effect({
+        let runs = runs.clone();
+        move || {
+            volume.get();
+            runs.set(runs.get() + 1);
+        }
+    });
+    assert_eq!(runs.get(), 1);
+
+    let clamped = volume.clamped(0, 100);
+    Runtime::flush(runtime);
+    assert_eq!(runs.get(), 1);
+    clamped.set(150);
+    Runtime::flush(runtime);
+    assert_eq!(runs.get(), 2);
+    assert_eq!(volume.get(), 100);
+}
+
+#[test]
+#[should_panic]
+fn clamped_state_needs_an_ordered_range() {
+    let scope = crate::scope!(claim_rt());
+    scope.state(0).clamped(10, 1);
+}
+
+#[cfg(feature = "debug-signals")]
+thread_local! {
+    static CONTEXT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Describe the effect that is currently running, if any. This is used to add context to panic messages
+#[cfg(feature = "debug-signals")]
+pub fn signal_context() -> Option<String> {
+    CONTEXT.with(|context| context.borrow().clone())
+}
+
+/// The updaters of the memos read while computing a memo, so they can be brought up to date before checking its sources
+type Upstream = Vec<Box<dyn Fn() -> bool>>;
+
+thread_local! {
+    static OBSERVER: RefCell<Option<Upstream>> = const { RefCell::new(None) };
+}
+
+/// Run f, collecting the memos it reads
+fn track_memos<T>(f: impl FnOnce() -> T) -> (T, Upstream) {
+    let prev = OBSERVER.with(|observer| observer.replace(Some(Vec::new())));
+    let value = f();
+    let upstream = OBSERVER.with(|observer| observer.replace(prev));
+    (value, upstream.unwrap())
+}
+
+fn read_memo(update: impl Fn() -> bool + 'static) {
+    OBSERVER.with(|observer| {
+        if let Some(upstream) = &mut *observer.borrow_mut() {
+            upstream.push(Box::new(update));
+        }
+    });
+}
+
+/// The states read while running an effect or computing a memo along with their versions at the time
+type Sources = Vec<(NodeRef, usize)>;
+
+/// True if any of the states that are still alive changed since they were read
+fn sources_dirty(sources: &Sources) -> bool {
+    sources
+        .iter()
+        .any(|(raw, version)| raw.alive() && raw.version() != *version)
+}
+
+thread_local! {
+    static SOURCES: RefCell<Option<Sources>> = const { RefCell::new(None) };
+}
+
+/// Run f, collecting the states it reads
+fn track_sources<T>(f: impl FnOnce() -> T) -> (T, Sources) {
+    let prev = SOURCES.with(|sources| sources.replace(Some(Vec::new())));
+    let value = f();
+    let sources = SOURCES.with(|sources| sources.replace(prev));
+    (value, sources.unwrap())
+}
+
+fn read_source(raw: NodeRef) {
+    SOURCES.with(|sources| {
+        if let Some(sources) = &mut *sources.borrow_mut() {
+            sources.push((raw, raw.version()));
+        }
+    });
+}
+
+struct EffectData {
+    run: Rc<dyn Fn()>,
+    sources: Sources,
+    priority: i32,
+}
+
+impl EffectData {
+    /// The tags of the states the effect read, without duplicates
+    #[cfg(feature = "debug-signals")]
+    fn source_names(&self) -> String {
+        let mut seen: Vec<NodeRef> = Vec::new();
+        let mut names = Vec::new();
+        for (raw, _) in &self.sources {
+            if !seen.contains(raw) {
+                seen.push(*raw);
+                names.push(raw.tag().unwrap_or("<unnamed>"));
+            }
+        }
+        names.join(", ")
+    }
+
+    fn dirty(&self) -> bool {
+        sources_dirty(&self.sources)
+    }
+}
+
+pub struct Memo<T: 'static> {
+    value: State<T>,
+    compute: State<Box<dyn Fn() -> T>>,
+    upstream: State<Upstream>,
+    sources: State<Sources>,
+}
+
+impl<T: 'static> Clone for Memo<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: 'static> Copy for Memo<T> {}
+
+impl<T: PartialEq + 'static> Memo<T> {
+    /// Recompute the value if any state it read changed. Returns true if the value changed
+    ///
+    /// If the memo only reads other memos, those memos are updated first so the memo sees their new values
+    pub fn update(&self) -> bool {
+        self.upstream.peek(|upstream| {
+            for update in upstream {
+                update();
+            }
+        });
+        if !self.sources.peek(sources_dirty) {
+            return false;
+        }
+
+        let ((new, upstream), sources) =
+            track_sources(|| self.compute.peek(|compute| track_memos(compute)));
+        self.upstream.set(upstream);
+        self.sources.set(sources);
+        // only write when the value changed so effects reading the memo are not rerun
+        let changed = self.value.peek(|current| *current != new);
+        with_rt(self.value.runtime(), |runtime| {
+            runtime.count(|stats| {
+                stats.memo_recomputes += 1;
+                stats.memo_unchanged += usize::from(!changed);
+            })
+        });
+        if changed {
+            self.value.set(new);
+        }
+        changed
+    }
+}
+
+/// Reading a memo while computing another memo makes it a dependency of that memo
+impl<T: PartialEq + 'static> Readable<T> for Memo<T> {
+    fn with<U: 'static, F: FnOnce(&T) -> U>(&self, f: F) -> U {
+        let myself = *self;
+        read_memo(move || myself.update());
+        read_source(self.value.raw);
+        self.value.peek(f)
+    }
+
+    fn peek<U: 'static, F: FnOnce(&T) -> U>(&self, f: F) -> U {
+        self.value.peek(f)
+    }
+}
+
+#[test]
+fn readable() {
+    fn display<R: Readable<String>>(r: &R) -> String {
+        r.with(|value| format!("<{value}>"))
+    }
+
+    let scope = crate::scope!(claim_rt());
+    let name = scope.state(String::from("qk"));
+    let pair = scope.state((String::from("first"), 0));
+    let first = pair.map(|pair| &pair.0, |pair| &mut pair.0, || {});
+    let upper = scope.memo(move || name.with(|name| name.to_uppercase()));
+
+    assert_eq!(display(&name), "<qk>");
+    assert_eq!(display(&first), "<first>");
+    assert_eq!(display(&upper), "<QK>");
+    assert_eq!(upper.cloned(), "QK");
+    assert_eq!(first.peek(|first| first.len()), 5);
+}
+
+#[test]
+fn writable() {
+    fn reset<W: Writable<i32>>(w: &W) {
+        w.set(0);
+    }
+
+    let scope = crate::scope!(claim_rt());
+    let count = scope.state(5);
+    let pair = scope.state((3, 4));
+    let first = pair.map(|pair| &pair.0, |pair| &mut pair.0, || {});
+
+    reset(&count);
+    reset(&first);
+    assert_eq!(count.get(), 0);
+    assert_eq!(pair.get(), (0, 4));
+
+    count.update(|count| *count += 2);
+    assert_eq!(count.get(), 2);
+}
+
+#[test]
+fn subscriber_count_follows_effects() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let count = scope.state(0);
+    let unread = scope.state(0);
+    assert_eq!(Runtime::subscriber_count(runtime, count.raw()), 0);
+
+    scope.effect(move || {
+        count.get();
+        count.get();
+    });
+    crate::child_scope!(scope, |cx| cx.effect(move || {
+        count.get();
+    }));
+    assert_eq!(Runtime::subscriber_count(runtime, count.raw()), 2);
+    assert_eq!(Runtime::subscriber_count(runtime, unread.raw()), 0);
+
+    drop(scope.children.take());
+    assert_eq!(Runtime::subscriber_count(runtime, count.raw()), 1);
+}
+
+#[test]
+fn flush_runs_dependent_effects_once() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let ui = crate::logging::LoggingRenderer::new(crate::mock::MockRenderer::default());
+    Runtime::add_renderer(runtime, ui.clone());
+
+    let a = scope.state(0);
+    let b = scope.state(0);
+    let unrelated = scope.state(0);
+    let doubled = scope.memo(move || a.get() * 2);
+    let total = scope.state(0);
+
+    let runs = Rc::new(Cell::new([0; 3]));
+    let count = |runs: &Rc<Cell<[usize; 3]>>, i: usize| {
+        let mut current = runs.get();
+        current[i] += 1;
+        runs.set(current);
+    };
+    scope.effect({
+        let runs = runs.clone();
+        move || {
+            count(&runs, 0);
+            total.set(a.get() + b.get());
+        }
+    });
+    scope.effect({
+        let runs = runs.clone();
+        move || {
+            count(&runs, 1);
+            doubled.get();
+        }
+    });
+    // depends on a state written by the first effect
+    scope.effect({
+        let runs = runs.clone();
+        move || {
+            count(&runs, 2);
+            total.get();
+        }
+    });
+    assert_eq!(runs.get(), [1, 1, 1]);
+
+    a.set(1);
+    a.set(2);
+    b.set(3);
+    unrelated.set(1);
+    Runtime::flush(runtime);
+    assert_eq!(runs.get(), [2, 2, 2]);
+    assert_eq!(total.get(), 5);
+    assert_eq!(ui.log(), ["flush"]);
+
+    Runtime::flush(runtime);
+    unrelated.set(2);
+    Runtime::flush(runtime);
+    assert_eq!(runs.get(), [2, 2, 2]);
+}
+
+#[test]
+fn state_type_name() {
+    let scope = crate::scope!(claim_rt());
+    let ids = scope.state(vec![1u32, 2]);
+    assert!(ids.type_name().contains("Vec"));
+    assert!(ids.type_name().contains("u32"));
+    // the name doesn't need the value, so it works after the scope is dropped
+    drop(scope);
+    assert!(ids.type_name().contains("Vec"));
+}
+
+#[cfg(feature = "debug-signals")]
+#[test]
+fn named_states_in_graph_dump() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let counter = scope.state(0).named("counter");
+    let step = scope.state(1);
+    scope.effect(move || {
+        counter.get();
+        step.get();
+        counter.get();
+    });
+
+    assert_eq!(counter.tag(), Some("counter"));
+    assert_eq!(step.tag(), None);
+    assert_eq!(
+        Runtime::dump_graph(runtime),
+        "effect 0 reads counter, <unnamed>\n"
+    );
+}
+
+#[test]
+fn on_idle_waits_for_effects() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let count = scope.state(0);
+    let seen = Rc::new(Cell::new(0));
+    scope.effect({
+        let seen = seen.clone();
+        move || seen.set(count.get())
+    });
+
+    // nothing is pending, so the callback runs right away
+    let idle = Rc::new(Cell::new(0));
+    Runtime::on_idle(runtime, {
+        let idle = idle.clone();
+        move || idle.set(idle.get() + 1)
+    });
+    assert_eq!(idle.get(), 1);
+
+    count.set(1);
+    Runtime::on_idle(runtime, {
+        let idle = idle.clone();
+        move || {
+            idle.set(idle.get() + 1);
+            // writing from the callback reruns the effect before flush returns
+            count.set(2);
+        }
+    });
+    assert_eq!(idle.get(), 1);
+    Runtime::flush(runtime);
+    assert_eq!(idle.get(), 2);
+    assert_eq!(seen.get(), 2);
+
+    Runtime::flush(runtime);
+    assert_eq!(idle.get(), 2);
+}
+
+#[test]
+fn effects_are_dropped_in_reverse_order() {
+    struct Logged(&'static str, Rc<RefCell<Vec<&'static str>>>);
+    impl Drop for Logged {
+        fn drop(&mut self) {
+            self.1.borrow_mut().push(self.0);
+        }
+    }
+
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let count = scope.state(0);
+    for name in ["first", "second", "third"] {
+        let logged = Logged(name, log.clone());
+        scope.effect(move || {
+            let _ = (&logged, count.get());
+        });
+    }
+
+    drop(scope);
+    assert_eq!(*log.borrow(), ["third", "second", "first"]);
+}
+
+#[test]
+fn effect_once_runs_after_the_next_flush() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let count = scope.state(0);
+    let runs = Rc::new(RefCell::new(Vec::new()));
+    scope.effect_once({
+        let runs = runs.clone();
+        move || runs.borrow_mut().push(count.get())
+    });
+    let dropped = crate::scope!(runtime);
+    dropped.effect_once({
+        let runs = runs.clone();
+        move || runs.borrow_mut().push(-1)
+    });
+    drop(dropped);
+    assert!(runs.borrow().is_empty());
+
+    count.set(1);
+    Runtime::flush(runtime);
+    assert_eq!(*runs.borrow(), [1]);
+
+    // reading count didn't subscribe to it
+    for value in 2..5 {
+        count.set(value);
+        Runtime::flush(runtime);
+    }
+    assert_eq!(*runs.borrow(), [1]);
+}
+
+#[cfg(feature = "ssr")]
+#[test]
+fn client_only_effects_wait_for_hydration() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let count = scope.state(0);
+    let runs = Rc::new(RefCell::new(Vec::new()));
+    scope.effect_client_only({
+        let runs = runs.clone();
+        move || runs.borrow_mut().push(count.get())
+    });
+    let unmounted = crate::scope!(runtime);
+    unmounted.effect_client_only({
+        let runs = runs.clone();
+        move || runs.borrow_mut().push(-1)
+    });
+    drop(unmounted);
+
+    // rendering on the server never runs the effect
+    assert!(!Runtime::is_client(runtime));
+    count.set(1);
+    Runtime::flush(runtime);
+    assert!(runs.borrow().is_empty());
+
+    // only effects of scopes that are still mounted run on hydration
+    Runtime::hydrate(runtime);
+    assert_eq!(*runs.borrow(), [1]);
+    count.set(2);
+    Runtime::flush(runtime);
+    assert_eq!(*runs.borrow(), [1, 2]);
+
+    // after hydration they run right away
+    scope.effect_client_only({
+        let runs = runs.clone();
+        move || runs.borrow_mut().push(10)
+    });
+    assert_eq!(*runs.borrow(), [1, 2, 10]);
+    drop(scope);
+    drop_rt(runtime);
+}
+
+#[test]
+fn effects_run_by_priority() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let size = scope.state(0);
+    let order = Rc::new(RefCell::new(Vec::new()));
+    let effect = |name: &'static str| {
+        let order = order.clone();
+        move || {
+            size.with(|_| ());
+            order.borrow_mut().push(name);
+        }
+    };
+    scope.effect(effect("paint"));
+    scope.effect_with_priority(-1, effect("log"));
+    scope.effect_with_priority(10, effect("layout"));
+    scope.effect(effect("paint again"));
+    scope.effect_with_priority(10, effect("layout again"));
+    order.borrow_mut().clear();
+
+    size.set(1);
+    Runtime::flush(runtime);
+    assert_eq!(
+        *order.borrow(),
+        ["layout", "layout again", "paint", "paint again", "log"]
+    );
+}
+
+#[test]
+fn pause_until_resumed() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let first = scope.state(0);
+    let second = scope.state(0);
+    let sums = Rc::new(RefCell::new(Vec::new()));
+    scope.effect({
+        let sums = sums.clone();
+        move || sums.borrow_mut().push(first.get() + second.get())
+    });
+    let idle = Rc::new(Cell::new(false));
+
+    Runtime::pause(runtime);
+    first.set(1);
+    Runtime::flush(runtime);
+    // a nested pause only resumes its own pause
+    Runtime::pause(runtime);
+    second.set(2);
+    Runtime::resume(runtime);
+    Runtime::flush(runtime);
+    Runtime::on_idle(runtime, {
+        let idle = idle.clone();
+        move || idle.set(true)
+    });
+    assert!(Runtime::paused(runtime));
+    assert_eq!(*sums.borrow(), [0]);
+    assert!(!idle.get());
+
+    Runtime::resume(runtime);
+    assert!(!Runtime::paused(runtime));
+    assert_eq!(*sums.borrow(), [0, 3]);
+    assert!(idle.get());
+}
+
+#[test]
+#[should_panic = "not paused"]
+fn resume_without_pause() {
+    Runtime::resume(claim_rt());
+}
+
+#[test]
+fn count_reactive_work() {
+    let runtime = claim_rt();
+    Runtime::reset_stats(runtime);
+    let scope = crate::scope!(runtime);
+    let count = scope.state(0);
+    let parity = scope.memo(move || count.get() % 2);
+    scope.effect(move || {
+        parity.get();
+    });
+    Runtime::flush(runtime);
+    assert_eq!(
+        Runtime::stats(runtime),
+        ReactiveStats {
+            effect_runs: 1,
+            memo_recomputes: 0,
+            memo_unchanged: 0,
+        }
+    );
+
+    // the memo is recomputed, but the parity is the same so the effect is skipped
+    count.set(2);
+    Runtime::flush(runtime);
+    assert_eq!(
+        Runtime::stats(runtime),
+        ReactiveStats {
+            effect_runs: 1,
+            memo_recomputes: 1,
+            memo_unchanged: 1,
+        }
+    );
+
+    // the memo is recomputed and the parity changes, so the effect reruns
+    count.set(1);
+    Runtime::flush(runtime);
+    assert_eq!(
+        Runtime::stats(runtime),
+        ReactiveStats {
+            effect_runs: 2,
+            memo_recomputes: 2,
+            memo_unchanged: 1,
+        }
+    );
+
+    Runtime::reset_stats(runtime);
+    assert_eq!(Runtime::stats(runtime), ReactiveStats::default());
+}
+
+#[cfg(feature = "debug-signals")]
+#[test]
+fn signal_context_names_the_running_effect() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let counter = scope.state(0).named("counter");
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    scope.effect({
+        let seen = seen.clone();
+        move || {
+            counter.get();
+            seen.borrow_mut().push(signal_context());
+        }
+    });
+    counter.set(1);
+    Runtime::flush(runtime);
+
+    assert_eq!(signal_context(), None);
+    assert_eq!(
+        seen.borrow().last().cloned().flatten().as_deref(),
+        Some("while running effect 0 that last read counter")
+    );
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "signal created inside an effect; did you mean to create it outside?")]
+fn state_created_in_effect() {
+    let runtime = claim_rt();
+    Runtime::check_states_in_effects(runtime, true);
+    let scope = Rc::new(crate::scope!(runtime));
+    let count = scope.state(0);
+    scope.effect({
+        let scope = scope.clone();
+        move || {
+            scope.state(count.get());
+        }
+    });
+}
+
+#[test]
+fn state_created_outside_effect() {
+    let runtime = claim_rt();
+    Runtime::check_states_in_effects(runtime, true);
+    let scope = crate::scope!(runtime);
+    let count = scope.state(0);
+    scope.effect(move || {
+        count.get();
+    });
+    scope.state(1);
+    count.set(1);
+    Runtime::flush(runtime);
+    Runtime::check_states_in_effects(runtime, false);
+}
+
+#[test]
+fn chained_memos() {
+    let scope = crate::scope!(claim_rt());
+    let runs = std::rc::Rc::new(Cell::new(0));
+    let a = scope.state(1);
+    let b = scope.memo(move || a.get() / 10);
+    let c = scope.memo({
+        let runs = runs.clone();
+        move || {
+            runs.set(runs.get() + 1);
+            b.get() * 2
+        }
+    });
+    assert_eq!(c.get(), 0);
+    assert_eq!(runs.get(), 1);
+
+    a.set(5);
+    assert!(!c.update());
+    assert_eq!(runs.get(), 1);
+
+    a.set(25);
+    assert!(c.update());
+    assert_eq!(c.get(), 4);
+    assert_eq!(runs.get(), 2);
+
+    assert!(!c.update());
+    assert_eq!(runs.get(), 2);
+}
+
+#[test]
+fn len_signal_only_tracks_length() {
+    let runtime = claim_rt();
+    let scope = crate::scope!(runtime);
+    let list = scope.state(vec![1, 2]);
+    let len = list.len_signal(&scope);
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    scope.effect({
+        let seen = seen.clone();
+        move || seen.borrow_mut().push(len.get())
+    });
+    assert_eq!(*seen.borrow(), [2]);
+
+    list.with_mut(|list| list.push(3));
+    Runtime::flush(runtime);
+    assert_eq!(*seen.borrow(), [2, 3]);
+
+    // changing an item keeps the length, so the effect doesn't run
+    list.with_mut(|list| list[0] = 10);
+    Runtime::flush(runtime);
+    assert_eq!(*seen.borrow(), [2, 3]);
+
+    list.with_mut(|list| list.pop());
+    Runtime::flush(runtime);
+    assert_eq!(*seen.borrow(), [2, 3, 2]);
 }
@@ -1,12 +1,17 @@
 use std::{
+    any::TypeId,
     cell::{Cell, Ref, RefCell, RefMut},
+    collections::HashMap,
     ptr::NonNull,
 };
 
+use crate::error::{QkError, QkResult};
+
 #[derive(Debug)]
 pub(crate) struct NodeData {
     pub(crate) ptr: NonNull<()>,
-    pub(crate) drop: unsafe fn(*mut ()),
+    // The index of the drop function in the queue's shared drop functions
+    pub(crate) drop: u32,
 }
 
 #[derive(Debug)]
@@ -15,31 +20,137 @@ pub(crate) struct Node {
     data: RefCell<Option<NodeData>>,
     next: Cell<Option<&'static Node>>,
     generation: Cell<usize>,
+    frozen: Cell<bool>,
+    // Incremented every time the data is borrowed mutably
+    version: Cell<usize>,
+    #[cfg(feature = "debug-signals")]
+    tag: Cell<Option<&'static str>>,
 }
 
+/// A reference to a node in the runtime that holds the value of a state
 #[derive(Copy, Clone, Debug)]
-pub(crate) struct NodeRef {
+pub struct NodeRef {
     node: &'static Node,
     generation: usize,
 }
 
+impl PartialEq for NodeRef {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.node, other.node) && self.generation == other.generation
+    }
+}
+
+impl Eq for NodeRef {}
+
 impl NodeRef {
-    fn alive(&self) -> bool {
+    pub(crate) fn alive(&self) -> bool {
         self.generation == self.node.generation.get()
     }
 
+    fn assert_alive(&self) {
+        assert!(
+            self.alive(),
+            "tried to use a state after the scope that owns it was dropped"
+        );
+    }
+
+    pub(crate) fn set_frozen(&self, frozen: bool) {
+        self.assert_alive();
+        self.node.frozen.set(frozen);
+    }
+
+    pub(crate) fn frozen(&self) -> bool {
+        self.node.frozen.get()
+    }
+
+    pub(crate) fn version(&self) -> usize {
+        self.node.version.get()
+    }
+
+    /// Mark the value as changed so subscribers see a write made with [`NodeRef::borrow_mut_silent`]
+    pub(crate) fn touch(&self) {
+        self.assert_alive();
+        self.node
+            .version
+            .set(self.node.version.get().wrapping_add(1));
+    }
+
+    #[cfg(feature = "debug-signals")]
+    pub(crate) fn set_tag(&self, tag: &'static str) {
+        self.assert_alive();
+        self.node.tag.set(Some(tag));
+    }
+
+    #[cfg(feature = "debug-signals")]
+    pub(crate) fn tag(&self) -> Option<&'static str> {
+        self.node.tag.get()
+    }
+
+    /// The address of the value. It doesn't change until the node is removed
+    pub(crate) fn ptr(&self) -> *mut () {
+        self.assert_alive();
+        self.node.data.borrow().as_ref().unwrap().ptr.as_ptr()
+    }
+
     /// Safety: The caller must ensure that the type `T` is correct.
     pub(crate) unsafe fn borrow<T>(&self) -> Ref<T> {
-        assert!(self.alive());
+        self.assert_alive();
         let borrow = self.node.data.borrow();
         Ref::map(borrow, |data| unsafe {
             &*(data.as_ref().unwrap().ptr.as_ptr() as *mut T)
         })
     }
 
+    /// Safety: The caller must ensure that the type `T` is correct.
+    pub(crate) unsafe fn try_borrow<T>(&self) -> QkResult<Ref<'_, T>> {
+        if !self.alive() {
+            return Err(QkError::ScopeDisposed);
+        }
+        let borrow = self
+            .node
+            .data
+            .try_borrow()
+            .map_err(|_| QkError::BorrowConflict)?;
+        Ok(Ref::map(borrow, |data| unsafe {
+            &*(data.as_ref().unwrap().ptr.as_ptr() as *mut T)
+        }))
+    }
+
+    /// Safety: The caller must ensure that the type `T` is correct.
+    pub(crate) unsafe fn try_borrow_mut<T>(&self) -> QkResult<RefMut<'_, T>> {
+        if !self.alive() {
+            return Err(QkError::ScopeDisposed);
+        }
+        let borrow = self
+            .node
+            .data
+            .try_borrow_mut()
+            .map_err(|_| QkError::BorrowConflict)?;
+        self.node
+            .version
+            .set(self.node.version.get().wrapping_add(1));
+        Ok(RefMut::map(borrow, |data| unsafe {
+            &mut *(data.as_ref().unwrap().ptr.as_ptr() as *mut T)
+        }))
+    }
+
     /// Safety: The caller must ensure that the type `T` is correct.
     pub(crate) unsafe fn borrow_mut<T>(&self) -> RefMut<T> {
-        assert!(self.alive());
+        self.assert_alive();
+        self.node
+            .version
+            .set(self.node.version.get().wrapping_add(1));
+        let borrow = self.node.data.borrow_mut();
+        RefMut::map(borrow, |data| unsafe {
+            &mut *(data.as_ref().unwrap().ptr.as_ptr() as *mut T)
+        })
+    }
+
+    /// Like [`NodeRef::borrow_mut`], but the version isn't changed so subscribers don't see the write
+    ///
+    /// Safety: The caller must ensure that the type `T` is correct.
+    pub(crate) unsafe fn borrow_mut_silent<T>(&self) -> RefMut<'_, T> {
+        self.assert_alive();
         let borrow = self.node.data.borrow_mut();
         RefMut::map(borrow, |data| unsafe {
             &mut *(data.as_ref().unwrap().ptr.as_ptr() as *mut T)
@@ -50,6 +161,18 @@ impl NodeRef {
 #[derive(Default)]
 pub(crate) struct Queue {
     head: Cell<Option<&'static Node>>,
+    // The number of nodes that currently hold data
+    live: Cell<usize>,
+    // One drop function for each type stored in the queue, shared by every node of that type
+    drops: RefCell<Vec<unsafe fn(*mut ())>>,
+    drop_ids: RefCell<HashMap<TypeId, u32>>,
+    // The type each drop function drops, so the type of a node can be found from its drop id
+    drop_types: RefCell<Vec<TypeId>>,
+    // The number of live nodes of each type, indexed by drop id
+    live_types: RefCell<Vec<usize>>,
+    // Every node the queue has allocated, live or free. Only needed to drop a runtime, which only happens with `ssr`
+    #[cfg(feature = "ssr")]
+    nodes: RefCell<Vec<&'static Node>>,
 }
 
 impl Queue {
@@ -58,6 +181,7 @@ impl Queue {
     }
 
     pub(crate) fn insert_with(&self, f: impl FnOnce(NodeRef) -> NodeData) -> NodeRef {
+        self.live.set(self.live.get() + 1);
         match self.head.get() {
             Some(head) => {
                 let node = NodeRef {
@@ -70,6 +194,7 @@ impl Queue {
 
                 // create the node with a reference to the data that will panic if used
                 let data = f(node);
+                self.count_type(data.drop, 1);
 
                 // Insert the data into the node. It is now possible to access the data
                 *head.data.borrow_mut() = Some(data);
@@ -81,30 +206,130 @@ impl Queue {
                     data: RefCell::new(None),
                     next: Cell::new(None),
                     generation: Cell::new(0),
+                    frozen: Cell::new(false),
+                    version: Cell::new(0),
+                    #[cfg(feature = "debug-signals")]
+                    tag: Cell::new(None),
                 };
                 let node = Box::leak(Box::new(node));
+                #[cfg(feature = "ssr")]
+                self.nodes.borrow_mut().push(node);
                 let node = NodeRef {
                     node,
                     generation: 0,
                 };
                 let data = f(node);
+                self.count_type(data.drop, 1);
                 *node.node.data.borrow_mut() = Some(data);
                 node
             }
         }
     }
 
-    pub(crate) unsafe fn remove(&self, node: NodeRef) {
-        // invalidate the pointer by incrementing the generation
-        node.node.generation.set(node.generation + 1);
+    /// The id of the drop function for `T` to store in [`NodeData`]
+    pub(crate) fn drop_id<T: 'static>(&self) -> u32 {
+        *self
+            .drop_ids
+            .borrow_mut()
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                let mut drops = self.drops.borrow_mut();
+                drops.push(|value: *mut ()| unsafe {
+                    std::ptr::drop_in_place(value as *mut T);
+                });
+                self.drop_types.borrow_mut().push(TypeId::of::<T>());
+                (drops.len() - 1) as u32
+            })
+    }
+
+    /// The type of the value stored in the node
+    pub(crate) fn type_id(&self, node: NodeRef) -> TypeId {
+        node.assert_alive();
+        let drop = node.node.data.borrow().as_ref().unwrap().drop;
+        self.drop_types.borrow()[drop as usize]
+    }
+
+    fn count_type(&self, drop: u32, change: isize) {
+        let mut live_types = self.live_types.borrow_mut();
+        if live_types.len() <= drop as usize {
+            live_types.resize(drop as usize + 1, 0);
+        }
+        let count = &mut live_types[drop as usize];
+        *count = count.wrapping_add_signed(change);
+    }
+
+    /// The number of live nodes of each type that has any
+    pub(crate) fn live_types(&self) -> Vec<(TypeId, usize)> {
+        let drop_types = self.drop_types.borrow();
+        self.live_types
+            .borrow()
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(drop, count)| (drop_types[drop], *count))
+            .collect()
+    }
+
+    /// The number of distinct drop functions
+    #[cfg(test)]
+    pub(crate) fn drop_thunks(&self) -> usize {
+        self.drops.borrow().len()
+    }
+
+    pub(crate) fn live(&self) -> usize {
+        self.live.get()
+    }
+
+    /// The number of nodes that can be reused without allocating
+    #[cfg(test)]
+    pub(crate) fn free_nodes(&self) -> usize {
+        let mut count = 0;
+        let mut current = self.head.get();
+        while let Some(node) = current {
+            count += 1;
+            current = node.next.get();
+        }
+        count
+    }
+
+    /// Remove every live node, for example when the runtime that owns the queue is dropped
+    #[cfg(feature = "ssr")]
+    pub(crate) unsafe fn remove_all(&self) {
+        let live: Vec<_> = self
+            .nodes
+            .borrow()
+            .iter()
+            .filter(|node| node.data.borrow().is_some())
+            .map(|node| NodeRef {
+                node,
+                generation: node.generation.get(),
+            })
+            .collect();
+        self.remove_many(&live);
+    }
 
-        // drop the data
-        let mut data = node.node.data.borrow_mut();
-        let data = data.take().unwrap();
-        (data.drop)(data.ptr.as_ptr());
+    /// Remove every node in one pass. All of the nodes are invalidated and linked into the free list before any value is dropped, so a drop that removes other nodes sees a consistent list. The values are dropped in the reverse of the order the nodes are given in
+    pub(crate) unsafe fn remove_many(&self, nodes: &[NodeRef]) {
+        let mut removed = Vec::with_capacity(nodes.len());
+        let mut head = self.head.get();
+        for node in nodes {
+            node.node.generation.set(node.generation + 1);
+            node.node.frozen.set(false);
+            #[cfg(feature = "debug-signals")]
+            node.node.tag.set(None);
+            let data = node.node.data.borrow_mut().take().unwrap();
+            self.count_type(data.drop, -1);
+            removed.push(data);
+            node.node.next.set(head);
+            head = Some(node.node);
+        }
+        self.head.set(head);
+        self.live.set(self.live.get() - nodes.len());
 
-        // reinsert the node at the head of the list
-        node.node.next.set(self.head.get());
-        self.head.set(Some(node.node));
+        for data in removed.into_iter().rev() {
+            // dropping a value may register a new drop function, so the list is borrowed for each one
+            let drop = self.drops.borrow()[data.drop as usize];
+            drop(data.ptr.as_ptr());
+        }
     }
 }
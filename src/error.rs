@@ -0,0 +1,32 @@
+use std::fmt::Display;
+
+/// The ways reading or writing reactive state can fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QkError {
+    /// The runtime the state belongs to was dropped with [`crate::copy::drop_rt`]
+    RuntimeGone,
+    /// The state is already borrowed in a way that conflicts with this access
+    BorrowConflict,
+    /// The scope that owns the state was dropped
+    ScopeDisposed,
+    /// The state was written while it is [frozen](crate::copy::State::freeze)
+    Frozen,
+}
+
+impl Display for QkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QkError::RuntimeGone => write!(f, "tried to get a runtime that was dropped"),
+            QkError::BorrowConflict => write!(f, "the state is already borrowed"),
+            QkError::ScopeDisposed => write!(
+                f,
+                "tried to use a state after the scope that owns it was dropped"
+            ),
+            QkError::Frozen => write!(f, "attempted to write a frozen signal during render"),
+        }
+    }
+}
+
+impl std::error::Error for QkError {}
+
+pub type QkResult<T> = Result<T, QkError>;
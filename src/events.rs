@@ -1,4 +1,4 @@
-pub trait EventDescription<P: PlatformEvents> {
+pub trait EventDescription<P: PlatformEvents>: Copy {
     type EventType;
 
     const ID: u16;
@@ -0,0 +1,60 @@
+use crate::copy::{Memo, Readable, Scope};
+
+// The disabled state of the closest group around a scope, provided as context
+#[derive(Clone, Copy)]
+struct DisabledGroup(Memo<bool>);
+
+impl Scope {
+    /// Disable every form control created below this scope while `disabled` is true, like the `disabled` attribute of a `<fieldset>`. Groups can be nested, and the controls in a group are disabled if any group around them is
+    pub fn provide_disabled(&self, disabled: impl Readable<bool> + 'static) {
+        let outer = self.use_context::<DisabledGroup>();
+        let group = self
+            .memo(move || disabled.get() || outer.is_some_and(|DisabledGroup(outer)| outer.get()));
+        self.provide_context(DisabledGroup(group));
+    }
+
+    /// Whether a form control created in this scope is disabled. `own` is the control's own disabled attribute. Like HTML, a disabled group disables the control even if `own` is false
+    pub fn disabled(&self, own: impl Readable<bool> + 'static) -> Memo<bool> {
+        let group = self.use_context::<DisabledGroup>();
+        self.memo(move || own.get() || group.is_some_and(|DisabledGroup(group)| group.get()))
+    }
+}
+
+#[test]
+fn groups_disable_their_controls() {
+    use crate::copy::{claim_rt, Runtime, Writable};
+
+    let runtime = claim_rt();
+    let cx = crate::scope!(runtime);
+    let form_disabled = cx.state(false);
+    let address_disabled = cx.state(false);
+    let own = cx.state(false);
+    cx.provide_disabled(form_disabled);
+    let (name, street) = crate::child_scope!(cx, |cx| {
+        let name = cx.disabled(own);
+        let street = crate::child_scope!(cx, |cx| {
+            cx.provide_disabled(address_disabled);
+            cx.disabled(own)
+        });
+        (name, street)
+    });
+    let other = crate::scope!(runtime);
+    let outside = other.disabled(own);
+    assert!(!name.get() && !street.get() && !outside.get());
+
+    // the nested group only disables its own controls
+    address_disabled.set(true);
+    Runtime::flush(runtime);
+    assert!(!name.get() && street.get());
+
+    // controls that aren't disabled themselves are still disabled by their group
+    address_disabled.set(false);
+    form_disabled.set(true);
+    Runtime::flush(runtime);
+    assert!(name.get() && street.get() && !outside.get());
+
+    form_disabled.set(false);
+    own.set(true);
+    Runtime::flush(runtime);
+    assert!(name.get() && street.get() && outside.get());
+}
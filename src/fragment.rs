@@ -1,10 +1,19 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    rc::Rc,
+};
+
 use crate::{
     component::{ComponentState, DynComponentState},
+    copy::{Readable, Scope, State, Writable},
     prelude::{PlatformEvents, Renderer},
 };
 
 pub struct Fragment<R: Renderer<P>, P: PlatformEvents> {
     items: Vec<DynComponentState<R, P>>,
+    roots_version: usize,
 }
 
 impl<R, P> Fragment<R, P>
@@ -13,7 +22,10 @@ where
     P: PlatformEvents,
 {
     pub fn new(items: Vec<DynComponentState<R, P>>) -> Self {
-        Self { items }
+        Self {
+            items,
+            roots_version: 0,
+        }
     }
 
     pub fn update(
@@ -22,6 +34,7 @@ where
         parent: u32,
         ui: &mut R,
     ) {
+        let old_roots = self.roots();
         for old in self.items.drain(..) {
             old.remove(ui);
         }
@@ -29,6 +42,39 @@ where
         for new in &self.items {
             ui.append_all(parent, new.roots());
         }
+        if self.roots() != old_roots {
+            self.roots_version += 1;
+        }
+    }
+
+    /// Reconcile a list without keys by index. Existing items are passed to `update` with their new value in place, and items are only created or removed at the tail
+    pub fn update_unkeyed<T>(
+        &mut self,
+        iter: impl IntoIterator<Item = T>,
+        parent: u32,
+        ui: &mut R,
+        mut create: impl FnMut(T, &mut R) -> DynComponentState<R, P>,
+        mut update: impl FnMut(&DynComponentState<R, P>, T, &mut R),
+    ) {
+        let old_len = self.items.len();
+        let mut len = 0;
+        for value in iter {
+            match self.items.get(len) {
+                Some(item) => update(item, value, ui),
+                None => {
+                    let item = create(value, ui);
+                    ui.append_all(parent, item.roots());
+                    self.items.push(item);
+                }
+            }
+            len += 1;
+        }
+        for surplus in self.items.drain(len..) {
+            surplus.remove(ui);
+        }
+        if len != old_len {
+            self.roots_version += 1;
+        }
     }
 }
 
@@ -40,4 +86,631 @@ where
     fn roots(&self) -> Vec<u32> {
         self.items.iter().flat_map(|item| item.roots()).collect()
     }
+
+    fn roots_version(&self) -> usize {
+        self.roots_version
+            + self
+                .items
+                .iter()
+                .map(|item| item.roots_version())
+                .sum::<usize>()
+    }
+}
+
+/// Runs tasks later, after the event loop has had a chance to handle input and paint
+pub trait Spawner {
+    fn spawn(&self, task: Box<dyn FnOnce()>);
+}
+
+type CreateRow<R, P, T> =
+    Box<dyn FnMut(T, State<usize>, &Scope, &mut R) -> DynComponentState<R, P>>;
+
+/// A list rendered from a state holding a `Vec`. Items are used as their own keys: each item is rendered once into a row with its own scope, and the row is kept until the item is removed from the list. Create one with [`each!`](crate::each)
+pub struct Each<R, P, T>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+    T: 'static,
+{
+    list: State<Vec<T>>,
+    create: CreateRow<R, P, T>,
+    // the parent of the rows' scopes
+    owner: Scope,
+    rows: Vec<Row<R, P, T>>,
+    roots_version: usize,
+    chunks: Option<Chunks>,
+}
+
+// Limits how many rows are created in one update of a chunked list
+struct Chunks {
+    size: usize,
+    // the items that don't have a row yet
+    pending: State<usize>,
+    // a task that renders the next chunk is waiting to run
+    scheduled: bool,
+}
+
+struct Row<R, P, T>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    key: T,
+    index: State<usize>,
+    state: DynComponentState<R, P>,
+    // owns index and any state the row created
+    _scope: Scope,
+}
+
+impl<R, P, T> Each<R, P, T>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+    T: Clone + Eq + Hash + 'static,
+{
+    /// `create` renders the row of an item. It is passed the item, its index which is updated when the row moves, and the scope of the row
+    pub fn new(
+        list: State<Vec<T>>,
+        create: impl FnMut(T, State<usize>, &Scope, &mut R) -> DynComponentState<R, P> + 'static,
+    ) -> Self {
+        Self {
+            list,
+            create: Box::new(create),
+            owner: Scope::internal(list.runtime()),
+            rows: Vec::new(),
+            roots_version: 0,
+            chunks: None,
+        }
+    }
+
+    /// Create the scopes of the rows as children of `cx`, so the rows can use the contexts it provides
+    pub fn scoped(mut self, cx: &Scope) -> Self {
+        self.owner = cx.detached_child();
+        self
+    }
+
+    /// Create at most `size` new rows in each update, so rendering a large list can be spread over several tasks with [`Each::update_in_chunks`]. The items without a row yet are counted in a state owned by `cx`
+    pub fn chunked(mut self, cx: &Scope, size: usize) -> Self {
+        self.chunks = Some(Chunks {
+            size: size.max(1),
+            pending: cx.state(0),
+            scheduled: false,
+        });
+        self
+    }
+
+    /// The number of items that are still waiting for their row, which can be shown as progress. Always 0 unless the list is [chunked](Each::chunked)
+    pub fn pending(&self) -> usize {
+        self.chunks
+            .as_ref()
+            .map_or(0, |chunks| chunks.pending.get())
+    }
+
+    /// Update the list, and if it is chunked keep updating it in tasks from `spawner` until every item has a row. Each task reconciles with the current value of the list, so the list can change while it is rendered
+    pub fn update_in_chunks(
+        this: &Rc<RefCell<Self>>,
+        parent: u32,
+        mut ui: R,
+        spawner: impl Spawner + Clone + 'static,
+    ) where
+        R: Clone + 'static,
+        P: 'static,
+    {
+        let mut each = this.borrow_mut();
+        each.update(parent, &mut ui);
+        let Some(chunks) = &mut each.chunks else {
+            return;
+        };
+        if chunks.scheduled || chunks.pending.peek(|pending| *pending == 0) {
+            return;
+        }
+        chunks.scheduled = true;
+        // the spawner may run the task right away
+        drop(each);
+        let weak = Rc::downgrade(this);
+        spawner.clone().spawn(Box::new(move || {
+            // the list may have been dropped before the task ran
+            if let Some(this) = weak.upgrade() {
+                if let Some(chunks) = &mut this.borrow_mut().chunks {
+                    chunks.scheduled = false;
+                }
+                Self::update_in_chunks(&this, parent, ui, spawner);
+            }
+        }));
+    }
+
+    /// Reconcile the rows with the current value of the list. New items get a new row, the rows of removed items are removed and their scopes dropped, and rows that are kept are moved without being rendered again
+    pub fn update(&mut self, parent: u32, ui: &mut R) {
+        let items = self.list.cloned();
+        let old_roots = self.roots();
+
+        // duplicate items reuse their old rows in order
+        let mut old: HashMap<T, Vec<Row<R, P, T>>> = HashMap::new();
+        for row in self.rows.drain(..).rev() {
+            old.entry(row.key.clone()).or_default().push(row);
+        }
+
+        // a chunked list leaves out the new items past the size of the chunk until the next update
+        let mut budget = self
+            .chunks
+            .as_ref()
+            .map_or(usize::MAX, |chunks| chunks.size);
+        let mut pending = 0;
+        for item in items {
+            let i = self.rows.len();
+            let row = match old.get_mut(&item).and_then(Vec::pop) {
+                Some(row) => {
+                    if row.index.get() != i {
+                        row.index.set(i);
+                    }
+                    row
+                }
+                None if budget == 0 => {
+                    pending += 1;
+                    continue;
+                }
+                None => {
+                    budget -= 1;
+                    let scope = self.owner.detached_child();
+                    let index = scope.state(i);
+                    let state = (self.create)(item.clone(), index, &scope, ui);
+                    Row {
+                        key: item,
+                        index,
+                        state,
+                        _scope: scope,
+                    }
+                }
+            };
+            self.rows.push(row);
+        }
+
+        for removed in old.into_values().flatten() {
+            removed.state.remove(ui);
+        }
+        if let Some(chunks) = &self.chunks {
+            if chunks.pending.peek(|old| *old != pending) {
+                chunks.pending.set(pending);
+            }
+        }
+
+        let roots = self.roots();
+        if roots != old_roots {
+            ui.append_all(
+                parent,
+                roots[in_place(&old_roots, &roots)..].iter().copied(),
+            );
+            self.roots_version += 1;
+        }
+    }
+}
+
+/// How many of the new roots can stay where they are. Nodes can only be moved by appending them, so the roots that stay are the longest start of the new order that is already in order on the page, and every root after them is appended
+fn in_place(old: &[u32], new: &[u32]) -> usize {
+    let kept: HashSet<u32> = new.iter().copied().collect();
+    // the rows of removed items are already gone from the page
+    let mut on_page = old.iter().filter(|root| kept.contains(root));
+    new.iter()
+        .take_while(|root| on_page.any(|old| old == *root))
+        .count()
+}
+
+impl<R, P, T> ComponentState<R, P> for Each<R, P, T>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+    T: 'static,
+{
+    fn roots(&self) -> Vec<u32> {
+        self.rows.iter().flat_map(|row| row.state.roots()).collect()
+    }
+
+    fn roots_version(&self) -> usize {
+        self.roots_version
+            + self
+                .rows
+                .iter()
+                .map(|row| row.state.roots_version())
+                .sum::<usize>()
+    }
+}
+
+/// Render each item of a `State<Vec<T>>` with a component. The rows are cached by item, so only new items are rendered when the list changes. Call [`Each::update`] to reconcile the rows after the list is written.
+///
+/// The closure is passed the item and its index as a `State<usize>`. A third argument can be added to get the scope of the row:
+/// ```rust, ignore
+/// let rows = each!(todos, |todo, index| Row { todo, index });
+/// let rows = each!(todos, |todo, index, cx| Row { todo, selected: cx.state(false) });
+/// ```
+#[macro_export]
+macro_rules! each {
+    ($list:expr, |$item:pat_param, $index:pat_param| $row:expr) => {
+        $crate::each!($list, |$item, $index, _| $row)
+    };
+    ($list:expr, |$item:pat_param, $index:pat_param, $cx:pat_param| $row:expr) => {
+        $crate::fragment::Each::new($list, move |$item, $index, $cx, ui| {
+            $crate::component::DynComponentState::new($crate::component::Component::create(
+                $row, ui,
+            ))
+        })
+    };
+}
+
+#[test]
+fn conditional_roots() {
+    use crate::mock::{fixtures::Leaf, MockRenderer};
+
+    let mut ui = MockRenderer::default();
+    let shown = ui.node();
+    ui.create_element(shown, "div");
+
+    let mut conditional: Fragment<MockRenderer, MockRenderer> = Fragment::new(Vec::new());
+    assert!(conditional.roots().is_empty());
+    let version = conditional.roots_version();
+
+    conditional.update(
+        std::iter::once(DynComponentState::new(Leaf(shown))),
+        0,
+        &mut ui,
+    );
+    assert_eq!(conditional.roots(), [shown]);
+    assert_ne!(conditional.roots_version(), version);
+    let version = conditional.roots_version();
+
+    // the same roots don't count as a change
+    conditional.update(
+        std::iter::once(DynComponentState::new(Leaf(shown))),
+        0,
+        &mut ui,
+    );
+    assert_eq!(conditional.roots_version(), version);
+
+    conditional.update(std::iter::empty(), 0, &mut ui);
+    assert!(conditional.roots().is_empty());
+    assert_ne!(conditional.roots_version(), version);
+}
+
+#[test]
+fn unkeyed_list_only_changes_the_tail() {
+    use crate::mock::{fixtures::Leaf, MockOp, MockRenderer};
+
+    fn create(text: &str, ui: &mut MockRenderer) -> DynComponentState<MockRenderer, MockRenderer> {
+        let id = ui.node();
+        ui.create_text(id, text);
+        DynComponentState::new(Leaf(id))
+    }
+
+    fn update(
+        item: &DynComponentState<MockRenderer, MockRenderer>,
+        text: &str,
+        ui: &mut MockRenderer,
+    ) {
+        ui.set_text(item.roots()[0], text);
+    }
+
+    let mut ui = MockRenderer::default();
+    let mut list: Fragment<MockRenderer, MockRenderer> = Fragment::new(Vec::new());
+    list.update_unkeyed(["a", "b"], 0, &mut ui, create, update);
+    let first_two = list.roots();
+    let version = list.roots_version();
+    ui.take_ops();
+
+    list.update_unkeyed(["a", "b", "c", "d", "e"], 0, &mut ui, create, update);
+    assert_eq!(list.roots()[..2], first_two);
+    assert_ne!(list.roots_version(), version);
+    let ops = ui.take_ops();
+    assert_eq!(
+        ops.iter()
+            .filter(|op| matches!(op, MockOp::CreateText { .. }))
+            .count(),
+        3
+    );
+    assert_eq!(
+        ops.iter()
+            .filter(|op| matches!(op, MockOp::AppendChild { .. }))
+            .count(),
+        3
+    );
+    assert!(!ops.iter().any(|op| matches!(op, MockOp::Remove { .. })));
+
+    // shrinking removes the surplus nodes from the tail
+    list.update_unkeyed(["x"], 0, &mut ui, create, update);
+    assert_eq!(list.roots(), first_two[..1]);
+    let ops = ui.take_ops();
+    assert_eq!(
+        ops.iter()
+            .filter(|op| matches!(op, MockOp::Remove { .. }))
+            .count(),
+        4
+    );
+    assert!(ops.contains(&MockOp::SetText {
+        id: first_two[0],
+        text: "x".to_string()
+    }));
+}
+
+#[test]
+fn inserting_in_the_middle_renders_one_row() {
+    use crate::copy::{claim_rt, Runtime};
+    use crate::mock::{fixtures::Text, MockOp, MockRenderer};
+
+    let runtime = claim_rt();
+    let cx = crate::scope!(runtime);
+    let list = cx.state(vec!["a", "c"]);
+    let mut ui = MockRenderer::default();
+    let mut rows = crate::each!(list, |item, _index| Text(item));
+    rows.update(0, &mut ui);
+    let [a, c] = rows.roots()[..] else {
+        panic!("expected two rows");
+    };
+    let scopes = Runtime::root_scopes(runtime);
+    let states = Runtime::live_states(runtime);
+    let version = rows.roots_version();
+    ui.take_ops();
+
+    // the row's scope owns its index
+    list.with_mut(|list| list.insert(1, "b"));
+    rows.update(0, &mut ui);
+    assert_eq!(Runtime::live_states(runtime), states + 1);
+    assert_eq!(Runtime::root_scopes(runtime), scopes);
+    assert_eq!(rows.roots()[0], a);
+    assert_eq!(rows.roots()[2], c);
+    assert_ne!(rows.roots_version(), version);
+    let ops = ui.take_ops();
+    assert_eq!(
+        ops.iter()
+            .filter(|op| matches!(op, MockOp::CreateText { .. }))
+            .count(),
+        1
+    );
+
+    // removing a row drops its scope, and the rows after it don't move
+    list.with_mut(|list| list.remove(0));
+    rows.update(0, &mut ui);
+    assert_eq!(Runtime::live_states(runtime), states);
+    assert_eq!(ui.take_ops(), [MockOp::Remove { id: a }]);
+}
+
+#[test]
+fn scoped_rows_use_the_contexts_of_the_scope() {
+    use crate::copy::claim_rt;
+    use crate::mock::{fixtures::Text, MockRenderer};
+
+    let cx = crate::scope!(claim_rt());
+    cx.provide_context("dark");
+    let list = cx.state(vec![0]);
+    let mut ui = MockRenderer::default();
+    let theme = |cx: &Scope| Text(cx.use_context().unwrap_or("none"));
+    let mut unscoped = crate::each!(list, |_, _index, cx| theme(cx));
+    let mut scoped = crate::each!(list, |_, _index, cx| theme(cx)).scoped(&cx);
+    unscoped.update(0, &mut ui);
+    scoped.update(0, &mut ui);
+    assert_eq!(ui.html_of(unscoped.roots()[0]).unwrap(), "none");
+    assert_eq!(ui.html_of(scoped.roots()[0]).unwrap(), "dark");
+}
+
+#[test]
+fn moves_are_minimal() {
+    let moved = |old: &[u32], new: &[u32]| new.len() - in_place(old, new);
+    // removing the first row moves nothing
+    assert_eq!(moved(&[1, 2, 3, 4], &[2, 3, 4]), 0);
+    // new rows at the end are appended
+    assert_eq!(moved(&[1, 2], &[1, 2, 3]), 1);
+    // a new row in the middle is appended along with the rows after it
+    assert_eq!(moved(&[1, 3], &[1, 2, 3]), 2);
+    // the last row moving to the start only keeps that row in place
+    assert_eq!(moved(&[1, 2, 3], &[3, 1, 2]), 2);
+    assert_eq!(moved(&[1, 2, 3], &[2, 1, 3]), 2);
+    assert_eq!(moved(&[1, 2, 3], &[1, 3, 2]), 1);
+}
+
+#[test]
+fn chunked_list_renders_across_tasks() {
+    use crate::copy::claim_rt;
+    use crate::mock::{fixtures::Item, MockRenderer};
+
+    // runs the spawned tasks only when the test asks
+    type Task = Box<dyn FnOnce()>;
+
+    #[derive(Clone, Default)]
+    struct Tasks(Rc<RefCell<Vec<Task>>>);
+
+    impl Spawner for Tasks {
+        fn spawn(&self, task: Box<dyn FnOnce()>) {
+            self.0.borrow_mut().push(task);
+        }
+    }
+
+    impl Tasks {
+        fn run(&self) -> usize {
+            let tasks = std::mem::take(&mut *self.0.borrow_mut());
+            let count = tasks.len();
+            tasks.into_iter().for_each(|task| task());
+            count
+        }
+    }
+
+    fn text(keys: impl IntoIterator<Item = u32>) -> String {
+        keys.into_iter().map(|key| format!("{key},")).collect()
+    }
+
+    let cx = crate::scope!(claim_rt());
+    let list = cx.state((0..10).collect::<Vec<u32>>());
+    let ui = MockRenderer::default();
+    let tasks = Tasks::default();
+    let rows = Rc::new(RefCell::new(
+        crate::each!(list, |key, _index| Item(key)).chunked(&cx, 4),
+    ));
+
+    Each::update_in_chunks(&rows, 0, ui.clone(), tasks.clone());
+    assert_eq!(ui.text_of(0).unwrap(), text(0..4));
+    assert_eq!(rows.borrow().pending(), 6);
+    assert_eq!(tasks.run(), 1);
+    assert_eq!(ui.text_of(0).unwrap(), text(0..8));
+    assert_eq!(rows.borrow().pending(), 2);
+
+    // the list changes before the last chunk, and the next task renders the new list
+    list.with_mut(|list| {
+        list.remove(1);
+        list.insert(0, 20);
+        list.extend([10, 11]);
+    });
+    Each::update_in_chunks(&rows, 0, ui.clone(), tasks.clone());
+    assert_eq!(rows.borrow().pending(), 1);
+    // the task that was already waiting renders the rest, so updating didn't spawn another
+    assert_eq!(tasks.run(), 1);
+    assert_eq!(tasks.run(), 0);
+    assert_eq!(ui.text_of(0).unwrap(), text(list.cloned()));
+    assert_eq!(rows.borrow().pending(), 0);
+}
+
+/// An edit to a list of unique keys. Indices are wrapped to the length of the list when the edit is applied
+#[cfg(test)]
+#[derive(Debug, Clone)]
+enum ListEdit {
+    /// Insert a new key
+    Insert(usize),
+    Remove(usize),
+    Move(usize, usize),
+    /// Reorder the whole list with the seed
+    Shuffle(u64),
+}
+
+#[cfg(test)]
+impl ListEdit {
+    fn apply(&self, keys: &mut Vec<u32>, next_key: &mut u32) {
+        match *self {
+            ListEdit::Insert(at) => {
+                keys.insert(at % (keys.len() + 1), *next_key);
+                *next_key += 1;
+            }
+            ListEdit::Remove(at) if !keys.is_empty() => {
+                keys.remove(at % keys.len());
+            }
+            ListEdit::Move(from, to) if !keys.is_empty() => {
+                let key = keys.remove(from % keys.len());
+                keys.insert(to % (keys.len() + 1), key);
+            }
+            ListEdit::Shuffle(mut seed) => {
+                for i in (1..keys.len()).rev() {
+                    // xorshift, so a seed always gives the same order
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    keys.swap(i, seed as usize % (i + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Apply the edits one at a time through `Each` and check the rendered children after each one
+#[cfg(test)]
+fn check_keyed_edits(edits: &[ListEdit]) {
+    use crate::copy::claim_rt;
+    use crate::mock::{fixtures::Item, MockOp, MockRenderer};
+
+    let cx = crate::scope!(claim_rt());
+    let list = cx.state(Vec::new());
+    let mut ui = MockRenderer::default();
+    let mut rows = crate::each!(list, |key, _index| Item(key));
+    let mut next_key = 0;
+    for edit in edits {
+        let before = list.cloned();
+        let mut after = before.clone();
+        edit.apply(&mut after, &mut next_key);
+        list.set(after.clone());
+        ui.take_ops();
+        rows.update(0, &mut ui);
+        let ops = ui.take_ops();
+
+        let expected: String = after.iter().map(|key| format!("{key},")).collect();
+        assert_eq!(ui.text_of(0).unwrap(), expected, "after {edit:?}");
+        // only new keys are rendered and only removed keys are removed
+        let count = |f: fn(&MockOp) -> bool| ops.iter().filter(|op| f(op)).count();
+        let created = after.iter().filter(|key| !before.contains(key)).count();
+        let removed = before.iter().filter(|key| !after.contains(key)).count();
+        assert_eq!(
+            count(|op| matches!(op, MockOp::CreateText { .. })),
+            created,
+            "after {edit:?}"
+        );
+        assert_eq!(
+            count(|op| matches!(op, MockOp::Remove { .. })),
+            removed,
+            "after {edit:?}"
+        );
+        // the rows that stay are the longest start of the list that was already in order, and only the rows after them are appended
+        let in_order = after
+            .iter()
+            .map(|key| before.iter().position(|old| old == key))
+            .scan(None, |last, position| match position {
+                Some(position) if *last < Some(position) => {
+                    *last = Some(position);
+                    Some(())
+                }
+                _ => None,
+            })
+            .count();
+        let appended: Vec<u32> = ops
+            .iter()
+            .filter_map(|op| match op {
+                MockOp::AppendChild { child, .. } => Some(*child),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(appended, rows.roots()[in_order..], "after {edit:?}");
+    }
+}
+
+#[test]
+fn keyed_edit_edge_cases() {
+    use ListEdit::*;
+
+    // edits on an empty list do nothing
+    check_keyed_edits(&[Remove(3), Move(1, 2), Shuffle(7)]);
+    // moving a row to where it already is, and to either end
+    check_keyed_edits(&[
+        Insert(0),
+        Insert(1),
+        Insert(2),
+        Move(1, 1),
+        Move(0, 2),
+        Move(2, 0),
+    ]);
+    // a zero seed never changes, so the shuffle moves the first row to the end and only that row is appended
+    check_keyed_edits(&[Insert(0), Insert(1), Insert(2), Insert(3), Shuffle(0)]);
+    // moving a row to the end only appends that row, even though the rows before it changed position
+    check_keyed_edits(&[Insert(0), Insert(1), Insert(2), Insert(3), Move(1, 3)]);
+    // removing every row and building the list again
+    check_keyed_edits(&[
+        Insert(0),
+        Insert(0),
+        Remove(0),
+        Remove(0),
+        Insert(0),
+        Insert(5),
+    ]);
+}
+
+#[cfg(test)]
+use proptest::strategy::Strategy;
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn keyed_edits_keep_the_order(
+        edits in proptest::collection::vec(
+            proptest::prop_oneof![
+                (0..16usize).prop_map(ListEdit::Insert),
+                (0..16usize).prop_map(ListEdit::Remove),
+                (0..16usize, 0..16usize).prop_map(|(from, to)| ListEdit::Move(from, to)),
+                proptest::arbitrary::any::<u64>().prop_map(ListEdit::Shuffle),
+            ],
+            0..40,
+        )
+    ) {
+        check_keyed_edits(&edits);
+    }
 }
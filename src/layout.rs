@@ -0,0 +1,151 @@
+use crate::{
+    component::{Component, ComponentState, DynComponentState},
+    prelude::{PlatformEvents, Renderer},
+};
+
+type CreateChild<R, P> = Box<dyn FnOnce(&mut R) -> DynComponentState<R, P>>;
+
+/// Components passed to a [`Layout`] by name. The layout decides where each one goes with [`slot!`](crate::slot)
+pub struct Slots<R, P>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    pending: Vec<(&'static str, CreateChild<R, P>)>,
+    placed: Vec<DynComponentState<R, P>>,
+}
+
+impl<R, P> Default for Slots<R, P>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    fn default() -> Self {
+        Self {
+            pending: Vec::new(),
+            placed: Vec::new(),
+        }
+    }
+}
+
+impl<R, P> Slots<R, P>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fill the slot with the name. The child is only created when the layout places the slot
+    pub fn with<C>(mut self, name: &'static str, child: C) -> Self
+    where
+        C: Component<R, P> + 'static,
+        C::State: 'static,
+    {
+        self.pending.push((
+            name,
+            Box::new(move |ui| DynComponentState::new(child.create(ui))),
+        ));
+        self
+    }
+
+    /// Create a `<slot>` element holding the child passed for the name. A slot that was not passed stays empty, so optional parts of the layout render nothing
+    pub fn mount(&mut self, name: &'static str, ui: &mut R) -> u32 {
+        let id = ui.node();
+        ui.create_element(id, "slot");
+        ui.set_attribute(id, "name", name);
+        // the slot element shouldn't change the layout of the page
+        ui.set_style(id, "display", "contents");
+        if let Some(index) = self.pending.iter().position(|(slot, _)| *slot == name) {
+            let (_, create) = self.pending.remove(index);
+            let child = create(ui);
+            ui.append_all(id, child.roots());
+            self.placed.push(child);
+        }
+        id
+    }
+}
+
+/// Places several components into one shell, so pages can share a header or sidebar while passing their own content
+pub struct Layout<R, P>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    shell: DynComponentState<R, P>,
+    slots: Slots<R, P>,
+}
+
+impl<R, P> Layout<R, P>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    pub fn new<S>(
+        ui: &mut R,
+        mut slots: Slots<R, P>,
+        shell: impl FnOnce(&mut Slots<R, P>, &mut R) -> S,
+    ) -> Self
+    where
+        S: ComponentState<R, P> + 'static,
+    {
+        let shell = DynComponentState::new(shell(&mut slots, ui));
+        Self { shell, slots }
+    }
+
+    /// The names of the slots that were passed but never placed by the shell
+    pub fn unplaced(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.slots.pending.iter().map(|(name, _)| *name)
+    }
+}
+
+impl<R, P> ComponentState<R, P> for Layout<R, P>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    fn roots(&self) -> Vec<u32> {
+        self.shell.roots()
+    }
+
+    fn roots_version(&self) -> usize {
+        self.shell.roots_version()
+    }
+}
+
+/// Place a slot of a [`Layout`]: `{slot!(slots, header)}` inside the shell's `rsx!` puts the component passed as `header` there
+#[macro_export]
+macro_rules! slot {
+    ($slots:expr, $name:ident) => {
+        |ui| $slots.mount(stringify!($name), ui)
+    };
+}
+
+#[test]
+fn slots_are_placed_by_name() {
+    use crate::mock::{
+        fixtures::{Leaf, Text},
+        MockRenderer,
+    };
+
+    let mut ui = MockRenderer::default();
+    let slots = Slots::new()
+        .with("content", Text("body"))
+        .with("header", Text("title"));
+    let layout = Layout::new(&mut ui, slots, |slots, ui| {
+        let root = ui.node();
+        ui.create_element(root, "div");
+        let header = (slot!(slots, header))(&mut *ui);
+        let sidebar = (slot!(slots, sidebar))(&mut *ui);
+        let content = (slot!(slots, content))(&mut *ui);
+        ui.append_children(root, &[header, sidebar, content]);
+        Leaf(root)
+    });
+    assert_eq!(layout.unplaced().count(), 0);
+    let root = layout.roots()[0];
+    assert_eq!(
+        ui.html_of(root).unwrap(),
+        r#"<div><slot name="header">title</slot><slot name="sidebar"></slot><slot name="content">body</slot></div>"#
+    );
+}
@@ -0,0 +1,160 @@
+use crate::{
+    component::{Component, ComponentState, DynComponentState},
+    copy::{Readable, Runtime, RuntimeId, Scope, State},
+    prelude::{PlatformEvents, Renderer},
+};
+
+type CreateContent<R, P> = Box<dyn FnOnce(&Scope, &mut R) -> DynComponentState<R, P>>;
+
+/// Shows a placeholder until it scrolls into view, and only then creates its content, so off screen parts of a long page don't cost anything up front. Once the content is created it stays, even if it scrolls out of view again
+///
+/// When rendering on the server the content is created right away so it is in the html, and hydrating keeps it
+pub struct LazyMount<R, P>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    visible: State<bool>,
+    placeholder: DynComponentState<R, P>,
+    create: Option<CreateContent<R, P>>,
+    content: Option<(DynComponentState<R, P>, Scope)>,
+    roots_version: usize,
+}
+
+impl<R, P> LazyMount<R, P>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    /// The first root of the placeholder is observed, so it should be an element with a size that can intersect the viewport
+    pub fn new<C>(
+        cx: &Scope,
+        ui: &mut R,
+        placeholder: C,
+        content: impl FnOnce(&Scope, &mut R) -> DynComponentState<R, P> + 'static,
+    ) -> Self
+    where
+        C: Component<R, P>,
+        C::State: 'static,
+    {
+        let visible = cx.state(false);
+        let placeholder = DynComponentState::new(placeholder.create(ui));
+        let mut lazy = Self {
+            visible,
+            placeholder,
+            create: Some(Box::new(content)),
+            content: None,
+            roots_version: 0,
+        };
+        if !Runtime::is_client(cx.runtime()) {
+            lazy.mount(cx.runtime(), ui);
+        } else if let Some(&observed) = lazy.placeholder.roots().first() {
+            ui.observe_visible(
+                observed,
+                Box::new(move |intersecting| {
+                    // the scope that owns the state may be dropped before the placeholder is
+                    if intersecting {
+                        let _ = visible.try_set(true);
+                    }
+                }),
+            );
+        }
+        lazy
+    }
+
+    /// If the content was created
+    pub fn mounted(&self) -> bool {
+        self.content.is_some()
+    }
+
+    /// Swap the placeholder for the content once the placeholder was visible
+    pub fn update(&mut self, parent: u32, ui: &mut R) {
+        if self.mounted() || !self.visible.get() {
+            return;
+        }
+        self.mount(self.visible.runtime(), ui);
+        ui.append_all(parent, self.roots());
+    }
+
+    fn mount(&mut self, runtime: RuntimeId, ui: &mut R) {
+        let Some(create) = self.create.take() else {
+            return;
+        };
+        let scope = crate::scope!(runtime);
+        let content = create(&scope, ui);
+        self.placeholder.remove(ui);
+        self.content = Some((content, scope));
+        self.roots_version += 1;
+    }
+
+    fn shown(&self) -> &DynComponentState<R, P> {
+        match &self.content {
+            Some((content, _)) => content,
+            None => &self.placeholder,
+        }
+    }
+}
+
+impl<R, P> ComponentState<R, P> for LazyMount<R, P>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    fn roots(&self) -> Vec<u32> {
+        self.shown().roots()
+    }
+
+    fn roots_version(&self) -> usize {
+        self.roots_version + self.shown().roots_version()
+    }
+}
+
+#[test]
+fn content_waits_for_the_placeholder_to_be_visible() {
+    use crate::copy::claim_rt;
+    use crate::mock::{fixtures::Text, MockRenderer};
+    use std::{cell::Cell, rc::Rc};
+
+    let runtime = claim_rt();
+    let cx = crate::scope!(runtime);
+    let mut ui = MockRenderer::default();
+    let created = Rc::new(Cell::new(0));
+    let mut lazy = LazyMount::new(&cx, &mut ui, Text("placeholder"), {
+        let created = created.clone();
+        move |_, ui| {
+            created.set(created.get() + 1);
+            DynComponentState::new(Text("content").create(ui))
+        }
+    });
+    ui.append_all(0, lazy.roots());
+    if !Runtime::is_client(runtime) {
+        // rendering on the server creates the content right away, and hydrating keeps it
+        assert_eq!(ui.text_of(0).unwrap(), "content");
+        Runtime::hydrate(runtime);
+        lazy.update(0, &mut ui);
+        assert_eq!(created.get(), 1);
+        assert_eq!(ui.text_of(0).unwrap(), "content");
+        return;
+    }
+    let placeholder = lazy.roots()[0];
+    lazy.update(0, &mut ui);
+    assert_eq!(created.get(), 0);
+    assert_eq!(ui.text_of(0).unwrap(), "placeholder");
+
+    // another element scrolling into view doesn't mount it
+    ui.set_visible(placeholder + 1, true);
+    lazy.update(0, &mut ui);
+    assert!(!lazy.mounted());
+
+    ui.set_visible(placeholder, true);
+    lazy.update(0, &mut ui);
+    assert_eq!(created.get(), 1);
+    assert_eq!(ui.text_of(0).unwrap(), "content");
+
+    // scrolling away and back keeps the content
+    ui.set_visible(placeholder, false);
+    ui.set_visible(placeholder, true);
+    lazy.update(0, &mut ui);
+    assert_eq!(created.get(), 1);
+    assert_eq!(ui.text_of(0).unwrap(), "content");
+}
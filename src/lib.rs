@@ -1,11 +1,23 @@
-// pub mod copy;
-// pub mod copy_ll;
+pub mod binding;
+pub mod channel;
+pub mod class_list;
 pub mod component;
+pub mod copy;
+pub(crate) mod copy_ll;
+pub mod error;
 pub mod events;
+pub mod form;
 pub mod fragment;
+pub mod layout;
+pub mod lazy;
+pub mod logging;
+pub mod mock;
 pub mod prelude;
 pub mod renderer;
 pub(crate) mod slab;
+pub mod storage;
+pub mod suspense;
+pub mod tee;
 mod tracking;
 pub mod web;
 
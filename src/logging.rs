@@ -0,0 +1,253 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::events::{EventDescription, PlatformEvents};
+use crate::renderer::{Delegation, Leave, Renderer};
+
+/// A renderer that records a line for every operation before forwarding it to the inner renderer
+#[derive(Clone)]
+pub struct LoggingRenderer<R> {
+    pub inner: R,
+    log: Rc<RefCell<Vec<String>>>,
+}
+
+impl<R> LoggingRenderer<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            log: Default::default(),
+        }
+    }
+
+    /// All lines logged so far
+    pub fn log(&self) -> Vec<String> {
+        self.log.borrow().clone()
+    }
+
+    /// Print and clear all lines logged so far
+    pub fn print(&self) {
+        for line in self.log.borrow_mut().drain(..) {
+            println!("{line}");
+        }
+    }
+
+    fn push(&self, line: String) {
+        self.log.borrow_mut().push(line);
+    }
+}
+
+impl<P: PlatformEvents, R: Renderer<P>> Renderer<P> for LoggingRenderer<R> {
+    fn node(&mut self) -> u32 {
+        let id = self.inner.node();
+        self.push(format!("node {id}"));
+        id
+    }
+
+    fn append_all(&mut self, parent: u32, children: impl IntoIterator<Item = u32>) {
+        let children: Vec<u32> = children.into_iter().collect();
+        self.push(format!("append_all {parent} {children:?}"));
+        self.inner.append_all(parent, children)
+    }
+
+    fn set_attribute(&mut self, id: u32, name: &'static str, value: &str) {
+        self.push(format!("set_attribute {id} {name}={value:?}"));
+        self.inner.set_attribute(id, name, value)
+    }
+
+    fn set_attributes(&mut self, id: u32, attributes: &[(&'static str, &str)]) {
+        self.push(format!("set_attributes {id} {attributes:?}"));
+        self.inner.set_attributes(id, attributes)
+    }
+
+    fn set_style(&mut self, id: u32, name: &'static str, value: &str) {
+        self.push(format!("set_style {id} {name}={value:?}"));
+        self.inner.set_style(id, name, value)
+    }
+
+    fn set_server_attribute(&mut self, id: u32, name: &'static str, value: &str) {
+        self.push(format!("set_server_attribute {id} {name}={value:?}"));
+        self.inner.set_server_attribute(id, name, value)
+    }
+
+    fn set_value(&mut self, id: u32, value: &str) {
+        self.push(format!("set_value {id} {value:?}"));
+        self.inner.set_value(id, value)
+    }
+
+    fn set_selected(&mut self, id: u32, values: &[&str]) {
+        self.push(format!("set_selected {id} {values:?}"));
+        self.inner.set_selected(id, values)
+    }
+
+    fn create_element(&mut self, id: u32, tag: &'static str) {
+        self.push(format!("create_element {id} {tag}"));
+        self.inner.create_element(id, tag)
+    }
+
+    fn create_element_ns(&mut self, id: u32, tag: &'static str, namespace: &'static str) {
+        self.push(format!("create_element_ns {id} {tag} {namespace}"));
+        self.inner.create_element_ns(id, tag, namespace)
+    }
+
+    fn create_text(&mut self, id: u32, text: &str) {
+        self.push(format!("create_text {id} {text:?}"));
+        self.inner.create_text(id, text)
+    }
+
+    fn set_text(&mut self, id: u32, text: &str) {
+        self.push(format!("set_text {id} {text:?}"));
+        self.inner.set_text(id, text)
+    }
+
+    fn replace_data(&mut self, id: u32, offset: u32, count: u32, data: &str) -> bool {
+        self.push(format!("replace_data {id} {offset} {count} {data:?}"));
+        self.inner.replace_data(id, offset, count, data)
+    }
+
+    fn set_tag(&mut self, id: u32, tag: &str) {
+        self.push(format!("set_tag {id} {tag}"));
+        self.inner.set_tag(id, tag)
+    }
+
+    fn append_child(&mut self, parent: u32, child: u32) {
+        self.push(format!("append_child {parent} {child}"));
+        self.inner.append_child(parent, child)
+    }
+
+    fn append_children(&mut self, parent: u32, children: &[u32]) {
+        self.push(format!("append_children {parent} {children:?}"));
+        self.inner.append_children(parent, children)
+    }
+
+    fn clone_node(&mut self, id: u32, new_id: u32) {
+        self.push(format!("clone_node {id} {new_id}"));
+        self.inner.clone_node(id, new_id)
+    }
+
+    fn copy(&mut self, from: u32, to: u32) {
+        self.push(format!("copy {from} {to}"));
+        self.inner.copy(from, to)
+    }
+
+    fn first_child(&mut self, id: u32) {
+        self.push(format!("first_child {id}"));
+        self.inner.first_child(id)
+    }
+
+    fn next_sibling(&mut self, id: u32) {
+        self.push(format!("next_sibling {id}"));
+        self.inner.next_sibling(id)
+    }
+
+    fn remove(&mut self, id: u32) {
+        self.push(format!("remove {id}"));
+        self.inner.remove(id)
+    }
+
+    fn set_leave_transition(&mut self, id: u32, leave: Leave) {
+        self.push(format!("set_leave_transition {id} {leave:?}"));
+        self.inner.set_leave_transition(id, leave)
+    }
+
+    fn replace_with(&mut self, id: u32, with: u32) {
+        self.push(format!("replace_with {id} {with}"));
+        self.inner.replace_with(id, with)
+    }
+
+    fn return_node(&mut self, id: u32) {
+        self.push(format!("return_node {id}"));
+        self.inner.return_node(id)
+    }
+
+    fn add_listener<E: EventDescription<P>>(
+        &mut self,
+        id: u32,
+        event: E,
+        callback: Box<dyn FnMut(web_sys::Event)>,
+    ) {
+        self.push(format!("add_listener {id} {}", E::NAME));
+        self.inner.add_listener(id, event, callback)
+    }
+
+    fn delegate_handler<E: EventDescription<P>>(
+        &mut self,
+        event: E,
+        selector: &'static str,
+        handler: Box<dyn FnMut(web_sys::Event, String)>,
+    ) -> Delegation {
+        self.push(format!("delegate_handler {} {selector}", E::NAME));
+        self.inner.delegate_handler(event, selector, handler)
+    }
+
+    fn observe_attribute(&mut self, id: u32, name: &'static str, callback: Box<dyn FnMut(String)>) {
+        self.push(format!("observe_attribute {id} {name}"));
+        self.inner.observe_attribute(id, name, callback)
+    }
+
+    fn focus(&mut self, id: u32) {
+        self.push(format!("focus {id}"));
+        self.inner.focus(id)
+    }
+
+    fn set_scroll_top(&mut self, id: u32, top: f64) {
+        self.push(format!("set_scroll_top {id} {top}"));
+        self.inner.set_scroll_top(id, top)
+    }
+
+    fn observe_scroll(&mut self, id: u32, callback: Box<dyn FnMut(f64)>) {
+        self.push(format!("observe_scroll {id}"));
+        self.inner.observe_scroll(id, callback)
+    }
+
+    fn observe_visible(&mut self, id: u32, callback: Box<dyn FnMut(bool)>) {
+        self.push(format!("observe_visible {id}"));
+        self.inner.observe_visible(id, callback)
+    }
+
+    fn measure(&mut self, id: u32) -> Option<(f64, f64)> {
+        self.push(format!("measure {id}"));
+        self.inner.measure(id)
+    }
+
+    fn flush(&mut self) {
+        self.push("flush".to_string());
+        self.inner.flush()
+    }
+}
+
+#[test]
+fn logging_is_transparent() {
+    use crate::mock::MockRenderer;
+
+    fn render<R: Renderer<MockRenderer>>(ui: &mut R) {
+        let div = ui.node();
+        ui.create_element(div, "div");
+        let text = ui.node();
+        ui.create_text(text, "hello");
+        ui.append_child(div, text);
+        ui.set_attribute(div, "class", "greeting");
+        ui.set_text(text, "world");
+        ui.remove(text);
+    }
+
+    let plain = MockRenderer::default();
+    render(&mut plain.clone());
+
+    let mut logged = LoggingRenderer::new(MockRenderer::default());
+    render(&mut logged);
+
+    assert_eq!(logged.inner.ops(), plain.ops());
+    assert_eq!(
+        logged.log(),
+        [
+            "node 1",
+            "create_element 1 div",
+            "node 2",
+            "create_text 2 \"hello\"",
+            "append_child 1 2",
+            "set_attribute 1 class=\"greeting\"",
+            "set_text 2 \"world\"",
+            "remove 2",
+        ]
+    );
+}
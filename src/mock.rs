@@ -0,0 +1,1620 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::events::{EventDescription, PlatformEvents};
+use crate::renderer::{Delegation, Leave, Renderer};
+use crate::slab::IdSlab;
+
+/// An operation recorded by the [`MockRenderer`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockOp {
+    CreateElement {
+        id: u32,
+        tag: &'static str,
+    },
+    CreateElementNs {
+        id: u32,
+        tag: &'static str,
+        namespace: &'static str,
+    },
+    CreateText {
+        id: u32,
+        text: String,
+    },
+    SetAttribute {
+        id: u32,
+        name: &'static str,
+        value: String,
+    },
+    SetStyle {
+        id: u32,
+        name: &'static str,
+        value: String,
+    },
+    SetText {
+        id: u32,
+        text: String,
+    },
+    ReplaceData {
+        id: u32,
+        offset: u32,
+        count: u32,
+        data: String,
+    },
+    SetTag {
+        id: u32,
+        tag: String,
+    },
+    SetValue {
+        id: u32,
+        value: String,
+    },
+    SetSelected {
+        id: u32,
+        values: Vec<String>,
+    },
+    AppendChild {
+        parent: u32,
+        child: u32,
+    },
+    CloneNode {
+        id: u32,
+        new_id: u32,
+    },
+    Copy {
+        from: u32,
+        to: u32,
+    },
+    FirstChild {
+        id: u32,
+    },
+    NextSibling {
+        id: u32,
+    },
+    Remove {
+        id: u32,
+    },
+    ReplaceWith {
+        id: u32,
+        with: u32,
+    },
+    ReturnNode {
+        id: u32,
+    },
+    Focus {
+        id: u32,
+    },
+    AddListener {
+        id: u32,
+        event: &'static str,
+    },
+}
+
+type Listener = Box<dyn FnMut(web_sys::Event)>;
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+type DelegatedHandler = Box<dyn FnMut(web_sys::Event, String)>;
+
+type Observer = Box<dyn FnMut(String)>;
+
+type ScrollObserver = Box<dyn FnMut(f64)>;
+
+type VisibilityObserver = Box<dyn FnMut(bool)>;
+
+/// A synthetic event that can be dispatched with [`MockRenderer::dispatch`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockEvent {
+    pub node: u32,
+    pub name: &'static str,
+    /// The key that was pressed for keyboard events
+    pub key: Option<String>,
+    /// The new value of the input for input events
+    pub value: Option<String>,
+}
+
+/// Creates synthetic events for the [`MockRenderer`]
+pub struct MockEvents;
+
+impl MockEvents {
+    pub fn click(node: u32) -> MockEvent {
+        MockEvent {
+            node,
+            name: "click",
+            key: None,
+            value: None,
+        }
+    }
+
+    pub fn key(node: u32, key: &str) -> MockEvent {
+        MockEvent {
+            node,
+            name: "keydown",
+            key: Some(key.to_string()),
+            value: None,
+        }
+    }
+
+    pub fn input(node: u32, value: &str) -> MockEvent {
+        MockEvent {
+            node,
+            name: "input",
+            key: None,
+            value: Some(value.to_string()),
+        }
+    }
+}
+
+/// A renderer that records every operation instead of rendering. Useful for testing
+#[derive(Clone)]
+pub struct MockRenderer(Rc<RefCell<MockRendererInner>>);
+
+pub struct MockRendererInner {
+    /// The node each id currently points to
+    ids: IdSlab<Option<usize>>,
+    /// A minimal tree of the nodes that have been created so ops can be attributed to a subtree
+    nodes: Vec<MockNode>,
+    ops: Vec<MockOp>,
+    /// The node each op in `ops` applies to
+    op_nodes: Vec<Option<usize>>,
+    listeners: Vec<(u32, &'static str, Listener)>,
+    /// The delegated handlers with the id their [`Delegation`] removes them by
+    delegated: Vec<(u32, &'static str, &'static str, DelegatedHandler)>,
+    next_delegation: u32,
+    observers: Vec<(u32, &'static str, Observer)>,
+    scroll_observers: Vec<(u32, ScrollObserver)>,
+    visibility_observers: Vec<(u32, VisibilityObserver)>,
+    /// The event being dispatched
+    current_event: Option<MockEvent>,
+    /// Set when a handler stops the rest of the handlers for the current event
+    immediate_stopped: bool,
+    /// If attributes only meant for server rendered html are written
+    server: bool,
+    /// The time in milliseconds, moved forward with [`MockRenderer::advance`]
+    now: u64,
+    /// Removed nodes that stay attached until their leave transition ends, with the time it ends
+    leaving: Vec<(usize, u64)>,
+    /// The classes that start an animation, set with [`MockRenderer::animate_class`]
+    animated: Vec<String>,
+    focused: Option<usize>,
+    /// A node that was focused before it was added to the page
+    pending_focus: Option<usize>,
+}
+
+#[derive(Default)]
+struct MockNode {
+    parent: Option<usize>,
+    children: Vec<usize>,
+    tag: Option<String>,
+    attributes: Vec<(&'static str, String)>,
+    text: Option<String>,
+    scroll_top: f64,
+    leave: Option<Leave>,
+}
+
+impl MockNode {
+    /// Only simple selectors are supported: a tag, `.class` or `[attribute]`
+    fn matches(&self, selector: &str) -> bool {
+        let attribute = |name: &str| {
+            self.attributes
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        if let Some(class) = selector.strip_prefix('.') {
+            attribute("class").is_some_and(|classes| classes.split_whitespace().any(|c| c == class))
+        } else if let Some(name) = selector.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            attribute(name).is_some()
+        } else {
+            self.tag.as_deref() == Some(selector)
+        }
+    }
+}
+
+impl Default for MockRenderer {
+    fn default() -> Self {
+        let mut ids = IdSlab::default();
+
+        // the root node
+        ids.id(Some(0));
+
+        Self(Rc::new(RefCell::new(MockRendererInner {
+            ids,
+            nodes: vec![MockNode::default()],
+            ops: Vec::new(),
+            op_nodes: Vec::new(),
+            listeners: Vec::new(),
+            delegated: Vec::new(),
+            next_delegation: 0,
+            observers: Vec::new(),
+            scroll_observers: Vec::new(),
+            visibility_observers: Vec::new(),
+            current_event: None,
+            immediate_stopped: false,
+            server: false,
+            now: 0,
+            leaving: Vec::new(),
+            animated: Vec::new(),
+            focused: None,
+            pending_focus: None,
+        })))
+    }
+}
+
+impl MockRenderer {
+    /// A renderer that renders like a server does, so attributes set with [`Renderer::set_server_attribute`] are written
+    pub fn server() -> Self {
+        let renderer = Self::default();
+        renderer.0.borrow_mut().server = true;
+        renderer
+    }
+
+    /// All operations recorded so far
+    pub fn ops(&self) -> Vec<MockOp> {
+        self.0.borrow().ops.clone()
+    }
+
+    /// Remove and return all operations recorded so far
+    pub fn take_ops(&self) -> Vec<MockOp> {
+        let mut myself = self.0.borrow_mut();
+        myself.op_nodes.clear();
+        std::mem::take(&mut myself.ops)
+    }
+
+    /// The operations recorded so far on the node `root` points to and its descendants.
+    ///
+    /// Parentage is checked against the current tree, so ops on a node that was created elsewhere and then appended under `root` are included.
+    pub fn scoped(&self, root: u32) -> impl Iterator<Item = MockOp> {
+        let myself = self.0.borrow();
+        let root = myself.node_of(root);
+        let ops: Vec<_> = myself
+            .ops
+            .iter()
+            .zip(&myself.op_nodes)
+            .filter(|(_, node)| match (root, node) {
+                (Some(root), Some(node)) => myself.is_descendant(*node, root),
+                _ => false,
+            })
+            .map(|(op, _)| op.clone())
+            .collect();
+        ops.into_iter()
+    }
+
+    /// The first node in document order with the attribute set to `value`
+    pub fn find_by_attr(&self, name: &str, value: &str) -> Option<u32> {
+        let myself = self.0.borrow();
+        let node = myself.find(0, &|node| {
+            node.attributes
+                .iter()
+                .any(|(key, val)| *key == name && val == value)
+        })?;
+        let id = myself
+            .ids
+            .iter()
+            .find(|(_, pointer)| **pointer == Some(node))
+            .map(|(id, _)| id);
+        id
+    }
+
+    /// The text content of the node: its own text, or the text of its descendants joined together
+    pub fn text_of(&self, id: u32) -> Option<String> {
+        let myself = self.0.borrow();
+        let node = myself.node_of(id)?;
+        Some(myself.text_content(node))
+    }
+
+    /// The subtree of the node as html, the way a server would send it. Text is escaped but otherwise written verbatim, so whitespace is kept
+    pub fn html_of(&self, id: u32) -> Option<String> {
+        let myself = self.0.borrow();
+        let node = myself.node_of(id)?;
+        let mut html = String::new();
+        myself.write_html(node, &mut html);
+        Some(html)
+    }
+
+    /// The value of an attribute of the element the id points to
+    pub fn attribute_of(&self, id: u32, name: &str) -> Option<String> {
+        let myself = self.0.borrow();
+        let node = myself.node_of(id)?;
+        myself.nodes[node]
+            .attributes
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// The scroll position of the element the id points to
+    pub fn scroll_top_of(&self, id: u32) -> Option<f64> {
+        let myself = self.0.borrow();
+        let node = myself.node_of(id)?;
+        Some(myself.nodes[node].scroll_top)
+    }
+
+    /// The tag of the element the id points to
+    pub fn tag_of(&self, id: u32) -> Option<String> {
+        let myself = self.0.borrow();
+        let node = myself.node_of(id)?;
+        myself.nodes[node].tag.clone()
+    }
+
+    /// Run the listeners for the event that were registered on its node in the order they were added, then the delegated handlers whose selector matches the node or one of its ancestors, returning how many ran.
+    ///
+    /// The handlers receive a placeholder `web_sys::Event`. They can read the key or value with [`MockRenderer::current_event`]
+    pub fn dispatch(&self, event: MockEvent) -> usize {
+        // The listeners are taken out while they run so they can use the renderer
+        let mut listeners = {
+            let mut myself = self.0.borrow_mut();
+            myself.current_event = Some(event.clone());
+            std::mem::take(&mut myself.listeners)
+        };
+        let mut ran = 0;
+        for (id, name, callback) in &mut listeners {
+            if *id == event.node && *name == event.name {
+                callback(JsValue::UNDEFINED.unchecked_into());
+                ran += 1;
+                if self.0.borrow().immediate_stopped {
+                    break;
+                }
+            }
+        }
+        if !self.0.borrow().immediate_stopped {
+            ran += self.dispatch_delegated(&event);
+        }
+        let mut myself = self.0.borrow_mut();
+        myself.current_event = None;
+        myself.immediate_stopped = false;
+        listeners.append(&mut myself.listeners);
+        myself.listeners = listeners;
+        ran
+    }
+
+    fn dispatch_delegated(&self, event: &MockEvent) -> usize {
+        let mut delegated = std::mem::take(&mut self.0.borrow_mut().delegated);
+        let mut ran = 0;
+        for (_, name, selector, handler) in &mut delegated {
+            if *name != event.name {
+                continue;
+            }
+            let key = {
+                let myself = self.0.borrow();
+                // events outside of the root don't reach the handler
+                let mut node = myself
+                    .node_of(event.node)
+                    .filter(|node| myself.connected(*node));
+                while let Some(current) = node {
+                    if myself.nodes[current].matches(selector) {
+                        break;
+                    }
+                    node = myself.nodes[current].parent;
+                }
+                node.map(|node| {
+                    myself.nodes[node]
+                        .attributes
+                        .iter()
+                        .find(|(key, _)| *key == "data-key")
+                        .map(|(_, value)| value.clone())
+                        .unwrap_or_default()
+                })
+            };
+            if let Some(key) = key {
+                handler(JsValue::UNDEFINED.unchecked_into(), key);
+                ran += 1;
+            }
+        }
+        let mut myself = self.0.borrow_mut();
+        delegated.append(&mut myself.delegated);
+        myself.delegated = delegated;
+        ran
+    }
+
+    /// If the element the id points to has keyboard focus
+    pub fn has_focus(&self, id: u32) -> bool {
+        let myself = self.0.borrow();
+        myself.focused.is_some() && myself.focused == myself.node_of(id)
+    }
+
+    /// Make the class start an animation, so elements that leave with it stay until the next [`MockRenderer::advance`]. Like in the browser, a leave class without an animation removes the element right away
+    pub fn animate_class(&self, class: &str) {
+        self.0.borrow_mut().animated.push(class.to_string());
+    }
+
+    /// Move the clock forward, removing the nodes whose leave transition ended. Animations of leave classes end on the next call
+    pub fn advance(&self, millis: u64) {
+        let mut myself = self.0.borrow_mut();
+        myself.now += millis;
+        let now = myself.now;
+        let (ended, leaving) = std::mem::take(&mut myself.leaving)
+            .into_iter()
+            .partition(|(_, end)| *end <= now);
+        myself.leaving = leaving;
+        for (node, _) in ended {
+            myself.detach(node);
+        }
+    }
+
+    /// Change an attribute the way a third party script would, without recording an op. Observers of the attribute are called with the new value
+    pub fn set_attribute_externally(&self, id: u32, name: &'static str, value: &str) {
+        self.with_node(id, |node| {
+            match node.attributes.iter_mut().find(|(key, _)| *key == name) {
+                Some((_, old)) => *old = value.to_string(),
+                None => node.attributes.push((name, value.to_string())),
+            }
+        });
+        // The observers are taken out while they run so they can use the renderer
+        let mut observers = std::mem::take(&mut self.0.borrow_mut().observers);
+        for (observed, observed_name, callback) in &mut observers {
+            if *observed == id && *observed_name == name {
+                callback(value.to_string());
+            }
+        }
+        let mut myself = self.0.borrow_mut();
+        observers.append(&mut myself.observers);
+        myself.observers = observers;
+    }
+
+    /// Scroll an element the way a user would, without recording an op. Scroll observers of the element are called with the new position
+    pub fn scroll_externally(&self, id: u32, top: f64) {
+        self.with_node(id, |node| node.scroll_top = top);
+        // The observers are taken out while they run so they can use the renderer
+        let mut observers = std::mem::take(&mut self.0.borrow_mut().scroll_observers);
+        for (observed, callback) in &mut observers {
+            if *observed == id {
+                callback(top);
+            }
+        }
+        let mut myself = self.0.borrow_mut();
+        observers.append(&mut myself.scroll_observers);
+        myself.scroll_observers = observers;
+    }
+
+    /// Move an element into or out of the viewport. Visibility observers of the element are called with whether it is visible. Elements start outside of the viewport
+    pub fn set_visible(&self, id: u32, visible: bool) {
+        // The observers are taken out while they run so they can use the renderer
+        let mut observers = std::mem::take(&mut self.0.borrow_mut().visibility_observers);
+        for (observed, callback) in &mut observers {
+            if *observed == id {
+                callback(visible);
+            }
+        }
+        let mut myself = self.0.borrow_mut();
+        observers.append(&mut myself.visibility_observers);
+        myself.visibility_observers = observers;
+    }
+
+    /// Stop the handlers after the one that is running from receiving the current event, like `Event::stop_immediate_propagation`
+    pub fn stop_immediate_propagation(&self) {
+        self.0.borrow_mut().immediate_stopped = true;
+    }
+
+    /// The event currently being dispatched
+    pub fn current_event(&self) -> Option<MockEvent> {
+        self.0.borrow().current_event.clone()
+    }
+
+    fn push(&self, id: u32, op: MockOp) {
+        let mut myself = self.0.borrow_mut();
+        let node = myself.node_of(id);
+        myself.ops.push(op);
+        myself.op_nodes.push(node);
+    }
+
+    fn point(&self, id: u32, node: Option<usize>) {
+        if let Some(pointer) = self.0.borrow_mut().ids.get_mut(id) {
+            *pointer = node;
+        }
+    }
+
+    fn with_node(&self, id: u32, f: impl FnOnce(&mut MockNode)) {
+        let mut myself = self.0.borrow_mut();
+        if let Some(node) = myself.node_of(id) {
+            f(&mut myself.nodes[node]);
+        }
+    }
+
+    fn create(&self, id: u32) {
+        let node = self.0.borrow_mut().new_node();
+        self.point(id, Some(node));
+    }
+}
+
+impl MockRendererInner {
+    fn node_of(&self, id: u32) -> Option<usize> {
+        self.ids.get(id).copied().flatten()
+    }
+
+    fn new_node(&mut self) -> usize {
+        self.nodes.push(MockNode::default());
+        self.nodes.len() - 1
+    }
+
+    // If the node is part of the tree under the root
+    fn connected(&self, mut node: usize) -> bool {
+        while let Some(parent) = self.nodes[node].parent {
+            node = parent;
+        }
+        node == 0
+    }
+
+    // Appending a leaving node back keeps it on the page
+    fn cancel_leave(&mut self, node: usize) {
+        let Some(index) = self
+            .leaving
+            .iter()
+            .position(|(leaving, _)| *leaving == node)
+        else {
+            return;
+        };
+        self.leaving.remove(index);
+        if let Some(Leave::Class(class)) = &self.nodes[node].leave {
+            let class = class.clone();
+            if let Some((_, classes)) = self.nodes[node]
+                .attributes
+                .iter_mut()
+                .find(|(key, _)| *key == "class")
+            {
+                *classes = classes
+                    .split_whitespace()
+                    .filter(|c| *c != class)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+            }
+        }
+    }
+
+    fn detach(&mut self, node: usize) {
+        if let Some(parent) = self.nodes[node].parent.take() {
+            self.nodes[parent].children.retain(|child| *child != node);
+        }
+    }
+
+    fn deep_clone(&mut self, node: usize) -> usize {
+        let new = self.new_node();
+        self.nodes[new].tag = self.nodes[node].tag.clone();
+        self.nodes[new].attributes = self.nodes[node].attributes.clone();
+        self.nodes[new].text = self.nodes[node].text.clone();
+        for child in self.nodes[node].children.clone() {
+            let child = self.deep_clone(child);
+            self.nodes[child].parent = Some(new);
+            self.nodes[new].children.push(child);
+        }
+        new
+    }
+
+    // Search the subtree in document order
+    fn find(&self, node: usize, matches: &dyn Fn(&MockNode) -> bool) -> Option<usize> {
+        if matches(&self.nodes[node]) {
+            return Some(node);
+        }
+        self.nodes[node]
+            .children
+            .iter()
+            .find_map(|child| self.find(*child, matches))
+    }
+
+    fn write_html(&self, node: usize, html: &mut String) {
+        let node = &self.nodes[node];
+        if let Some(text) = &node.text {
+            html.push_str(&escape_html(text));
+            return;
+        }
+        if let Some(tag) = &node.tag {
+            html.push('<');
+            html.push_str(tag);
+            for (name, value) in &node.attributes {
+                html.push_str(&format!(" {name}=\"{}\"", escape_html(value)));
+            }
+            html.push('>');
+        }
+        for child in &node.children {
+            self.write_html(*child, html);
+        }
+        if let Some(tag) = &node.tag {
+            html.push_str(&format!("</{tag}>"));
+        }
+    }
+
+    fn text_content(&self, node: usize) -> String {
+        let node = &self.nodes[node];
+        match &node.text {
+            Some(text) => text.clone(),
+            None => node
+                .children
+                .iter()
+                .map(|child| self.text_content(*child))
+                .collect(),
+        }
+    }
+
+    fn is_descendant(&self, mut node: usize, root: usize) -> bool {
+        loop {
+            if node == root {
+                return true;
+            }
+            match self.nodes[node].parent {
+                Some(parent) => node = parent,
+                None => return false,
+            }
+        }
+    }
+}
+
+impl PlatformEvents for MockRenderer {
+    type AnimationEvent = MockEvent;
+    type BeforeUnloadEvent = MockEvent;
+    type CompositionEvent = MockEvent;
+    type DeviceMotionEvent = MockEvent;
+    type DeviceOrientationEvent = MockEvent;
+    type DragEvent = MockEvent;
+    type ErrorEvent = MockEvent;
+    type Event = MockEvent;
+    type FocusEvent = MockEvent;
+    type GamepadEvent = MockEvent;
+    type HashChangeEvent = MockEvent;
+    type InputEvent = MockEvent;
+    type KeyboardEvent = MockEvent;
+    type MessageEvent = MockEvent;
+    type MouseEvent = MockEvent;
+    type PageTransitionEvent = MockEvent;
+    type PointerEvent = MockEvent;
+    type PopStateEvent = MockEvent;
+    type PromiseRejectionEvent = MockEvent;
+    type SecurityPolicyViolationEvent = MockEvent;
+    type StorageEvent = MockEvent;
+    type SubmitEvent = MockEvent;
+    type TouchEvent = MockEvent;
+    type TransitionEvent = MockEvent;
+    type UiEvent = MockEvent;
+    type WheelEvent = MockEvent;
+    type ProgressEvent = MockEvent;
+}
+
+impl Renderer<MockRenderer> for MockRenderer {
+    fn node(&mut self) -> u32 {
+        self.0.borrow_mut().ids.id(None)
+    }
+
+    fn append_all(&mut self, parent: u32, children: impl IntoIterator<Item = u32>) {
+        for child in children.into_iter() {
+            self.append_child(parent, child);
+        }
+    }
+
+    fn set_attribute(&mut self, id: u32, name: &'static str, value: &str) {
+        self.with_node(id, |node| {
+            match node.attributes.iter_mut().find(|(key, _)| *key == name) {
+                Some((_, old)) => *old = value.to_string(),
+                None => node.attributes.push((name, value.to_string())),
+            }
+        });
+        self.push(
+            id,
+            MockOp::SetAttribute {
+                id,
+                name,
+                value: value.to_string(),
+            },
+        );
+    }
+
+    fn set_server_attribute(&mut self, id: u32, name: &'static str, value: &str) {
+        if self.0.borrow().server {
+            self.set_attribute(id, name, value);
+        }
+    }
+
+    fn set_style(&mut self, id: u32, name: &'static str, value: &str) {
+        self.push(
+            id,
+            MockOp::SetStyle {
+                id,
+                name,
+                value: value.to_string(),
+            },
+        );
+    }
+
+    fn set_value(&mut self, id: u32, value: &str) {
+        self.push(
+            id,
+            MockOp::SetValue {
+                id,
+                value: value.to_string(),
+            },
+        );
+    }
+
+    fn set_selected(&mut self, id: u32, values: &[&str]) {
+        self.push(
+            id,
+            MockOp::SetSelected {
+                id,
+                values: values.iter().map(|value| value.to_string()).collect(),
+            },
+        );
+    }
+
+    fn create_element(&mut self, id: u32, tag: &'static str) {
+        self.create(id);
+        self.with_node(id, |node| node.tag = Some(tag.to_string()));
+        self.push(id, MockOp::CreateElement { id, tag });
+    }
+
+    fn create_element_ns(&mut self, id: u32, tag: &'static str, namespace: &'static str) {
+        self.create(id);
+        self.with_node(id, |node| node.tag = Some(tag.to_string()));
+        self.push(id, MockOp::CreateElementNs { id, tag, namespace });
+    }
+
+    fn create_text(&mut self, id: u32, text: &str) {
+        self.create(id);
+        self.with_node(id, |node| node.text = Some(text.to_string()));
+        self.push(
+            id,
+            MockOp::CreateText {
+                id,
+                text: text.to_string(),
+            },
+        );
+    }
+
+    fn set_text(&mut self, id: u32, text: &str) {
+        self.with_node(id, |node| node.text = Some(text.to_string()));
+        self.push(
+            id,
+            MockOp::SetText {
+                id,
+                text: text.to_string(),
+            },
+        );
+    }
+
+    fn replace_data(&mut self, id: u32, offset: u32, count: u32, data: &str) -> bool {
+        self.with_node(id, |node| {
+            if let Some(text) = &mut node.text {
+                let mut units: Vec<u16> = text.encode_utf16().collect();
+                let start = offset as usize;
+                units.splice(start..start + count as usize, data.encode_utf16());
+                *text = String::from_utf16_lossy(&units);
+            }
+        });
+        self.push(
+            id,
+            MockOp::ReplaceData {
+                id,
+                offset,
+                count,
+                data: data.to_string(),
+            },
+        );
+        true
+    }
+
+    // The node keeps its children and attributes, so only the tag changes
+    fn set_tag(&mut self, id: u32, tag: &str) {
+        self.with_node(id, |node| node.tag = Some(tag.to_string()));
+        self.push(
+            id,
+            MockOp::SetTag {
+                id,
+                tag: tag.to_string(),
+            },
+        );
+    }
+
+    fn append_child(&mut self, parent: u32, child: u32) {
+        {
+            let mut myself = self.0.borrow_mut();
+            if let (Some(parent), Some(child)) = (myself.node_of(parent), myself.node_of(child)) {
+                myself.cancel_leave(child);
+                myself.detach(child);
+                myself.nodes[child].parent = Some(parent);
+                myself.nodes[parent].children.push(child);
+                if let Some(pending) = myself.pending_focus {
+                    if myself.connected(pending) {
+                        myself.focused = myself.pending_focus.take();
+                    }
+                }
+            }
+        }
+        self.push(child, MockOp::AppendChild { parent, child });
+    }
+
+    fn clone_node(&mut self, id: u32, new_id: u32) {
+        let node = self.0.borrow().node_of(id);
+        let new = node.map(|node| self.0.borrow_mut().deep_clone(node));
+        self.point(new_id, new);
+        self.push(new_id, MockOp::CloneNode { id, new_id });
+    }
+
+    fn copy(&mut self, from: u32, to: u32) {
+        let node = self.0.borrow().node_of(from);
+        self.point(to, node);
+        self.push(to, MockOp::Copy { from, to });
+    }
+
+    fn first_child(&mut self, id: u32) {
+        let child = {
+            let myself = self.0.borrow();
+            myself
+                .node_of(id)
+                .and_then(|node| myself.nodes[node].children.first().copied())
+        };
+        self.point(id, child);
+        self.push(id, MockOp::FirstChild { id });
+    }
+
+    fn next_sibling(&mut self, id: u32) {
+        let sibling = {
+            let myself = self.0.borrow();
+            myself.node_of(id).and_then(|node| {
+                let siblings = &myself.nodes[myself.nodes[node].parent?].children;
+                let idx = siblings.iter().position(|sibling| *sibling == node)?;
+                siblings.get(idx + 1).copied()
+            })
+        };
+        self.point(id, sibling);
+        self.push(id, MockOp::NextSibling { id });
+    }
+
+    fn remove(&mut self, id: u32) {
+        self.push(id, MockOp::Remove { id });
+        let mut myself = self.0.borrow_mut();
+        let Some(node) = myself.node_of(id) else {
+            return;
+        };
+        if myself.leaving.iter().any(|(leaving, _)| *leaving == node) {
+            return;
+        }
+        let end = match myself.nodes[node].leave.clone() {
+            Some(Leave::Millis(millis)) => myself.now + u64::from(millis),
+            Some(Leave::Class(class)) => {
+                let animated = myself.animated.contains(&class);
+                let attributes = &mut myself.nodes[node].attributes;
+                match attributes.iter_mut().find(|(key, _)| *key == "class") {
+                    Some((_, classes)) if !classes.is_empty() => {
+                        classes.push(' ');
+                        classes.push_str(&class);
+                    }
+                    Some((_, classes)) => *classes = class,
+                    None => attributes.push(("class", class)),
+                }
+                // nothing would end the leave
+                if !animated {
+                    myself.detach(node);
+                    return;
+                }
+                myself.now
+            }
+            None => {
+                myself.detach(node);
+                return;
+            }
+        };
+        myself.leaving.push((node, end));
+    }
+
+    fn set_leave_transition(&mut self, id: u32, leave: Leave) {
+        self.with_node(id, |node| node.leave = Some(leave));
+    }
+
+    fn focus(&mut self, id: u32) {
+        self.push(id, MockOp::Focus { id });
+        let mut myself = self.0.borrow_mut();
+        let Some(node) = myself.node_of(id) else {
+            return;
+        };
+        if myself.connected(node) {
+            myself.focused = Some(node);
+            myself.pending_focus = None;
+        } else {
+            myself.pending_focus = Some(node);
+        }
+    }
+
+    fn replace_with(&mut self, id: u32, with: u32) {
+        self.push(with, MockOp::ReplaceWith { id, with });
+        let mut myself = self.0.borrow_mut();
+        if let (Some(node), Some(new)) = (myself.node_of(id), myself.node_of(with)) {
+            myself.detach(new);
+            if let Some(parent) = myself.nodes[node].parent.take() {
+                let siblings = &mut myself.nodes[parent].children;
+                if let Some(index) = siblings.iter().position(|child| *child == node) {
+                    siblings[index] = new;
+                }
+                myself.nodes[new].parent = Some(parent);
+            }
+        }
+    }
+
+    fn return_node(&mut self, id: u32) {
+        self.push(id, MockOp::ReturnNode { id });
+        self.0.borrow_mut().ids.recycle(id);
+    }
+
+    fn add_listener<E: EventDescription<MockRenderer>>(
+        &mut self,
+        id: u32,
+        _: E,
+        callback: Box<dyn FnMut(web_sys::Event)>,
+    ) {
+        self.0.borrow_mut().listeners.push((id, E::NAME, callback));
+        self.push(id, MockOp::AddListener { id, event: E::NAME });
+    }
+
+    fn delegate_handler<E: EventDescription<MockRenderer>>(
+        &mut self,
+        _: E,
+        selector: &'static str,
+        handler: Box<dyn FnMut(web_sys::Event, String)>,
+    ) -> Delegation {
+        let mut myself = self.0.borrow_mut();
+        let id = myself.next_delegation;
+        myself.next_delegation += 1;
+        myself.delegated.push((id, E::NAME, selector, handler));
+        let renderer = Rc::downgrade(&self.0);
+        Delegation::new(move || {
+            if let Some(renderer) = renderer.upgrade() {
+                renderer
+                    .borrow_mut()
+                    .delegated
+                    .retain(|(delegation, ..)| *delegation != id);
+            }
+        })
+    }
+
+    fn observe_attribute(&mut self, id: u32, name: &'static str, callback: Box<dyn FnMut(String)>) {
+        self.0.borrow_mut().observers.push((id, name, callback));
+    }
+
+    // Scrolling isn't recorded as an op. Read the position with `scroll_top_of`
+    fn set_scroll_top(&mut self, id: u32, top: f64) {
+        self.with_node(id, |node| node.scroll_top = top);
+    }
+
+    fn observe_scroll(&mut self, id: u32, callback: Box<dyn FnMut(f64)>) {
+        self.0.borrow_mut().scroll_observers.push((id, callback));
+    }
+
+    // Nothing is visible until `set_visible` is called
+    fn observe_visible(&mut self, id: u32, callback: Box<dyn FnMut(bool)>) {
+        self.0
+            .borrow_mut()
+            .visibility_observers
+            .push((id, callback));
+    }
+}
+
+/// Components shared by the tests of the other modules
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::MockRenderer;
+    use crate::component::{Component, ComponentState};
+    use crate::renderer::Renderer;
+
+    /// The state of a component with a single root
+    pub(crate) struct Leaf(pub u32);
+
+    impl ComponentState<MockRenderer, MockRenderer> for Leaf {
+        fn roots(&self) -> Vec<u32> {
+            vec![self.0]
+        }
+    }
+
+    /// A text node
+    pub(crate) struct Text(pub &'static str);
+
+    impl Component<MockRenderer, MockRenderer> for Text {
+        type State = Leaf;
+
+        fn create(self, ui: &mut MockRenderer) -> Leaf {
+            let id = ui.node();
+            ui.create_text(id, self.0);
+            Leaf(id)
+        }
+
+        fn update(self, _: &mut Leaf) {}
+    }
+
+    /// A list row that renders its key followed by a comma
+    pub(crate) struct Item(pub u32);
+
+    impl Component<MockRenderer, MockRenderer> for Item {
+        type State = Leaf;
+
+        fn create(self, ui: &mut MockRenderer) -> Leaf {
+            let id = ui.node();
+            ui.create_text(id, &format!("{},", self.0));
+            Leaf(id)
+        }
+
+        fn update(self, _: &mut Leaf) {}
+    }
+}
+
+#[test]
+fn set_attributes_records_all_pairs() {
+    let mut ui = MockRenderer::default();
+    let div = ui.node();
+    ui.set_attributes(div, &[("id", "main"), ("class", "big"), ("title", "hi")]);
+    assert_eq!(
+        ui.ops(),
+        [
+            MockOp::SetAttribute {
+                id: div,
+                name: "id",
+                value: "main".to_string()
+            },
+            MockOp::SetAttribute {
+                id: div,
+                name: "class",
+                value: "big".to_string()
+            },
+            MockOp::SetAttribute {
+                id: div,
+                name: "title",
+                value: "hi".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn scoped_ops_only_include_the_subtree() {
+    let mut ui = MockRenderer::default();
+
+    // two sibling components under the root
+    let first = ui.node();
+    ui.create_element(first, "div");
+    let first_text = ui.node();
+    ui.create_text(first_text, "first");
+    ui.append_child(first, first_text);
+    ui.append_child(0, first);
+
+    let second = ui.node();
+    ui.create_element(second, "div");
+    ui.set_attribute(second, "class", "second");
+    ui.append_child(0, second);
+
+    // created outside the first component, then moved into it
+    let moved = ui.node();
+    ui.create_element(moved, "span");
+    ui.set_text(moved, "moved");
+    ui.append_child(first, moved);
+
+    assert_eq!(
+        ui.scoped(first).collect::<Vec<_>>(),
+        [
+            MockOp::CreateElement {
+                id: first,
+                tag: "div"
+            },
+            MockOp::CreateText {
+                id: first_text,
+                text: "first".to_string()
+            },
+            MockOp::AppendChild {
+                parent: first,
+                child: first_text
+            },
+            MockOp::AppendChild {
+                parent: 0,
+                child: first
+            },
+            MockOp::CreateElement {
+                id: moved,
+                tag: "span"
+            },
+            MockOp::SetText {
+                id: moved,
+                text: "moved".to_string()
+            },
+            MockOp::AppendChild {
+                parent: first,
+                child: moved
+            },
+        ]
+    );
+    assert_eq!(ui.scoped(second).count(), 3);
+    assert_eq!(ui.scoped(0).count(), ui.ops().len());
+}
+
+#[test]
+fn measure_has_no_layout() {
+    let mut ui = MockRenderer::default();
+    let div = ui.node();
+    ui.create_element(div, "div");
+    assert_eq!(ui.measure(div), None);
+    ui.append_child(0, div);
+    assert_eq!(ui.measure(div), None);
+}
+
+#[test]
+fn handlers_for_the_same_event_run_in_order() {
+    let mut ui = MockRenderer::default();
+    let button = ui.node();
+    ui.create_element(button, "button");
+
+    let calls = Rc::new(RefCell::new(Vec::new()));
+    let stop = Rc::new(std::cell::Cell::new(false));
+    ui.add_listener(button, crate::events::click, {
+        let ui = ui.clone();
+        let calls = calls.clone();
+        let stop = stop.clone();
+        Box::new(move |_| {
+            calls.borrow_mut().push("first");
+            if stop.get() {
+                ui.stop_immediate_propagation();
+            }
+        })
+    });
+    ui.add_listener(button, crate::events::click, {
+        let calls = calls.clone();
+        Box::new(move |_| calls.borrow_mut().push("second"))
+    });
+
+    assert_eq!(ui.dispatch(MockEvents::click(button)), 2);
+    assert_eq!(*calls.borrow(), ["first", "second"]);
+
+    calls.borrow_mut().clear();
+    stop.set(true);
+    assert_eq!(ui.dispatch(MockEvents::click(button)), 1);
+    assert_eq!(*calls.borrow(), ["first"]);
+
+    // the stop only lasts for the event it was called in
+    calls.borrow_mut().clear();
+    stop.set(false);
+    assert_eq!(ui.dispatch(MockEvents::click(button)), 2);
+    assert_eq!(*calls.borrow(), ["first", "second"]);
+}
+
+#[test]
+fn external_attribute_changes_are_observed() {
+    let mut ui = MockRenderer::default();
+    let input = ui.node();
+    ui.create_element(input, "input");
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    ui.observe_attribute(input, "value", {
+        let seen = seen.clone();
+        Box::new(move |value| seen.borrow_mut().push(value))
+    });
+
+    ui.set_attribute(input, "value", "ours");
+    ui.set_attribute_externally(input, "title", "other");
+    ui.set_attribute_externally(input, "value", "theirs");
+    assert_eq!(*seen.borrow(), ["theirs"]);
+    assert_eq!(ui.attribute_of(input, "value").as_deref(), Some("theirs"));
+}
+
+#[test]
+fn scroll_position_is_bound_both_ways() {
+    use crate::copy::{claim_rt, Readable, Runtime, Writable};
+
+    let runtime = claim_rt();
+    let cx = crate::scope!(runtime);
+    let top = cx.state(0.0);
+    let mut ui = MockRenderer::default();
+    let list = ui.node();
+    ui.create_element(list, "ul");
+    // what `bind:scroll_top` registers
+    let writes = Rc::new(std::cell::Cell::new(0));
+    ui.observe_scroll(list, {
+        let writes = writes.clone();
+        Box::new(move |scrolled| {
+            if top.get() != scrolled {
+                top.set(scrolled);
+                writes.set(writes.get() + 1);
+            }
+        })
+    });
+    cx.effect({
+        let ui = ui.clone();
+        move || ui.clone().set_scroll_top(list, top.get())
+    });
+
+    top.set(120.0);
+    Runtime::flush(runtime);
+    assert_eq!(ui.scroll_top_of(list), Some(120.0));
+
+    ui.scroll_externally(list, 40.0);
+    Runtime::flush(runtime);
+    assert_eq!((top.get(), writes.get()), (40.0, 1));
+    // the position written back from the state doesn't write the state again
+    ui.scroll_externally(list, 40.0);
+    assert_eq!(writes.get(), 1);
+}
+
+#[test]
+fn dispatch_synthetic_events() {
+    let mut ui = MockRenderer::default();
+    let button = ui.node();
+    ui.create_element(button, "button");
+    let input = ui.node();
+    ui.create_element(input, "input");
+
+    let clicks = Rc::new(std::cell::Cell::new(0));
+    ui.add_listener(button, crate::events::click, {
+        let clicks = clicks.clone();
+        Box::new(move |_| clicks.set(clicks.get() + 1))
+    });
+    let typed = Rc::new(RefCell::new(Vec::new()));
+    ui.add_listener(input, crate::events::input, {
+        let ui = ui.clone();
+        let typed = typed.clone();
+        Box::new(move |_| {
+            let event = ui.current_event().unwrap();
+            typed.borrow_mut().push(event.value.unwrap());
+        })
+    });
+
+    assert_eq!(ui.dispatch(MockEvents::click(button)), 1);
+    assert_eq!(clicks.get(), 1);
+    // no listener for the event on that node
+    assert_eq!(ui.dispatch(MockEvents::click(input)), 0);
+    assert_eq!(ui.dispatch(MockEvents::key(button, "Enter")), 0);
+    assert_eq!(clicks.get(), 1);
+
+    assert_eq!(ui.dispatch(MockEvents::input(input, "qk")), 1);
+    assert_eq!(*typed.borrow(), ["qk"]);
+    assert_eq!(ui.current_event(), None);
+}
+
+#[test]
+fn append_children_in_order() {
+    let mut ui = MockRenderer::default();
+    let list = ui.node();
+    ui.create_element(list, "ul");
+    ui.append_child(0, list);
+    let items: Vec<u32> = ["a", "b", "c"]
+        .into_iter()
+        .map(|name| {
+            let item = ui.node();
+            ui.create_text(item, name);
+            item
+        })
+        .collect();
+    ui.take_ops();
+
+    ui.append_children(list, &items);
+    assert_eq!(ui.text_of(list).as_deref(), Some("abc"));
+    let appended: Vec<MockOp> = items
+        .iter()
+        .map(|&child| MockOp::AppendChild {
+            parent: list,
+            child,
+        })
+        .collect();
+    assert_eq!(ui.take_ops(), appended);
+}
+
+#[test]
+fn replaced_node_takes_its_place() {
+    let mut ui = MockRenderer::default();
+    let list = ui.node();
+    ui.create_element(list, "p");
+    ui.append_child(0, list);
+    let children: Vec<u32> = ["a", " ", "c"]
+        .into_iter()
+        .map(|text| {
+            let child = ui.node();
+            ui.create_text(child, text);
+            ui.append_child(list, child);
+            child
+        })
+        .collect();
+    let custom = ui.node();
+    ui.create_text(custom, "b");
+
+    ui.replace_with(children[1], custom);
+    assert_eq!(ui.text_of(list).as_deref(), Some("abc"));
+    // the placeholder is detached but keeps its id until it is returned
+    assert_eq!(ui.text_of(children[1]).as_deref(), Some(" "));
+    ui.remove(list);
+    assert_eq!(ui.text_of(0).as_deref(), Some(""));
+}
+
+#[test]
+fn html_keeps_whitespace() {
+    let mut ui = MockRenderer::default();
+    let pre = ui.node();
+    ui.create_element(pre, "pre");
+    ui.set_attribute(pre, "class", "a \"b\"");
+    ui.append_child(0, pre);
+    let code = ui.node();
+    ui.create_text(code, "fn main() {\n    print(\"<hi>\");\n}\n");
+    ui.append_child(pre, code);
+    assert_eq!(
+        ui.html_of(pre).unwrap(),
+        "<pre class=\"a &quot;b&quot;\">fn main() {\n    print(&quot;&lt;hi&gt;&quot;);\n}\n</pre>"
+    );
+
+    // updating the text keeps the whitespace too
+    ui.set_text(code, "  a\n\n  b  ");
+    assert_eq!(
+        ui.html_of(pre).unwrap(),
+        "<pre class=\"a &quot;b&quot;\">  a\n\n  b  </pre>"
+    );
+}
+
+#[test]
+fn find_by_attr_and_text_of() {
+    let mut ui = MockRenderer::default();
+    let list = ui.node();
+    ui.create_element(list, "ul");
+    ui.append_child(0, list);
+    for (i, name) in ["first", "second"].into_iter().enumerate() {
+        let item = ui.node();
+        ui.create_element(item, "li");
+        ui.set_attribute(item, "data-testid", "item");
+        ui.set_attribute(item, "data-index", &i.to_string());
+        let text = ui.node();
+        ui.create_text(text, name);
+        ui.append_child(item, text);
+        ui.append_child(list, item);
+    }
+
+    // the first match in document order
+    let item = ui.find_by_attr("data-testid", "item").unwrap();
+    assert_eq!(ui.text_of(item).as_deref(), Some("first"));
+    let second = ui.find_by_attr("data-index", "1").unwrap();
+    assert_eq!(ui.text_of(second).as_deref(), Some("second"));
+    assert_eq!(ui.text_of(list).as_deref(), Some("firstsecond"));
+
+    ui.set_attribute(second, "data-index", "2");
+    assert_eq!(ui.find_by_attr("data-index", "1"), None);
+    assert_eq!(ui.find_by_attr("data-index", "2"), Some(second));
+}
+
+#[test]
+fn small_text_changes_replace_data() {
+    let mut ui = MockRenderer::default();
+    let log = ui.node();
+    let old = "line\n".repeat(100);
+    ui.create_text(log, &old);
+    ui.take_ops();
+
+    // change one line in the middle
+    let new = "line\n".repeat(50) + "LINE\n" + &"line\n".repeat(49);
+    ui.set_text_diff(log, &old, &new);
+    assert_eq!(
+        ui.take_ops(),
+        [MockOp::ReplaceData {
+            id: log,
+            offset: 250,
+            count: 4,
+            data: "LINE".to_string()
+        }]
+    );
+    assert_eq!(ui.text_of(log), Some(new));
+
+    // a completely different string replaces the whole text
+    ui.set_text_diff(log, &old, "cleared");
+    assert_eq!(
+        ui.take_ops(),
+        [MockOp::SetText {
+            id: log,
+            text: "cleared".to_string()
+        }]
+    );
+}
+
+#[test]
+fn switching_tags_keeps_children() {
+    let mut ui = MockRenderer::default();
+    let link = ui.node();
+    ui.create_element(link, "a");
+    ui.set_attribute(link, "data-testid", "action");
+    let label = ui.node();
+    ui.create_text(label, "save");
+    ui.append_child(link, label);
+    ui.append_child(0, link);
+    ui.take_ops();
+
+    ui.set_tag(link, "button");
+    assert_eq!(
+        ui.take_ops(),
+        [MockOp::SetTag {
+            id: link,
+            tag: "button".to_string()
+        }]
+    );
+    assert_eq!(ui.tag_of(link).as_deref(), Some("button"));
+    assert_eq!(ui.find_by_attr("data-testid", "action"), Some(link));
+    assert_eq!(ui.text_of(link).as_deref(), Some("save"));
+    assert_eq!(ui.text_of(label).as_deref(), Some("save"));
+}
+
+#[test]
+fn server_attributes_are_only_written_on_the_server() {
+    let mut server = MockRenderer::server();
+    let id = server.node();
+    server.create_element(id, "script");
+    server.set_server_attribute(id, "nonce", "abc");
+    assert_eq!(
+        server.html_of(id).unwrap(),
+        r#"<script nonce="abc"></script>"#
+    );
+
+    // hydrating the server html on the client leaves the attribute alone
+    let mut client = MockRenderer::default();
+    let id = client.node();
+    client.create_element(id, "script");
+    client.set_attribute(id, "nonce", "abc");
+    client.take_ops();
+    client.set_server_attribute(id, "nonce", "");
+    assert_eq!(client.attribute_of(id, "nonce").unwrap(), "abc");
+    assert!(client.ops().is_empty());
+}
+
+#[test]
+fn delegated_handler_runs_for_each_matching_row() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let mut ui = MockRenderer::default();
+    let clicked = Rc::new(RefCell::new(Vec::new()));
+    let delegation = ui.delegate_handler(crate::events::click, ".row", {
+        let clicked = clicked.clone();
+        Box::new(move |_, key| clicked.borrow_mut().push(key))
+    });
+
+    let list = ui.node();
+    ui.create_element(list, "ul");
+    ui.append_child(0, list);
+    let row = |ui: &mut MockRenderer, key: &str| {
+        let row = ui.node();
+        ui.create_element(row, "li");
+        ui.set_attributes(row, &[("class", "row"), ("data-key", key)]);
+        let label = ui.node();
+        ui.create_text(label, key);
+        ui.append_child(row, label);
+        ui.append_child(list, row);
+        (row, label)
+    };
+    let (first, _) = row(&mut ui, "1");
+    let (_, second_label) = row(&mut ui, "2");
+
+    assert_eq!(ui.dispatch(MockEvents::click(first)), 1);
+    // clicks on a child of the row are handled for the row
+    assert_eq!(ui.dispatch(MockEvents::click(second_label)), 1);
+    // rows added after the handler are handled without registering again
+    let (third, _) = row(&mut ui, "3");
+    assert_eq!(ui.dispatch(MockEvents::click(third)), 1);
+    // elements outside of a row are ignored
+    assert_eq!(ui.dispatch(MockEvents::click(list)), 0);
+    assert_eq!(*clicked.borrow(), ["1", "2", "3"]);
+
+    // a row that isn't inside the root doesn't match
+    let detached = ui.node();
+    ui.create_element(detached, "li");
+    ui.set_attribute(detached, "class", "row");
+    let child = ui.node();
+    ui.create_element(child, "span");
+    ui.append_child(detached, child);
+    assert_eq!(ui.dispatch(MockEvents::click(child)), 0);
+
+    // dropping the handle removes the handler
+    drop(delegation);
+    assert_eq!(ui.dispatch(MockEvents::click(first)), 0);
+    assert_eq!(*clicked.borrow(), ["1", "2", "3"]);
+}
+
+#[test]
+fn leave_transition_delays_removal() {
+    let mut ui = MockRenderer::default();
+    let list = ui.node();
+    ui.create_element(list, "ul");
+    let row = ui.node();
+    ui.create_element(row, "li");
+    ui.set_leave_transition(row, Leave::Millis(300));
+    ui.append_child(list, row);
+
+    ui.remove(row);
+    ui.advance(200);
+    assert_eq!(ui.html_of(list).unwrap(), "<ul><li></li></ul>");
+    // removing it again doesn't restart the transition
+    ui.remove(row);
+    ui.advance(100);
+    assert_eq!(ui.html_of(list).unwrap(), "<ul></ul>");
+
+    // showing it again before the transition ends keeps it
+    ui.append_child(list, row);
+    ui.remove(row);
+    ui.advance(250);
+    ui.append_child(list, row);
+    ui.advance(1000);
+    assert_eq!(ui.html_of(list).unwrap(), "<ul><li></li></ul>");
+
+    // a leave class stays until the animation ends
+    ui.animate_class("fade-out");
+    ui.set_leave_transition(row, Leave::from("fade-out"));
+    ui.remove(row);
+    assert_eq!(ui.attribute_of(row, "class").unwrap(), "fade-out");
+    assert_eq!(
+        ui.html_of(list).unwrap(),
+        r#"<ul><li class="fade-out"></li></ul>"#
+    );
+    ui.advance(0);
+    assert_eq!(ui.html_of(list).unwrap(), "<ul></ul>");
+
+    // a class without an animation would never end, so the element is removed right away
+    ui.append_child(list, row);
+    ui.set_leave_transition(row, Leave::from("hidden"));
+    ui.remove(row);
+    assert_eq!(ui.html_of(list).unwrap(), "<ul></ul>");
+}
+
+#[test]
+fn focus_when_signal_becomes_true() {
+    use crate::copy::{claim_rt, Readable, Runtime, Writable};
+
+    let runtime = claim_rt();
+    let cx = crate::scope!(runtime);
+    let invalid = cx.state(false);
+    let label = cx.state("name");
+    let mut ui = MockRenderer::default();
+    let input = ui.node();
+    ui.create_element(input, "input");
+    ui.append_child(0, input);
+    let other = ui.node();
+    ui.create_element(other, "input");
+    ui.append_child(0, other);
+    // the update `autofocus_when` generates, sharing the element's update with another attribute
+    let focus = crate::renderer::AutoFocus::default();
+    cx.effect({
+        let ui = ui.clone();
+        move || {
+            let mut ui = ui.clone();
+            ui.set_attribute(input, "aria-label", label.get());
+            if focus.rose(invalid.get()) {
+                ui.focus(input);
+            }
+        }
+    });
+    assert!(!ui.has_focus(input));
+
+    invalid.set(true);
+    Runtime::flush(runtime);
+    assert!(ui.has_focus(input));
+
+    // the user moves on, and rerunning the update while the field is still invalid leaves the focus alone
+    ui.focus(other);
+    label.set("full name");
+    Runtime::flush(runtime);
+    assert!(ui.has_focus(other));
+
+    // the field is focused again the next time it becomes invalid
+    invalid.set(false);
+    Runtime::flush(runtime);
+    assert!(ui.has_focus(other));
+    invalid.set(true);
+    Runtime::flush(runtime);
+    assert!(ui.has_focus(input));
+}
+
+#[test]
+fn focus_waits_for_the_element_to_be_added() {
+    let mut ui = MockRenderer::default();
+    let form = ui.node();
+    ui.create_element(form, "form");
+    let input = ui.node();
+    ui.create_element(input, "input");
+    ui.append_child(form, input);
+
+    ui.focus(input);
+    assert!(!ui.has_focus(input));
+    ui.append_child(0, form);
+    assert!(ui.has_focus(input));
+
+    let other = ui.node();
+    ui.create_element(other, "input");
+    ui.append_child(form, other);
+    ui.focus(other);
+    assert!(ui.has_focus(other));
+    assert!(!ui.has_focus(input));
+}
@@ -1,6 +1,10 @@
+pub use crate::error::{QkError, QkResult};
 pub use crate::events::PlatformEvents;
 pub use crate::launch;
 pub use crate::renderer::Renderer;
-pub use crate::tracking::{DirtyTrack, DirtyTrackSet, Effect, RwTrack};
+pub use crate::tracking::{
+    BoolDirtyTrack, DirtyTrack, DirtyTrackSet, Effect, RcSignal, RwTrack, TrackedField,
+    TrackedFields,
+};
 pub use crate::web::WebRenderer;
 pub use qk_macro::*;
@@ -7,16 +7,78 @@ pub trait Renderer<P: PlatformEvents>: Sized {
 
     fn set_attribute(&mut self, id: u32, name: &'static str, value: &str);
 
+    fn set_attributes(&mut self, id: u32, attributes: &[(&'static str, &str)]) {
+        for (name, value) in attributes {
+            self.set_attribute(id, name, value);
+        }
+    }
+
     fn set_style(&mut self, id: u32, name: &'static str, value: &str);
 
+    /// Set an attribute that only belongs in html rendered on the server, like a nonce or a hydration marker. Client renderers ignore it, so hydrating keeps the value the server wrote
+    fn set_server_attribute(&mut self, id: u32, name: &'static str, value: &str) {
+        let _ = (id, name, value);
+    }
+
+    /// Set the value of an input, textarea or select. Renderers without a value property write the `value` attribute
+    fn set_value(&mut self, id: u32, value: &str) {
+        self.set_attribute(id, "value", value);
+    }
+
+    /// Set the selected options of a select with the multiple attribute. Renderers without multiple selection select the first value
+    fn set_selected(&mut self, id: u32, values: &[&str]) {
+        self.set_value(id, values.first().copied().unwrap_or_default());
+    }
+
     fn create_element(&mut self, id: u32, tag: &'static str);
 
+    /// Create an element in a namespace, like svg. Renderers without namespaces create a plain element
+    fn create_element_ns(&mut self, id: u32, tag: &'static str, namespace: &'static str) {
+        let _ = namespace;
+        self.create_element(id, tag);
+    }
+
     fn create_text(&mut self, id: u32, text: &str);
 
     fn set_text(&mut self, id: u32, text: &str);
 
+    /// Replace `count` UTF-16 code units starting at `offset` in a text node with `data`, like `CharacterData.replaceData`. Returns false if the renderer can't edit text in place, so the caller sets the whole text instead
+    fn replace_data(&mut self, id: u32, offset: u32, count: u32, data: &str) -> bool {
+        let _ = (id, offset, count, data);
+        false
+    }
+
+    /// Replace an element with a new element with a different tag in the same place. The attributes, children, listeners and observers are moved to the new element, which `id` then points to. Renderers that can't replace elements keep the old tag
+    fn set_tag(&mut self, id: u32, tag: &str) {
+        let _ = (id, tag);
+    }
+
+    /// Update a text node from `old` to `new`, only replacing the part in the middle that changed. Strings that are mostly different are replaced entirely
+    fn set_text_diff(&mut self, id: u32, old: &str, new: &str) {
+        match text_diff(old, new) {
+            TextDiff::Unchanged => {}
+            TextDiff::Replace {
+                offset,
+                count,
+                data,
+            } => {
+                if !self.replace_data(id, offset, count, data) {
+                    self.set_text(id, new);
+                }
+            }
+            TextDiff::Full => self.set_text(id, new),
+        }
+    }
+
     fn append_child(&mut self, parent: u32, child: u32);
 
+    /// Append the children in order. Renderers that can append many nodes in one call override this
+    fn append_children(&mut self, parent: u32, children: &[u32]) {
+        for &child in children {
+            self.append_child(parent, child);
+        }
+    }
+
     fn clone_node(&mut self, id: u32, new_id: u32);
 
     fn copy(&mut self, from: u32, to: u32);
@@ -27,8 +89,43 @@ pub trait Renderer<P: PlatformEvents>: Sized {
 
     fn remove(&mut self, id: u32);
 
+    /// Keep the element on the page after it is removed until its exit animation is done. Appending it back before then cancels the pending removal. Renderers without animations remove it right away
+    fn set_leave_transition(&mut self, id: u32, leave: Leave) {
+        let _ = (id, leave);
+    }
+
+    /// Put the node `with` where `id` is in the document. `id` is detached but keeps its id until it is returned
+    ///
+    /// `rsx!` uses this to place nodes built by closures. The renderer doesn't track where nodes are, so it can't be done with the other methods and every renderer has to implement it
+    fn replace_with(&mut self, id: u32, with: u32);
+
     fn return_node(&mut self, id: u32);
 
+    /// Build a subtree once so identical subtrees can be cloned from it with [`Renderer::instantiate_template`] instead of being created node by node. `build` creates the nodes and returns the root, which must not be attached to the document
+    fn create_template(&mut self, build: impl FnOnce(&mut Self) -> u32) -> TemplateId {
+        TemplateId(build(self))
+    }
+
+    /// Deep clone the subtree of a template, returning the id of the new root
+    fn instantiate_template(&mut self, template: TemplateId) -> u32 {
+        let id = self.node();
+        self.clone_node(template.0, id);
+        id
+    }
+
+    /// Find a node below `root` by the index of the child to take at each level, returning a new id for it. Cloning keeps the structure of a template, so the path to a hole in the template finds the same hole in every instance
+    fn node_at_path(&mut self, root: u32, path: &[u32]) -> u32 {
+        let id = self.node();
+        self.copy(root, id);
+        for index in path {
+            self.first_child(id);
+            for _ in 0..*index {
+                self.next_sibling(id);
+            }
+        }
+        id
+    }
+
     fn add_listener<E: EventDescription<P>>(
         &mut self,
         id: u32,
@@ -36,6 +133,51 @@ pub trait Renderer<P: PlatformEvents>: Sized {
         callback: Box<dyn FnMut(web_sys::Event)>,
     );
 
+    /// Handle the event for every element matching `selector` with one handler registered on the root, instead of a listener per element. Elements added later are handled too. The handler receives the `data-key` attribute of the closest element matching the selector, or an empty string if it has none. Only elements inside the root match
+    ///
+    /// The handler is removed when the returned [`Delegation`] is dropped. Renderers without events ignore it
+    fn delegate_handler<E: EventDescription<P>>(
+        &mut self,
+        event: E,
+        selector: &'static str,
+        handler: Box<dyn FnMut(web_sys::Event, String)>,
+    ) -> Delegation {
+        let _ = (event, selector, handler);
+        Delegation::none()
+    }
+
+    /// Call `callback` with the new value when something other than the renderer changes the attribute, like a third party script. The renderer's own writes may be reported too, so the callback should ignore values it already has
+    fn observe_attribute(&mut self, id: u32, name: &'static str, callback: Box<dyn FnMut(String)>) {
+        let _ = (id, name, callback);
+    }
+
+    /// Move keyboard focus to the element. An element that isn't on the page yet is focused once it is added. Renderers without focus ignore it
+    fn focus(&mut self, id: u32) {
+        let _ = id;
+    }
+
+    /// Scroll the element so `top` pixels of its content are above the visible area. Renderers without scrolling ignore it
+    fn set_scroll_top(&mut self, id: u32, top: f64) {
+        let _ = (id, top);
+    }
+
+    /// Call `callback` with the scroll position of the element after it is scrolled. Renderers may report at most one position per frame while the element is scrolling, and scrolling done with [`Renderer::set_scroll_top`] may be reported too
+    fn observe_scroll(&mut self, id: u32, callback: Box<dyn FnMut(f64)>) {
+        let _ = (id, callback);
+    }
+
+    /// Call `callback` with true when the element scrolls into the viewport and false when it leaves it. Renderers without a viewport treat every element as visible and call it once right away
+    fn observe_visible(&mut self, id: u32, mut callback: Box<dyn FnMut(bool)>) {
+        let _ = id;
+        callback(true);
+    }
+
+    /// The width and height of an element, or None if the node is not attached to a document or the renderer has no layout
+    fn measure(&mut self, id: u32) -> Option<(f64, f64)> {
+        let _ = id;
+        None
+    }
+
     fn flush(&mut self) {}
 }
 
@@ -52,14 +194,34 @@ impl<'a, R: Renderer<R> + PlatformEvents + Sized> Renderer<R> for &'a mut R {
         R::set_attribute(self, id, name, value)
     }
 
+    fn set_attributes(&mut self, id: u32, attributes: &[(&'static str, &str)]) {
+        R::set_attributes(self, id, attributes)
+    }
+
     fn set_style(&mut self, id: u32, name: &'static str, value: &str) {
         R::set_style(self, id, name, value)
     }
 
+    fn set_server_attribute(&mut self, id: u32, name: &'static str, value: &str) {
+        R::set_server_attribute(self, id, name, value)
+    }
+
+    fn set_value(&mut self, id: u32, value: &str) {
+        R::set_value(self, id, value)
+    }
+
+    fn set_selected(&mut self, id: u32, values: &[&str]) {
+        R::set_selected(self, id, values)
+    }
+
     fn create_element(&mut self, id: u32, tag: &'static str) {
         R::create_element(self, id, tag)
     }
 
+    fn create_element_ns(&mut self, id: u32, tag: &'static str, namespace: &'static str) {
+        R::create_element_ns(self, id, tag, namespace)
+    }
+
     fn create_text(&mut self, id: u32, text: &str) {
         R::create_text(self, id, text)
     }
@@ -68,10 +230,26 @@ impl<'a, R: Renderer<R> + PlatformEvents + Sized> Renderer<R> for &'a mut R {
         R::set_text(self, id, text)
     }
 
+    fn replace_data(&mut self, id: u32, offset: u32, count: u32, data: &str) -> bool {
+        R::replace_data(self, id, offset, count, data)
+    }
+
+    fn set_tag(&mut self, id: u32, tag: &str) {
+        R::set_tag(self, id, tag)
+    }
+
+    fn set_text_diff(&mut self, id: u32, old: &str, new: &str) {
+        R::set_text_diff(self, id, old, new)
+    }
+
     fn append_child(&mut self, parent: u32, child: u32) {
         R::append_child(self, parent, child)
     }
 
+    fn append_children(&mut self, parent: u32, children: &[u32]) {
+        R::append_children(self, parent, children)
+    }
+
     fn clone_node(&mut self, id: u32, new_id: u32) {
         R::clone_node(self, id, new_id)
     }
@@ -92,10 +270,31 @@ impl<'a, R: Renderer<R> + PlatformEvents + Sized> Renderer<R> for &'a mut R {
         R::remove(self, id)
     }
 
+    fn set_leave_transition(&mut self, id: u32, leave: Leave) {
+        R::set_leave_transition(self, id, leave)
+    }
+
+    fn replace_with(&mut self, id: u32, with: u32) {
+        R::replace_with(self, id, with)
+    }
+
     fn return_node(&mut self, id: u32) {
         R::return_node(self, id)
     }
 
+    fn create_template(&mut self, build: impl FnOnce(&mut Self) -> u32) -> TemplateId {
+        let root = build(self);
+        R::create_template(self, |_| root)
+    }
+
+    fn instantiate_template(&mut self, template: TemplateId) -> u32 {
+        R::instantiate_template(self, template)
+    }
+
+    fn node_at_path(&mut self, root: u32, path: &[u32]) -> u32 {
+        R::node_at_path(self, root, path)
+    }
+
     fn add_listener<E: EventDescription<R>>(
         &mut self,
         id: u32,
@@ -105,7 +304,368 @@ impl<'a, R: Renderer<R> + PlatformEvents + Sized> Renderer<R> for &'a mut R {
         R::add_listener(self, id, event, callback)
     }
 
+    fn delegate_handler<E: EventDescription<R>>(
+        &mut self,
+        event: E,
+        selector: &'static str,
+        handler: Box<dyn FnMut(web_sys::Event, String)>,
+    ) -> Delegation {
+        R::delegate_handler(self, event, selector, handler)
+    }
+
+    fn observe_attribute(&mut self, id: u32, name: &'static str, callback: Box<dyn FnMut(String)>) {
+        R::observe_attribute(self, id, name, callback)
+    }
+
+    fn focus(&mut self, id: u32) {
+        R::focus(self, id)
+    }
+
+    fn set_scroll_top(&mut self, id: u32, top: f64) {
+        R::set_scroll_top(self, id, top)
+    }
+
+    fn observe_scroll(&mut self, id: u32, callback: Box<dyn FnMut(f64)>) {
+        R::observe_scroll(self, id, callback)
+    }
+
+    fn observe_visible(&mut self, id: u32, callback: Box<dyn FnMut(bool)>) {
+        R::observe_visible(self, id, callback)
+    }
+
+    fn measure(&mut self, id: u32) -> Option<(f64, f64)> {
+        R::measure(self, id)
+    }
+
     fn flush(&mut self) {
         R::flush(self)
     }
 }
+
+/// A subtree created with [`Renderer::create_template`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TemplateId(pub u32);
+
+/// How an element leaves the page, set with `transition:leave` in `rsx!`: `transition:leave="fade-out"` or `transition:leave={300}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Leave {
+    /// Add the class and remove the element when its animation or transition ends. If the class starts neither, the element is removed right away
+    Class(String),
+    /// Remove the element after a number of milliseconds
+    Millis(u32),
+}
+
+impl From<&str> for Leave {
+    fn from(class: &str) -> Self {
+        Self::Class(class.to_string())
+    }
+}
+
+impl From<String> for Leave {
+    fn from(class: String) -> Self {
+        Self::Class(class)
+    }
+}
+
+impl From<u32> for Leave {
+    fn from(millis: u32) -> Self {
+        Self::Millis(millis)
+    }
+}
+
+/// A handler registered with [`Renderer::delegate_handler`]. Dropping it removes the handler
+#[must_use = "the delegated handler is removed as soon as this is dropped"]
+pub struct Delegation(Option<Box<dyn FnOnce()>>);
+
+impl Delegation {
+    /// A handle that calls `remove` when it is dropped
+    pub fn new(remove: impl FnOnce() + 'static) -> Self {
+        Self(Some(Box::new(remove)))
+    }
+
+    /// A handle for a handler that was never registered
+    pub fn none() -> Self {
+        Self(None)
+    }
+
+    /// Keep the handler for as long as the renderer lives
+    pub fn forget(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for Delegation {
+    fn drop(&mut self) {
+        if let Some(remove) = self.0.take() {
+            remove();
+        }
+    }
+}
+
+/// Remembers the last value of an `autofocus_when` signal, so the element is only focused when the signal changes from false to true. Rerunning the update while the signal stays true doesn't take focus back from an element the user moved to
+#[derive(Debug, Default)]
+pub struct AutoFocus(std::cell::Cell<bool>);
+
+impl AutoFocus {
+    /// Record the new value, returning true if the element should be focused
+    pub fn rose(&self, focus: bool) -> bool {
+        !self.0.replace(focus) && focus
+    }
+}
+
+/// The smallest change that turns one text into another
+#[derive(Debug, PartialEq, Eq)]
+pub enum TextDiff<'a> {
+    Unchanged,
+    /// Replace `count` UTF-16 code units starting at `offset` with `data`
+    Replace {
+        offset: u32,
+        count: u32,
+        data: &'a str,
+    },
+    /// The texts have too little in common to be worth diffing
+    Full,
+}
+
+/// Find the part of `new` between the prefix and suffix it shares with `old`
+pub fn text_diff<'a>(old: &str, new: &'a str) -> TextDiff<'a> {
+    if old == new {
+        return TextDiff::Unchanged;
+    }
+    let prefix: usize = old
+        .chars()
+        .zip(new.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    // only look after the prefix so the suffix can't overlap it
+    let suffix: usize = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+
+    if (prefix + suffix) * 2 < new.len() {
+        return TextDiff::Full;
+    }
+
+    let utf16_len = |text: &str| text.encode_utf16().count() as u32;
+    TextDiff::Replace {
+        offset: utf16_len(&old[..prefix]),
+        count: utf16_len(&old[prefix..old.len() - suffix]),
+        data: &new[prefix..new.len() - suffix],
+    }
+}
+
+#[test]
+fn diffs_text() {
+    assert_eq!(text_diff("abc", "abc"), TextDiff::Unchanged);
+    assert_eq!(
+        text_diff("hello world", "hello there world"),
+        TextDiff::Replace {
+            offset: 6,
+            count: 0,
+            data: "there "
+        }
+    );
+    assert_eq!(
+        text_diff("aaa", "aa"),
+        TextDiff::Replace {
+            offset: 2,
+            count: 1,
+            data: ""
+        }
+    );
+    // offsets count UTF-16 code units
+    assert_eq!(
+        text_diff("🦀 crab", "🦀 crabs"),
+        TextDiff::Replace {
+            offset: 7,
+            count: 0,
+            data: "s"
+        }
+    );
+    assert_eq!(text_diff("something", "else entirely"), TextDiff::Full);
+}
+
+#[test]
+fn template_rows() {
+    use crate::mock::{MockOp, MockRenderer};
+
+    fn row(ui: &mut MockRenderer, label: &str) -> u32 {
+        let row = ui.node();
+        ui.create_element(row, "li");
+        ui.set_attribute(row, "class", "row");
+        let text = ui.node();
+        ui.create_text(text, label);
+        ui.append_child(row, text);
+        row
+    }
+
+    let mut built = MockRenderer::default();
+    for _ in 0..1000 {
+        let id = row(&mut built, "row");
+        built.append_child(0, id);
+    }
+
+    let mut cloned = MockRenderer::default();
+    let template = cloned.create_template(|ui| row(ui, "row"));
+    let rows: Vec<u32> = (0..1000)
+        .map(|_| cloned.instantiate_template(template))
+        .collect();
+    cloned.append_all(0, rows.iter().copied());
+
+    assert_eq!(cloned.text_of(0), built.text_of(0));
+    assert_eq!(cloned.text_of(rows[999]).as_deref(), Some("row"));
+    // each row is one clone instead of one operation per node
+    let count = |ui: &MockRenderer, creates: fn(&MockOp) -> bool| {
+        ui.ops().iter().filter(|op| creates(op)).count()
+    };
+    assert_eq!(
+        count(&built, |op| matches!(
+            op,
+            MockOp::CreateElement { .. } | MockOp::CreateText { .. } | MockOp::SetAttribute { .. }
+        )),
+        3000
+    );
+    assert_eq!(
+        count(&cloned, |op| matches!(op, MockOp::CloneNode { .. })),
+        1000
+    );
+}
+
+#[test]
+fn resolve_template_holes() {
+    use crate::mock::MockRenderer;
+
+    fn element(ui: &mut MockRenderer, tag: &'static str, children: &[u32]) -> u32 {
+        let id = ui.node();
+        ui.create_element(id, tag);
+        ui.append_all(id, children.iter().copied());
+        id
+    }
+
+    fn text(ui: &mut MockRenderer, text: &str) -> u32 {
+        let id = ui.node();
+        ui.create_text(id, text);
+        id
+    }
+
+    let mut ui = MockRenderer::default();
+    // <tr><td>"#"</td><td><b>"name"</b><i>"placeholder"</i></td></tr>
+    let template = ui.create_template(|ui| {
+        let number = text(ui, "#");
+        let number = element(ui, "td", &[number]);
+        let name = text(ui, "name");
+        let name = element(ui, "b", &[name]);
+        let placeholder = text(ui, "placeholder");
+        let placeholder = element(ui, "i", &[placeholder]);
+        let cell = element(ui, "td", &[name, placeholder]);
+        element(ui, "tr", &[number, cell])
+    });
+
+    let first = ui.instantiate_template(template);
+    let second = ui.instantiate_template(template);
+    ui.append_all(0, [first, second]);
+    let hole = ui.node_at_path(second, &[1, 1, 0]);
+    ui.set_text(hole, "Ferris");
+
+    assert_eq!(ui.text_of(second).as_deref(), Some("#nameFerris"));
+    // the other instance and the template are separate copies
+    assert_eq!(ui.text_of(first).as_deref(), Some("#nameplaceholder"));
+    assert_eq!(ui.text_of(template.0).as_deref(), Some("#nameplaceholder"));
+}
+
+/// A value that can be bound to an attribute. Strings are passed through and numbers are formatted into a reused buffer, so binding a number doesn't allocate a new `String` on every update
+pub trait AttributeValue {
+    fn with_attribute<O>(&self, f: impl FnOnce(&str) -> O) -> O;
+}
+
+impl AttributeValue for str {
+    fn with_attribute<O>(&self, f: impl FnOnce(&str) -> O) -> O {
+        f(self)
+    }
+}
+
+impl AttributeValue for String {
+    fn with_attribute<O>(&self, f: impl FnOnce(&str) -> O) -> O {
+        f(self)
+    }
+}
+
+impl<T: AttributeValue + ?Sized> AttributeValue for &T {
+    fn with_attribute<O>(&self, f: impl FnOnce(&str) -> O) -> O {
+        T::with_attribute(self, f)
+    }
+}
+
+thread_local! {
+    static ATTRIBUTE_BUFFER: std::cell::Cell<String> = const { std::cell::Cell::new(String::new()) };
+}
+
+/// Format `value` into a buffer that is reused between calls and pass the result to `f`
+pub fn with_display<O>(value: &impl std::fmt::Display, f: impl FnOnce(&str) -> O) -> O {
+    use std::fmt::Write;
+
+    // take the buffer so formatting another value inside f can't conflict with it
+    let mut buffer = ATTRIBUTE_BUFFER.with(|buffer| buffer.take());
+    buffer.clear();
+    write!(buffer, "{value}").unwrap();
+    let result = f(&buffer);
+    ATTRIBUTE_BUFFER.with(|cached| cached.set(buffer));
+    result
+}
+
+macro_rules! display_attribute_values {
+    ($($ty:ty),*) => {
+        $(
+            impl AttributeValue for $ty {
+                fn with_attribute<O>(&self, f: impl FnOnce(&str) -> O) -> O {
+                    with_display(self, f)
+                }
+            }
+        )*
+    };
+}
+
+display_attribute_values!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, bool, char
+);
+
+#[test]
+fn numbers_bind_without_allocating() {
+    use crate::copy::{claim_rt, Readable, Writable};
+    use crate::mock::MockRenderer;
+
+    let buffer = || {
+        ATTRIBUTE_BUFFER.with(|buffer| {
+            let cached = buffer.take();
+            let ptr = cached.as_ptr();
+            buffer.set(cached);
+            ptr
+        })
+    };
+
+    let cx = crate::scope!(claim_rt());
+    let count = cx.state(-42i64);
+    let mut ui = MockRenderer::default();
+    let id = ui.node();
+    ui.create_element(id, "div");
+    ui.append_child(0, id);
+
+    count
+        .get()
+        .with_attribute(|value| ui.set_attribute(id, "data-count", value));
+    assert_eq!(ui.find_by_attr("data-count", "-42"), Some(id));
+
+    // the buffer is reused once it is big enough
+    let before = buffer();
+    count.set(7);
+    count
+        .get()
+        .with_attribute(|value| ui.set_attribute(id, "data-count", value));
+    assert_eq!(ui.find_by_attr("data-count", "7"), Some(id));
+    assert_eq!(buffer(), before);
+}
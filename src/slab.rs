@@ -35,6 +35,18 @@ impl<T> IdSlab<T> {
         self.data[id as usize] = None;
     }
 
+    /// The ids that are in use along with their data
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &T)> {
+        self.data
+            .iter()
+            .enumerate()
+            .filter_map(|(id, data)| Some((id as u32, data.as_ref()?)))
+    }
+
+    pub fn get(&self, id: u32) -> Option<&T> {
+        self.data.get(id as usize).and_then(Option::as_ref)
+    }
+
     pub fn get_mut(&mut self, id: u32) -> Option<&mut T> {
         self.data[id as usize].as_mut()
     }
@@ -0,0 +1,142 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::copy::{Readable, Scope, State, Writable};
+
+/// A string key value store that states can be persisted to, like the browser's `localStorage`
+pub trait Storage: 'static {
+    fn get_item(&self, key: &str) -> Option<String>;
+
+    /// Fails if the value could not be stored, for example because the storage quota is exceeded
+    fn set_item(&self, key: &str, value: &str) -> Result<(), String>;
+}
+
+/// Storage that only lives as long as the page. Used when `localStorage` isn't available and in tests
+#[derive(Clone, Default)]
+pub struct MemoryStorage {
+    items: Rc<RefCell<HashMap<String, String>>>,
+    // The most bytes of values the storage holds
+    quota: Option<usize>,
+}
+
+impl MemoryStorage {
+    /// Storage that rejects writes once its values would take more than `quota` bytes
+    pub fn with_quota(quota: usize) -> Self {
+        Self {
+            quota: Some(quota),
+            ..Default::default()
+        }
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get_item(&self, key: &str) -> Option<String> {
+        self.items.borrow().get(key).cloned()
+    }
+
+    fn set_item(&self, key: &str, value: &str) -> Result<(), String> {
+        let mut items = self.items.borrow_mut();
+        if let Some(quota) = self.quota {
+            let others: usize = items
+                .iter()
+                .filter(|(other, _)| *other != key)
+                .map(|(_, value)| value.len())
+                .sum();
+            if others + value.len() > quota {
+                return Err(format!(
+                    "storing {key} would exceed the quota of {quota} bytes"
+                ));
+            }
+        }
+        items.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
+
+impl Scope {
+    /// A state that starts with the value stored as JSON under `key`, or `default` if nothing that deserializes is stored, and writes every change back to the storage. [`web::local_storage_signal`](crate::web::local_storage_signal) creates one backed by `localStorage`
+    ///
+    /// If a write fails, for example because the storage is full, a warning is logged and the state keeps the new value
+    pub(crate) fn stored<T: Serialize + DeserializeOwned + 'static>(
+        &self,
+        storage: impl Storage,
+        key: &str,
+        default: impl FnOnce() -> T,
+    ) -> State<T> {
+        let value = storage
+            .get_item(key)
+            .and_then(|stored| serde_json::from_str(&stored).ok())
+            .unwrap_or_else(default);
+        let state = self.state(value);
+        let key = key.to_string();
+        self.effect(move || {
+            let value = match state.with(serde_json::to_string) {
+                Ok(value) => value,
+                Err(err) => {
+                    crate::copy::warn(&format!("failed to serialize {key}: {err}"));
+                    return;
+                }
+            };
+            // values loaded from the storage don't need to be written back
+            if storage.get_item(&key).as_ref() == Some(&value) {
+                return;
+            }
+            if let Err(err) = storage.set_item(&key, &value) {
+                crate::copy::warn(&format!("failed to store {key}: {err}"));
+            }
+        });
+        state
+    }
+}
+
+// Write a value that was stored by another tab into a state created with `Scope::stored`. Values that don't deserialize are ignored, and the state is only written if the value changed
+pub(crate) fn reload<T: Serialize + DeserializeOwned + 'static>(
+    state: State<T>,
+    stored: Option<&str>,
+) {
+    let Some(stored) = stored else {
+        return;
+    };
+    if state.peek(|current| serde_json::to_string(current).ok().as_deref() == Some(stored)) {
+        return;
+    }
+    if let Ok(value) = serde_json::from_str(stored) {
+        state.set(value);
+    }
+}
+
+#[test]
+fn stored_values_persist() {
+    use crate::copy::{claim_rt, Runtime};
+
+    let runtime = claim_rt();
+    let cx = crate::scope!(runtime);
+    let storage = MemoryStorage::with_quota(8);
+    storage.set_item("count", "5").unwrap();
+    storage.set_item("broken", "five").unwrap();
+
+    let count = cx.stored(storage.clone(), "count", || 0);
+    let broken = cx.stored(storage.clone(), "broken", || 1);
+    let name = cx.stored(storage.clone(), "name", String::new);
+    assert_eq!(count.get(), 5);
+    assert_eq!(broken.get(), 1);
+
+    count.set(7);
+    Runtime::flush(runtime);
+    assert_eq!(storage.get_item("count").as_deref(), Some("7"));
+
+    // a full storage keeps the old value, but the state still changes
+    name.set("too long to store".to_string());
+    Runtime::flush(runtime);
+    assert_eq!(storage.get_item("name").as_deref(), Some(r#""""#));
+    assert_eq!(name.cloned(), "too long to store");
+
+    // another tab wrote the value, which the storage listener reads back
+    storage.set_item("count", "9").unwrap();
+    reload(count, storage.get_item("count").as_deref());
+    reload(count, Some("nine"));
+    Runtime::flush(runtime);
+    assert_eq!(count.get(), 9);
+    assert_eq!(storage.get_item("count").as_deref(), Some("9"));
+}
@@ -0,0 +1,347 @@
+use crate::{
+    component::{Component, ComponentState, DynComponentState},
+    copy::{with_rt, Readable, Scope, State, Writable},
+    prelude::{PlatformEvents, Renderer},
+};
+
+/// A value that is loaded asynchronously. While it is pending, the innermost [`Suspense`] it was created in shows its fallback
+pub struct Resource<T: 'static> {
+    value: State<Option<T>>,
+    // The pending count of the boundary the resource was created in
+    boundary: Option<State<usize>>,
+}
+
+impl<T: 'static> Clone for Resource<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: 'static> Copy for Resource<T> {}
+
+impl<T: 'static> Resource<T> {
+    pub fn pending(&self) -> bool {
+        self.value.with(Option::is_none)
+    }
+
+    /// Store the loaded value. The boundary the resource was created in stops waiting for it
+    pub fn complete(&self, value: T) {
+        let was_pending = self.value.peek(Option::is_none);
+        self.value.set(Some(value));
+        if let (true, Some(boundary)) = (was_pending, self.boundary) {
+            boundary.with_mut(|pending| *pending -= 1);
+        }
+    }
+
+    /// Read the value, which is None while the resource is pending
+    pub fn with<U: 'static>(&self, f: impl FnOnce(Option<&T>) -> U) -> U {
+        self.value.with(|value| f(value.as_ref()))
+    }
+}
+
+impl Scope {
+    /// Create a resource that is pending until [`Resource::complete`] is called. Resources created while the content of a [`Suspense`] is created hold that boundary's fallback
+    pub fn resource<T: 'static>(&self) -> Resource<T> {
+        let boundary = with_rt(self.runtime(), |runtime| {
+            runtime.suspense.borrow().last().copied()
+        });
+        if let Some(boundary) = boundary {
+            boundary.with_mut(|pending| *pending += 1);
+        }
+        Resource {
+            value: self.state(None),
+            boundary,
+        }
+    }
+}
+
+/// Shows a fallback while any resource created with its content is pending, and the content once they have all completed. Boundaries can be nested, a resource only holds the innermost boundary it was created in
+pub struct Suspense<R, P>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    pending: State<usize>,
+    fallback: DynComponentState<R, P>,
+    content: DynComponentState<R, P>,
+    showing_content: bool,
+    roots_version: usize,
+}
+
+impl<R, P> Suspense<R, P>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    /// The content is created right away so it can start loading, but its roots are only shown once nothing is pending
+    pub fn new<C>(
+        cx: &Scope,
+        ui: &mut R,
+        fallback: C,
+        content: impl FnOnce(&Scope, &mut R) -> DynComponentState<R, P>,
+    ) -> Self
+    where
+        C: Component<R, P>,
+        C::State: 'static,
+    {
+        let pending = cx.state(0);
+        let fallback = DynComponentState::new(fallback.create(ui));
+        with_rt(cx.runtime(), |runtime| {
+            runtime.suspense.borrow_mut().push(pending)
+        });
+        let content = content(cx, ui);
+        with_rt(cx.runtime(), |runtime| runtime.suspense.borrow_mut().pop());
+        Self {
+            pending,
+            fallback,
+            content,
+            showing_content: pending.peek(|pending| *pending == 0),
+            roots_version: 0,
+        }
+    }
+
+    pub fn pending(&self) -> bool {
+        self.pending.with(|pending| *pending > 0)
+    }
+
+    /// Swap the fallback for the content once every resource completed
+    pub fn update(&mut self, parent: u32, ui: &mut R) {
+        let ready = !self.pending();
+        if ready == self.showing_content {
+            return;
+        }
+        let (hidden, shown) = if ready {
+            (&self.fallback, &self.content)
+        } else {
+            (&self.content, &self.fallback)
+        };
+        hidden.remove(ui);
+        ui.append_all(parent, shown.roots());
+        self.showing_content = ready;
+        self.roots_version += 1;
+    }
+
+    fn shown(&self) -> &DynComponentState<R, P> {
+        if self.showing_content {
+            &self.content
+        } else {
+            &self.fallback
+        }
+    }
+}
+
+impl<R, P> ComponentState<R, P> for Suspense<R, P>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    fn roots(&self) -> Vec<u32> {
+        self.shown().roots()
+    }
+
+    fn roots_version(&self) -> usize {
+        self.roots_version + self.shown().roots_version()
+    }
+}
+
+type Render<V, R, P> = Box<dyn Fn(&V, &mut R) -> DynComponentState<R, P>>;
+
+// How an ErrorDisplay renders each state of its result
+struct ResultViews<T, E, R, P>
+where
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    loading: Render<(), R, P>,
+    ok: Render<T, R, P>,
+    err: Render<E, R, P>,
+}
+
+impl<T, E, R, P> ResultViews<T, E, R, P>
+where
+    T: 'static,
+    E: 'static,
+    R: Renderer<P> + 'static,
+    P: PlatformEvents + 'static,
+{
+    fn render(&self, result: State<Option<Result<T, E>>>, ui: &mut R) -> DynComponentState<R, P> {
+        result.with(|result| match result {
+            None => (self.loading)(&(), ui),
+            Some(Ok(value)) => (self.ok)(value, ui),
+            Some(Err(error)) => (self.err)(error, ui),
+        })
+    }
+}
+
+/// Shows one of three subtrees for a fallible value that is loaded asynchronously: one while it is None, one for Ok and one for Err. The subtree is created again whenever the value is written
+pub struct ErrorDisplay<T, E, R, P>
+where
+    T: 'static,
+    E: 'static,
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    result: State<Option<Result<T, E>>>,
+    views: ResultViews<T, E, R, P>,
+    shown: DynComponentState<R, P>,
+    // The version of the result the shown subtree was created from
+    version: usize,
+    roots_version: usize,
+}
+
+impl<T, E, R, P> ErrorDisplay<T, E, R, P>
+where
+    T: 'static,
+    E: 'static,
+    R: Renderer<P> + 'static,
+    P: PlatformEvents + 'static,
+{
+    pub fn new(
+        result: State<Option<Result<T, E>>>,
+        ui: &mut R,
+        loading: impl Fn(&mut R) -> DynComponentState<R, P> + 'static,
+        ok: impl Fn(&T, &mut R) -> DynComponentState<R, P> + 'static,
+        err: impl Fn(&E, &mut R) -> DynComponentState<R, P> + 'static,
+    ) -> Self {
+        let views = ResultViews {
+            loading: Box::new(move |_, ui| loading(ui)),
+            ok: Box::new(ok),
+            err: Box::new(err),
+        };
+        Self {
+            result,
+            shown: views.render(result, ui),
+            views,
+            version: result.raw().version(),
+            roots_version: 0,
+        }
+    }
+
+    /// Replace the shown subtree if the result was written since it was created
+    pub fn update(&mut self, parent: u32, ui: &mut R) {
+        let version = self.result.raw().version();
+        if version == self.version {
+            return;
+        }
+        self.version = version;
+        self.shown.remove(ui);
+        self.shown = self.views.render(self.result, ui);
+        ui.append_all(parent, self.shown.roots());
+        self.roots_version += 1;
+    }
+}
+
+impl<T, E, R, P> ComponentState<R, P> for ErrorDisplay<T, E, R, P>
+where
+    T: 'static,
+    E: 'static,
+    R: Renderer<P>,
+    P: PlatformEvents,
+{
+    fn roots(&self) -> Vec<u32> {
+        self.shown.roots()
+    }
+
+    fn roots_version(&self) -> usize {
+        self.roots_version + self.shown.roots_version()
+    }
+}
+
+#[test]
+fn fallback_until_resources_complete() {
+    use crate::copy::claim_rt;
+    use crate::mock::{fixtures::Text, MockRenderer};
+
+    fn text(
+        text: &'static str,
+        ui: &mut MockRenderer,
+    ) -> DynComponentState<MockRenderer, MockRenderer> {
+        DynComponentState::new(Text(text).create(ui))
+    }
+
+    let cx = crate::scope!(claim_rt());
+    let mut ui = MockRenderer::default();
+    let mut inner_resource = None;
+    let mut outer_resource = None;
+    let mut inner = None;
+    let mut outer = Suspense::new(&cx, &mut ui, Text("loading outer"), |cx, ui| {
+        outer_resource = Some(cx.resource::<u32>());
+        inner = Some(Suspense::new(cx, ui, Text("loading inner"), |cx, ui| {
+            inner_resource = Some(cx.resource::<u32>());
+            text("inner", ui)
+        }));
+        text("outer", ui)
+    });
+    let (mut inner, inner_resource, outer_resource) = (
+        inner.unwrap(),
+        inner_resource.unwrap(),
+        outer_resource.unwrap(),
+    );
+    ui.append_all(0, outer.roots());
+    ui.append_all(0, inner.roots());
+    assert_eq!(ui.text_of(outer.roots()[0]).unwrap(), "loading outer");
+    assert_eq!(ui.text_of(inner.roots()[0]).unwrap(), "loading inner");
+
+    // the boundaries resolve independently
+    outer_resource.complete(1);
+    outer.update(0, &mut ui);
+    inner.update(0, &mut ui);
+    assert!(!outer.pending());
+    assert!(inner.pending());
+    assert_eq!(ui.text_of(outer.roots()[0]).unwrap(), "outer");
+    assert_eq!(ui.text_of(inner.roots()[0]).unwrap(), "loading inner");
+
+    inner_resource.complete(2);
+    inner.update(0, &mut ui);
+    assert_eq!(ui.text_of(inner.roots()[0]).unwrap(), "inner");
+    assert_eq!(inner_resource.with(|value| value.copied()), Some(2));
+}
+
+#[test]
+fn error_display_follows_the_result() {
+    use crate::copy::claim_rt;
+    use crate::mock::{fixtures::Leaf, MockRenderer};
+
+    fn text(text: &str, ui: &mut MockRenderer) -> DynComponentState<MockRenderer, MockRenderer> {
+        let id = ui.node();
+        ui.create_text(id, text);
+        DynComponentState::new(Leaf(id))
+    }
+
+    let cx = crate::scope!(claim_rt());
+    let mut ui = MockRenderer::default();
+    let result = cx.state(None);
+    let mut display = ErrorDisplay::new(
+        result,
+        &mut ui,
+        |ui| text("loading", ui),
+        |user: &&str, ui| text(&format!("hello {user}"), ui),
+        |error: &String, ui| text(&format!("failed: {error}"), ui),
+    );
+    ui.append_all(0, display.roots());
+    let shown = |display: &ErrorDisplay<_, _, _, _>, ui: &MockRenderer| {
+        let roots = display.roots();
+        assert_eq!(roots.len(), 1);
+        ui.text_of(roots[0]).unwrap()
+    };
+    assert_eq!(shown(&display, &ui), "loading");
+
+    // nothing was written, so the subtree is kept
+    let version = display.roots_version();
+    display.update(0, &mut ui);
+    assert_eq!(display.roots_version(), version);
+
+    result.set(Some(Ok("qk")));
+    display.update(0, &mut ui);
+    assert_eq!(shown(&display, &ui), "hello qk");
+
+    result.set(Some(Err("timed out".to_string())));
+    display.update(0, &mut ui);
+    assert_eq!(shown(&display, &ui), "failed: timed out");
+
+    // retrying goes from the error back to loading
+    result.set(None);
+    display.update(0, &mut ui);
+    assert_eq!(shown(&display, &ui), "loading");
+    assert_eq!(ui.text_of(0).unwrap(), "loading");
+}
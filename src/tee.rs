@@ -0,0 +1,277 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::events::{EventDescription, PlatformEvents};
+use crate::renderer::{Delegation, Leave, Renderer};
+
+/// A renderer that forwards every operation to two renderers.
+///
+/// Node ids are allocated by the primary renderer `A`. Each one is mirrored by a node allocated in `B`, and ids are translated before they are passed to `B`. Operations that return a value like [`Renderer::measure`] only come from `A`.
+#[derive(Clone)]
+pub struct TeeRenderer<A, B> {
+    pub primary: A,
+    pub secondary: B,
+    // The id in the secondary renderer of each id in the primary renderer
+    mirrored: Rc<RefCell<Vec<u32>>>,
+}
+
+impl<A, B> TeeRenderer<A, B> {
+    pub fn new(primary: A, secondary: B) -> Self {
+        Self {
+            primary,
+            secondary,
+            // the root node
+            mirrored: Rc::new(RefCell::new(vec![0])),
+        }
+    }
+
+    fn mirror(&self, id: u32) -> u32 {
+        self.mirrored.borrow()[id as usize]
+    }
+}
+
+impl<P: PlatformEvents, A: Renderer<P>, B: Renderer<P>> Renderer<P> for TeeRenderer<A, B> {
+    fn node(&mut self) -> u32 {
+        let id = self.primary.node();
+        let mirror = self.secondary.node();
+        let mut mirrored = self.mirrored.borrow_mut();
+        if mirrored.len() <= id as usize {
+            mirrored.resize(id as usize + 1, 0);
+        }
+        mirrored[id as usize] = mirror;
+        id
+    }
+
+    fn append_all(&mut self, parent: u32, children: impl IntoIterator<Item = u32>) {
+        let children: Vec<u32> = children.into_iter().collect();
+        self.primary.append_all(parent, children.iter().copied());
+        let mirrored: Vec<u32> = children.iter().map(|child| self.mirror(*child)).collect();
+        self.secondary.append_all(self.mirror(parent), mirrored)
+    }
+
+    fn set_attribute(&mut self, id: u32, name: &'static str, value: &str) {
+        self.primary.set_attribute(id, name, value);
+        self.secondary.set_attribute(self.mirror(id), name, value)
+    }
+
+    fn set_attributes(&mut self, id: u32, attributes: &[(&'static str, &str)]) {
+        self.primary.set_attributes(id, attributes);
+        self.secondary.set_attributes(self.mirror(id), attributes)
+    }
+
+    fn set_style(&mut self, id: u32, name: &'static str, value: &str) {
+        self.primary.set_style(id, name, value);
+        self.secondary.set_style(self.mirror(id), name, value)
+    }
+
+    fn set_server_attribute(&mut self, id: u32, name: &'static str, value: &str) {
+        self.primary.set_server_attribute(id, name, value);
+        self.secondary
+            .set_server_attribute(self.mirror(id), name, value)
+    }
+
+    fn set_value(&mut self, id: u32, value: &str) {
+        self.primary.set_value(id, value);
+        self.secondary.set_value(self.mirror(id), value)
+    }
+
+    fn set_selected(&mut self, id: u32, values: &[&str]) {
+        self.primary.set_selected(id, values);
+        self.secondary.set_selected(self.mirror(id), values)
+    }
+
+    fn create_element(&mut self, id: u32, tag: &'static str) {
+        self.primary.create_element(id, tag);
+        self.secondary.create_element(self.mirror(id), tag)
+    }
+
+    fn create_element_ns(&mut self, id: u32, tag: &'static str, namespace: &'static str) {
+        self.primary.create_element_ns(id, tag, namespace);
+        self.secondary
+            .create_element_ns(self.mirror(id), tag, namespace)
+    }
+
+    fn create_text(&mut self, id: u32, text: &str) {
+        self.primary.create_text(id, text);
+        self.secondary.create_text(self.mirror(id), text)
+    }
+
+    fn set_text(&mut self, id: u32, text: &str) {
+        self.primary.set_text(id, text);
+        self.secondary.set_text(self.mirror(id), text)
+    }
+
+    fn replace_data(&mut self, id: u32, offset: u32, count: u32, data: &str) -> bool {
+        let primary = self.primary.replace_data(id, offset, count, data);
+        // if either can't edit in place, both get the whole text
+        self.secondary
+            .replace_data(self.mirror(id), offset, count, data)
+            && primary
+    }
+
+    fn set_tag(&mut self, id: u32, tag: &str) {
+        self.primary.set_tag(id, tag);
+        self.secondary.set_tag(self.mirror(id), tag)
+    }
+
+    fn append_child(&mut self, parent: u32, child: u32) {
+        self.primary.append_child(parent, child);
+        self.secondary
+            .append_child(self.mirror(parent), self.mirror(child))
+    }
+
+    fn append_children(&mut self, parent: u32, children: &[u32]) {
+        self.primary.append_children(parent, children);
+        let mirrored: Vec<u32> = children.iter().map(|child| self.mirror(*child)).collect();
+        self.secondary
+            .append_children(self.mirror(parent), &mirrored)
+    }
+
+    fn clone_node(&mut self, id: u32, new_id: u32) {
+        self.primary.clone_node(id, new_id);
+        self.secondary
+            .clone_node(self.mirror(id), self.mirror(new_id))
+    }
+
+    fn copy(&mut self, from: u32, to: u32) {
+        self.primary.copy(from, to);
+        self.secondary.copy(self.mirror(from), self.mirror(to))
+    }
+
+    fn first_child(&mut self, id: u32) {
+        self.primary.first_child(id);
+        self.secondary.first_child(self.mirror(id))
+    }
+
+    fn next_sibling(&mut self, id: u32) {
+        self.primary.next_sibling(id);
+        self.secondary.next_sibling(self.mirror(id))
+    }
+
+    fn remove(&mut self, id: u32) {
+        self.primary.remove(id);
+        self.secondary.remove(self.mirror(id))
+    }
+
+    fn set_leave_transition(&mut self, id: u32, leave: Leave) {
+        self.primary.set_leave_transition(id, leave.clone());
+        self.secondary.set_leave_transition(self.mirror(id), leave)
+    }
+
+    fn replace_with(&mut self, id: u32, with: u32) {
+        self.primary.replace_with(id, with);
+        self.secondary
+            .replace_with(self.mirror(id), self.mirror(with))
+    }
+
+    fn return_node(&mut self, id: u32) {
+        self.primary.return_node(id);
+        self.secondary.return_node(self.mirror(id))
+    }
+
+    // The callback can only be called by one renderer, so the secondary renderer gets a listener that does nothing
+    fn add_listener<E: EventDescription<P>>(
+        &mut self,
+        id: u32,
+        event: E,
+        callback: Box<dyn FnMut(web_sys::Event)>,
+    ) {
+        self.primary.add_listener(id, event, callback);
+        self.secondary
+            .add_listener(self.mirror(id), event, Box::new(|_| {}))
+    }
+
+    fn delegate_handler<E: EventDescription<P>>(
+        &mut self,
+        event: E,
+        selector: &'static str,
+        handler: Box<dyn FnMut(web_sys::Event, String)>,
+    ) -> Delegation {
+        self.primary.delegate_handler(event, selector, handler)
+    }
+
+    fn observe_attribute(&mut self, id: u32, name: &'static str, callback: Box<dyn FnMut(String)>) {
+        self.primary.observe_attribute(id, name, callback)
+    }
+
+    fn focus(&mut self, id: u32) {
+        self.primary.focus(id)
+    }
+
+    fn set_scroll_top(&mut self, id: u32, top: f64) {
+        self.primary.set_scroll_top(id, top);
+        self.secondary.set_scroll_top(self.mirror(id), top)
+    }
+
+    fn observe_scroll(&mut self, id: u32, callback: Box<dyn FnMut(f64)>) {
+        self.primary.observe_scroll(id, callback)
+    }
+
+    fn observe_visible(&mut self, id: u32, callback: Box<dyn FnMut(bool)>) {
+        self.primary.observe_visible(id, callback)
+    }
+
+    fn measure(&mut self, id: u32) -> Option<(f64, f64)> {
+        self.primary.measure(id)
+    }
+
+    fn flush(&mut self) {
+        self.primary.flush();
+        self.secondary.flush()
+    }
+}
+
+#[test]
+fn tee_forwards_identical_ops() {
+    use crate::mock::MockRenderer;
+
+    let primary = MockRenderer::default();
+    let secondary = MockRenderer::default();
+    let mut ui = TeeRenderer::new(primary.clone(), secondary.clone());
+
+    let div = ui.node();
+    ui.create_element(div, "div");
+    ui.set_attributes(div, &[("id", "main"), ("class", "big")]);
+    let text = ui.node();
+    ui.create_text(text, "hello");
+    ui.append_child(div, text);
+    let copy = ui.node();
+    ui.clone_node(div, copy);
+    ui.append_all(0, [div, copy]);
+    ui.add_listener(div, crate::events::click, Box::new(|_| {}));
+    ui.remove(copy);
+    ui.return_node(copy);
+    assert_eq!(ui.measure(div), None);
+
+    assert!(!primary.ops().is_empty());
+    assert_eq!(primary.ops(), secondary.ops());
+}
+
+#[test]
+fn tee_translates_ids() {
+    use crate::mock::{MockOp, MockRenderer};
+
+    let primary = MockRenderer::default();
+    let mut secondary = MockRenderer::default();
+    // the secondary renderer already has a node, so its ids are offset by one
+    secondary.node();
+    let mut ui = TeeRenderer::new(primary.clone(), secondary.clone());
+
+    let div = ui.node();
+    ui.create_element(div, "div");
+    ui.append_child(0, div);
+
+    assert_eq!(
+        secondary.ops(),
+        [
+            MockOp::CreateElement {
+                id: div + 1,
+                tag: "div"
+            },
+            MockOp::AppendChild {
+                parent: 0,
+                child: div + 1
+            }
+        ]
+    );
+}
@@ -1,7 +1,72 @@
-use num_traits::PrimInt;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::fmt::{Debug, Display};
 use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+use crate::copy::{Readable, Writable};
+use crate::renderer::AttributeValue;
+
+/// The storage for the read and write bits of a [`DirtyTrackSet`]
+pub trait TrackBits: Copy {
+    const EMPTY: Self;
+
+    /// The number of slots that can be tracked
+    const SLOTS: u32;
+
+    fn with(self, num: u8) -> Self;
+
+    fn without(self, num: u8) -> Self;
+
+    fn contains(self, num: u8) -> bool;
+}
+
+macro_rules! int_track_bits {
+    ($($ty:ty),*) => {
+        $(
+            impl TrackBits for $ty {
+                const EMPTY: Self = 0;
+
+                const SLOTS: u32 = <$ty>::BITS;
+
+                fn with(self, num: u8) -> Self {
+                    self | (1 << num)
+                }
+
+                fn without(self, num: u8) -> Self {
+                    self & !(1 << num)
+                }
+
+                fn contains(self, num: u8) -> bool {
+                    self & (1 << num) != 0
+                }
+            }
+        )*
+    };
+}
+
+int_track_bits!(u8, u16, u32, u64, u128, usize);
+
+/// A single slot. The index is ignored
+impl TrackBits for bool {
+    const EMPTY: Self = false;
+
+    const SLOTS: u32 = 1;
+
+    fn with(self, _: u8) -> Self {
+        true
+    }
+
+    fn without(self, _: u8) -> Self {
+        false
+    }
+
+    fn contains(self, _: u8) -> bool {
+        self
+    }
+}
+
+/// Tracking for components with exactly one reactive slot
+pub type BoolDirtyTrack = DirtyTrackSet<bool, bool>;
 
 #[derive(Default)]
 pub struct DirtyTrackSet<R, W> {
@@ -9,13 +74,13 @@ pub struct DirtyTrackSet<R, W> {
     pub write: Cell<W>,
 }
 
-impl<R: PrimInt, W: PrimInt> DirtyTrackSet<R, W> {
+impl<R: TrackBits, W: TrackBits> DirtyTrackSet<R, W> {
     pub fn is_read(&self, num: u8) -> bool {
-        !(self.read.get() & (R::one() << num as usize)).is_zero()
+        self.read.get().contains(num)
     }
 
     pub fn is_write(&self, num: u8) -> bool {
-        !(self.write.get() & (W::one() << num as usize)).is_zero()
+        self.write.get().contains(num)
     }
 
     pub fn track(&self, num: u8) -> DirtyTrack<R, W> {
@@ -27,7 +92,7 @@ impl<R: PrimInt, W: PrimInt> DirtyTrackSet<R, W> {
     }
 
     pub fn reset_read(&self) {
-        self.read.set(R::zero());
+        self.read.set(R::EMPTY);
     }
 
     pub fn get_write(&self) -> W {
@@ -35,7 +100,121 @@ impl<R: PrimInt, W: PrimInt> DirtyTrackSet<R, W> {
     }
 
     pub fn reset_write(&self) {
-        self.write.set(W::zero());
+        self.write.set(W::EMPTY);
+    }
+
+    /// OR the bits of a narrower set, like the tracking of a child component, into this one. Slot `i` of the other set becomes slot `offset + i`
+    pub fn merge_shifted<R2: TrackBits, W2: TrackBits>(
+        &self,
+        other_read: R2,
+        other_write: W2,
+        offset: u8,
+    ) {
+        debug_assert!(
+            offset as u32 + R2::SLOTS <= R::SLOTS && offset as u32 + W2::SLOTS <= W::SLOTS,
+            "merging {} slots at offset {offset} overflows a set of {} slots",
+            R2::SLOTS.max(W2::SLOTS),
+            R::SLOTS.min(W::SLOTS)
+        );
+        let mut read = self.read.get();
+        for slot in (0..R2::SLOTS as u8).filter(|slot| other_read.contains(*slot)) {
+            read = read.with(offset + slot);
+        }
+        self.read.set(read);
+        let mut write = self.write.get();
+        for slot in (0..W2::SLOTS as u8).filter(|slot| other_write.contains(*slot)) {
+            write = write.with(offset + slot);
+        }
+        self.write.set(write);
+    }
+
+    /// Check a render that subscribed to the slots in `read` for writes to those slots. Such a write invalidates the output of the render, and following it would rerun the render forever, so the write bits are cleared and a diagnostic is returned. The caller should render once more with the written values. In debug builds the diagnostic is also logged as a warning
+    pub fn guard_render(&self, render: &str, read: R) -> Option<String> {
+        let mut write = self.write.get();
+        let slots: Vec<u8> = (0..R::SLOTS.min(W::SLOTS) as u8)
+            .filter(|slot| read.contains(*slot) && write.contains(*slot))
+            .collect();
+        if slots.is_empty() {
+            return None;
+        }
+        for slot in &slots {
+            write = write.without(*slot);
+        }
+        self.write.set(write);
+        let message = format!(
+            "{render} wrote the states in slots {slots:?} while rendering, but it also reads them. It was rendered once more instead of rerunning until the writes stop"
+        );
+        if cfg!(debug_assertions) {
+            crate::copy::warn(&message);
+        }
+        Some(message)
+    }
+}
+
+/// A [`DirtyTrackSet`] shared between the `N` reactive fields of a component. Each field is assigned the slot matching its index
+pub struct TrackedFields<R, W, const N: usize> {
+    tracking: DirtyTrackSet<R, W>,
+}
+
+impl<R: TrackBits, W: TrackBits, const N: usize> Default for TrackedFields<R, W, N> {
+    fn default() -> Self {
+        assert!(
+            N as u32 <= R::SLOTS && N as u32 <= W::SLOTS,
+            "{N} fields do not fit in the tracking bits"
+        );
+        Self {
+            tracking: DirtyTrackSet {
+                read: Cell::new(R::EMPTY),
+                write: Cell::new(W::EMPTY),
+            },
+        }
+    }
+}
+
+impl<R: TrackBits, W: TrackBits, const N: usize> TrackedFields<R, W, N> {
+    /// The handle for the field in slot `i`
+    pub fn field(&self, i: usize) -> TrackedField<'_, R, W> {
+        assert!(i < N, "field {i} is out of range for {N} tracked fields");
+        TrackedField {
+            tracking: self.tracking.track(i as u8),
+        }
+    }
+
+    /// The handles for every field in slot order
+    pub fn fields(&self) -> [TrackedField<'_, R, W>; N] {
+        std::array::from_fn(|i| self.field(i))
+    }
+}
+
+impl<R, W, const N: usize> Deref for TrackedFields<R, W, N> {
+    type Target = DirtyTrackSet<R, W>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tracking
+    }
+}
+
+/// A single field of [`TrackedFields`]
+#[derive(Copy, Clone)]
+pub struct TrackedField<'a, R, W> {
+    tracking: DirtyTrack<'a, R, W>,
+}
+
+impl<'a, R: TrackBits, W: TrackBits> TrackedField<'a, R, W> {
+    /// Wrap the field's data so reads and writes are recorded in this field's slot
+    pub fn track<T>(&self, data: &'a mut T) -> RwTrack<'a, T, R, W> {
+        RwTrack {
+            data,
+            tracking: self.tracking,
+        }
+    }
+
+    pub fn is_read(&self) -> bool {
+        self.tracking.data.is_read(self.tracking.num)
+    }
+
+    pub fn is_write(&self) -> bool {
+        self.tracking.data.is_write(self.tracking.num)
     }
 }
 
@@ -45,17 +224,13 @@ pub struct DirtyTrack<'a, R, W> {
     pub num: u8,
 }
 
-impl<R: PrimInt, W: PrimInt> DirtyTrack<'_, R, W> {
+impl<R: TrackBits, W: TrackBits> DirtyTrack<'_, R, W> {
     fn read(&self) {
-        self.data
-            .read
-            .set(self.data.read.get() | (R::one() << self.num as usize));
+        self.data.read.set(self.data.read.get().with(self.num));
     }
 
     fn write(&self) {
-        self.data
-            .write
-            .set(self.data.write.get() | (W::one() << self.num as usize));
+        self.data.write.set(self.data.write.get().with(self.num));
     }
 }
 
@@ -64,19 +239,25 @@ pub struct RwTrack<'a, T, R, W> {
     pub tracking: DirtyTrack<'a, R, W>,
 }
 
-impl<T: Display, R: PrimInt, W: PrimInt> Display for RwTrack<'_, T, R, W> {
+impl<T: Display, R: TrackBits, W: TrackBits> Display for RwTrack<'_, T, R, W> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.deref())
     }
 }
 
-impl<T: Debug, R: PrimInt, W: PrimInt> Debug for RwTrack<'_, T, R, W> {
+impl<T: Debug, R: TrackBits, W: TrackBits> Debug for RwTrack<'_, T, R, W> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self.deref())
     }
 }
 
-impl<T, R: PrimInt, W: PrimInt> Deref for RwTrack<'_, T, R, W> {
+impl<T: AttributeValue, R: TrackBits, W: TrackBits> AttributeValue for RwTrack<'_, T, R, W> {
+    fn with_attribute<O>(&self, f: impl FnOnce(&str) -> O) -> O {
+        self.deref().with_attribute(f)
+    }
+}
+
+impl<T, R: TrackBits, W: TrackBits> Deref for RwTrack<'_, T, R, W> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
@@ -85,7 +266,7 @@ impl<T, R: PrimInt, W: PrimInt> Deref for RwTrack<'_, T, R, W> {
     }
 }
 
-impl<T, R: PrimInt, W: PrimInt> DerefMut for RwTrack<'_, T, R, W> {
+impl<T, R: TrackBits, W: TrackBits> DerefMut for RwTrack<'_, T, R, W> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.tracking.write();
         self.data
@@ -134,8 +315,223 @@ fn rw_track() {
     }
 }
 
+#[test]
+fn tracked_fields() {
+    let fields: TrackedFields<u8, u8, 3> = TrackedFields::default();
+    let [first, second, third] = fields.fields();
+    let mut name = String::from("qk");
+    let mut count = 0;
+    let mut done = false;
+
+    {
+        let name = first.track(&mut name);
+        let mut count = second.track(&mut count);
+        let done = third.track(&mut done);
+
+        if !*done {
+            *count += name.len();
+        }
+    }
+
+    assert!(first.is_read() && !first.is_write());
+    assert!(!second.is_read() && second.is_write());
+    assert!(third.is_read() && !third.is_write());
+    assert_eq!(count, 2);
+
+    fields.reset_read();
+    fields.reset_write();
+    *third.track(&mut done) = true;
+    assert!(!first.is_read() && !second.is_read() && !third.is_read());
+    assert!(!first.is_write() && !second.is_write() && third.is_write());
+}
+
+#[test]
+#[should_panic(expected = "2 fields do not fit in the tracking bits")]
+fn too_many_tracked_fields() {
+    let _: TrackedFields<bool, bool, 2> = TrackedFields::default();
+}
+
+#[test]
+fn merge_child_tracking() {
+    let parent: DirtyTrackSet<u16, u16> = DirtyTrackSet::default();
+    parent.read.set(0b1);
+    parent.merge_shifted(0b1000_0101u8, 0b10u8, 4);
+    assert_eq!(parent.get_read(), 0b1000_0101_0001);
+    assert_eq!(parent.get_write(), 0b10_0000);
+    assert!(parent.is_read(4) && parent.is_read(6) && parent.is_read(11));
+    assert!(parent.is_write(5));
+}
+
+#[test]
+#[should_panic(expected = "merging 8 slots at offset 12 overflows a set of 16 slots")]
+fn merge_past_the_end() {
+    let parent: DirtyTrackSet<u16, u16> = DirtyTrackSet::default();
+    parent.merge_shifted(0u8, 0u8, 12);
+}
+
+#[test]
+fn bool_track_matches_int_track() {
+    let int_tracking: DirtyTrackSet<u8, u8> = DirtyTrackSet::default();
+    let bool_tracking: BoolDirtyTrack = DirtyTrackSet::default();
+    let mut int_value = 0;
+    let mut bool_value = 0;
+
+    let mut int_track = RwTrack {
+        data: &mut int_value,
+        tracking: int_tracking.track(0),
+    };
+    let mut bool_track = RwTrack {
+        data: &mut bool_value,
+        tracking: bool_tracking.track(0),
+    };
+
+    assert_eq!(int_tracking.is_read(0), bool_tracking.is_read(0));
+    assert_eq!(int_tracking.is_write(0), bool_tracking.is_write(0));
+
+    assert_eq!(*int_track, *bool_track);
+    assert!(int_tracking.is_read(0) && bool_tracking.is_read(0));
+    assert!(!int_tracking.is_write(0) && !bool_tracking.is_write(0));
+
+    *int_track += 1;
+    *bool_track += 1;
+    assert!(int_tracking.is_write(0) && bool_tracking.is_write(0));
+
+    int_tracking.reset_read();
+    bool_tracking.reset_read();
+    int_tracking.reset_write();
+    bool_tracking.reset_write();
+    assert!(!int_tracking.is_read(0) && !bool_tracking.is_read(0));
+    assert!(!int_tracking.is_write(0) && !bool_tracking.is_write(0));
+}
+
+/// Lets existing `Rc<RefCell<T>>` state take part in dirty tracking
+pub struct RcSignal<'a, T, R, W> {
+    pub data: Rc<RefCell<T>>,
+    pub tracking: DirtyTrack<'a, R, W>,
+}
+
+impl<T: 'static, R: TrackBits, W: TrackBits> Readable<T> for RcSignal<'_, T, R, W> {
+    fn with<U: 'static, F: FnOnce(&T) -> U>(&self, f: F) -> U {
+        let data = self.data.borrow();
+        self.tracking.read();
+        f(&data)
+    }
+
+    fn peek<U: 'static, F: FnOnce(&T) -> U>(&self, f: F) -> U {
+        f(&self.data.borrow())
+    }
+}
+
+impl<T: 'static, R: TrackBits, W: TrackBits> Writable<T> for RcSignal<'_, T, R, W> {
+    fn with_mut<F: FnOnce(&mut T) -> O, O>(&self, f: F) -> O {
+        let mut data = self.data.borrow_mut();
+        self.tracking.write();
+        f(&mut data)
+    }
+
+    fn with_mut_maybe<F: FnOnce(&mut T) -> (bool, O), O>(&self, f: F) -> O {
+        let mut data = self.data.borrow_mut();
+        let (changed, r) = f(&mut data);
+        if changed {
+            self.tracking.write();
+        }
+        r
+    }
+}
+
+#[test]
+fn rc_signal_reruns_effect() {
+    let shared = Rc::new(RefCell::new(vec![1, 2]));
+    let tracking: DirtyTrackSet<u8, u8> = DirtyTrackSet::default();
+    let signal = RcSignal {
+        data: shared.clone(),
+        tracking: tracking.track(0),
+    };
+
+    let mut effect = Effect {
+        rx: |signal: &RcSignal<Vec<i32>, u8, u8>| signal.with(|list| list.iter().sum::<i32>()),
+        rx_subscriptions: 0,
+        current: 0,
+    };
+    tracking.reset_read();
+    effect.current = (effect.rx)(&signal);
+    effect.rx_subscriptions = tracking.get_read();
+    assert_eq!(effect.current, 3);
+
+    tracking.reset_write();
+    signal.with_mut(|list| list.push(3));
+    assert!(tracking.get_write() & effect.rx_subscriptions != 0);
+    effect.current = (effect.rx)(&signal);
+    assert_eq!(effect.current, 6);
+    assert_eq!(*shared.borrow(), [1, 2, 3]);
+}
+
+#[test]
+fn render_that_writes_what_it_reads_reruns_once() {
+    let mut count = 0;
+    let tracking: DirtyTrackSet<u8, u8> = DirtyTrackSet::default();
+    let render = |count: &mut RwTrack<i32, u8, u8>| {
+        // an accidental write during render
+        if **count < 10 {
+            **count += 1;
+        }
+        format!("{}", **count)
+    };
+
+    tracking.reset_read();
+    let mut output = render(&mut RwTrack {
+        data: &mut count,
+        tracking: tracking.track(0),
+    });
+    let subscriptions = tracking.get_read();
+
+    // the same loop a generated memo update runs
+    let mut renders = 0;
+    let mut diagnostics = Vec::new();
+    tracking.reset_write();
+    for _ in 0..2 {
+        output = render(&mut RwTrack {
+            data: &mut count,
+            tracking: tracking.track(0),
+        });
+        renders += 1;
+        match tracking.guard_render("Counter::memo_0", subscriptions) {
+            Some(diagnostic) => diagnostics.push(diagnostic),
+            None => break,
+        }
+    }
+
+    // the extra render still writes, but it isn't rendered again
+    assert_eq!(renders, 2);
+    assert_eq!(output, "3");
+    assert_eq!(diagnostics.len(), 2);
+    assert!(diagnostics[0].starts_with("Counter::memo_0 wrote the states in slots [0]"));
+    assert_eq!(tracking.get_write(), 0);
+}
+
 pub struct Effect<F, T> {
     pub rx: F,
     pub rx_subscriptions: u8,
     pub current: T,
 }
+
+#[test]
+fn tracked_attribute_values() {
+    let mut count = 5;
+    let mut name = String::from("qk");
+    let tracking: DirtyTrackSet<u8, u8> = DirtyTrackSet::default();
+    let count = RwTrack {
+        data: &mut count,
+        tracking: tracking.track(0),
+    };
+    let name = RwTrack {
+        data: &mut name,
+        tracking: tracking.track(1),
+    };
+
+    count.with_attribute(|value| assert_eq!(value, "5"));
+    name.with_attribute(|value| assert_eq!(value, "qk"));
+    // binding an attribute reads the state
+    assert!(tracking.is_read(0));
+    assert!(tracking.is_read(1));
+}
@@ -1,13 +1,20 @@
 use js_sys::Function;
+use serde::{de::DeserializeOwned, Serialize};
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::DerefMut;
 use std::rc::Rc;
+use std::str::FromStr;
 use std::sync::atomic::AtomicU64;
 use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
 
+use crate::copy::{Scope, State};
+use crate::fragment::Spawner;
 use crate::slab::IdSlab;
+use crate::storage::{MemoryStorage, Storage};
 use crate::{
     events::{EventDescription, PlatformEvents, EVENT_COUNT},
-    renderer::Renderer,
+    renderer::{Delegation, Leave, Renderer, TemplateId},
 };
 
 #[derive(Clone)]
@@ -15,9 +22,194 @@ pub struct WebRenderer(Rc<RefCell<WebRendererInner>>);
 
 pub struct WebRendererInner {
     channel: Channel,
-    ids: IdSlab<()>,
-    queued_listeners: Vec<(u32, &'static str, Box<dyn FnMut(web_sys::Event)>)>,
+    ids: IdSlab<Option<Fragment>>,
+    /// Listeners and observers to attach in the next flush, once their element exists
+    queued: Vec<(u32, Attached)>,
+    /// What was attached to each element, so it can be attached again when `set_tag` replaces the element
+    attached: HashMap<u32, Vec<Attached>>,
     event_handlers: SharedListeners,
+    leave_transitions: HashMap<u32, Leave>,
+    queued_focus: Option<u32>,
+    /// Removed elements that stay on the page until their leave transition ends
+    leaving: Vec<u32>,
+}
+
+/// A listener or observer on an element. The callback is shared so it can be attached to more than one element over time
+#[derive(Clone)]
+enum Attached {
+    Listener(&'static str, Rc<RefCell<dyn FnMut(web_sys::Event)>>),
+    Attribute(&'static str, Rc<RefCell<dyn FnMut(String)>>),
+    Scroll(Rc<RefCell<dyn FnMut(f64)>>),
+    Visible(Rc<RefCell<dyn FnMut(bool)>>),
+}
+
+impl Attached {
+    fn attach(&self, id: u32) {
+        match self.clone() {
+            Attached::Listener(event_name, callback) => {
+                let cb: Closure<dyn FnMut(web_sys::Event)> =
+                    Closure::new(move |event: web_sys::Event| (callback.borrow_mut())(event));
+                let cb_fn: &Function = cb.as_ref().unchecked_ref();
+                let node = get_node(id);
+                node.add_event_listener_with_callback(event_name, cb_fn)
+                    .unwrap();
+                cb.forget();
+            }
+            Attached::Attribute(name, callback) => {
+                let element: web_sys::Element = get_node(id).unchecked_into();
+                let observed = element.clone();
+                let cb: Closure<dyn FnMut(js_sys::Array)> = Closure::new(move |_| {
+                    // mutation records are batched, so only the latest value is reported
+                    if let Some(value) = observed.get_attribute(name) {
+                        (callback.borrow_mut())(value);
+                    }
+                });
+                let observer = web_sys::MutationObserver::new(cb.as_ref().unchecked_ref()).unwrap();
+                let mut options = web_sys::MutationObserverInit::new();
+                options
+                    .attributes(true)
+                    .attribute_filter(&js_sys::Array::of1(&JsValue::from_str(name)));
+                observer.observe_with_options(&element, &options).unwrap();
+                cb.forget();
+            }
+            Attached::Scroll(callback) => {
+                let element: web_sys::Element = get_node(id).unchecked_into();
+                let scrolled = element.clone();
+                // scroll events can fire many times a frame, so the position is read once in the next frame
+                let frame_requested = Rc::new(std::cell::Cell::new(false));
+                let frame: Closure<dyn FnMut()> = Closure::new({
+                    let frame_requested = frame_requested.clone();
+                    move || {
+                        frame_requested.set(false);
+                        (callback.borrow_mut())(scrolled.scroll_top() as f64);
+                    }
+                });
+                let cb: Closure<dyn FnMut()> = Closure::new(move || {
+                    if !frame_requested.replace(true) {
+                        let window = web_sys::window().unwrap();
+                        window
+                            .request_animation_frame(frame.as_ref().unchecked_ref())
+                            .unwrap();
+                    }
+                });
+                element
+                    .add_event_listener_with_callback("scroll", cb.as_ref().unchecked_ref())
+                    .unwrap();
+                cb.forget();
+            }
+            Attached::Visible(callback) => {
+                let element: web_sys::Element = get_node(id).unchecked_into();
+                let cb: Closure<dyn FnMut(js_sys::Array)> =
+                    Closure::new(move |entries: js_sys::Array| {
+                        // entries are batched, so only the latest one is reported
+                        if let Some(entry) = entries.iter().last() {
+                            let entry: web_sys::IntersectionObserverEntry = entry.unchecked_into();
+                            (callback.borrow_mut())(entry.is_intersecting());
+                        }
+                    });
+                let observer =
+                    web_sys::IntersectionObserver::new(cb.as_ref().unchecked_ref()).unwrap();
+                observer.observe(&element);
+                cb.forget();
+            }
+        }
+    }
+}
+
+impl WebRendererInner {
+    fn cancel_leave(&mut self, id: u32) {
+        if let Some(index) = self.leaving.iter().position(|leaving| *leaving == id) {
+            self.leaving.swap_remove(index);
+            self.channel.cancel_leave(id);
+        }
+    }
+
+    // The id of the js copy of a static string, sending it to js the first time it is used
+    fn intern(&mut self, value: &'static str) -> u32 {
+        let channel = &mut self.channel;
+        INTERNED.with(|interned| {
+            define_interned(interned, value, |id, value| {
+                channel.intern(id, value);
+                channel.flush();
+            })
+        })
+    }
+}
+
+thread_local! {
+    // The js string table is shared by every renderer on the page
+    static INTERNED: RefCell<Interner> = RefCell::new(Interner::default());
+}
+
+/// The id of a string in the shared table, calling `define` the first time the string is seen. Another renderer can use the id as soon as this returns, possibly flushing before the renderer that saw the string first, so `define` has to send the string to js right away instead of queuing it with the rest of the renderer's batch
+fn define_interned(
+    interned: &RefCell<Interner>,
+    value: &'static str,
+    define: impl FnOnce(u32, &'static str),
+) -> u32 {
+    let (id, new) = interned.borrow_mut().intern(value);
+    if new {
+        define(id, value);
+    }
+    id
+}
+
+/// Tags and attribute names are static strings that are used over and over. Each one is sent to js once and referred to by id after that. Strings that aren't static, like text or attribute values, are always sent
+#[derive(Default)]
+struct Interner {
+    ids: HashMap<&'static str, u32>,
+}
+
+impl Interner {
+    /// The id of the string and whether it was just added
+    fn intern(&mut self, value: &'static str) -> (u32, bool) {
+        let next = self.ids.len() as u32;
+        let id = *self.ids.entry(value).or_insert(next);
+        (id, id == next)
+    }
+}
+
+#[test]
+fn static_strings_are_interned_once() {
+    let mut interner = Interner::default();
+    let mut sent = Vec::new();
+    for _ in 0..1000 {
+        for name in ["div", "class"] {
+            let (id, new) = interner.intern(name);
+            if new {
+                sent.push((id, name));
+            }
+        }
+    }
+    assert_eq!(sent, [(0, "div"), (1, "class")]);
+    assert_eq!(interner.intern("class"), (1, false));
+    assert_eq!(interner.intern("span"), (2, true));
+
+    let encoded = encode_attributes(&[("class", "row"), ("id", "a")], |name| {
+        interner.intern(name).0
+    });
+    assert_eq!(encoded, "1\0row\03\0a\0");
+}
+
+#[test]
+fn interned_strings_are_defined_before_another_renderer_uses_them() {
+    // a stand in for the js string table and two renderers that queue ops until they flush
+    let table = RefCell::new(HashMap::new());
+    let interned = RefCell::new(Interner::default());
+    let mut first = Vec::new();
+    let mut second = Vec::new();
+
+    let div = define_interned(&interned, "div", |id, value| {
+        table.borrow_mut().insert(id, value);
+    });
+    first.push(div);
+    let again = define_interned(&interned, "div", |_, _| unreachable!());
+    second.push(again);
+
+    // the second renderer flushes first, but the string it refers to is already in the table
+    for id in second.drain(..).chain(first.drain(..)) {
+        assert_eq!(table.borrow().get(&id), Some(&"div"));
+    }
 }
 
 impl PlatformEvents for WebRenderer {
@@ -52,47 +244,86 @@ impl PlatformEvents for WebRenderer {
 
 impl Default for WebRenderer {
     fn default() -> Self {
-        let mut ids: IdSlab<()> = IdSlab::default();
+        let mut ids: IdSlab<Option<Fragment>> = IdSlab::default();
 
         // the root node
-        ids.id(());
+        ids.id(None);
 
         Self(Rc::new(RefCell::new(WebRendererInner {
             channel: Channel::default(),
             ids,
-            queued_listeners: Vec::new(),
+            queued: Vec::new(),
+            attached: HashMap::new(),
             event_handlers: SharedListeners::default(),
+            leave_transitions: HashMap::new(),
+            queued_focus: None,
+            leaving: Vec::new(),
         })))
     }
 }
 
+impl WebRenderer {
+    /// Create a node backed by a `DocumentFragment`. Children appended to the fragment are built off the DOM and moved into the real parent with a single append
+    pub fn create_fragment(&mut self) -> u32 {
+        let mut myself = self.0.borrow_mut();
+        let id = myself.ids.id(Some(Fragment::default()));
+        myself.channel.create_fragment(id);
+        id
+    }
+}
+
 impl Renderer<WebRenderer> for WebRenderer {
     fn node(&mut self) -> u32 {
         let mut myself = self.0.borrow_mut();
-        myself.ids.id(())
+        myself.ids.id(None)
     }
 
     fn append_all(&mut self, parent: u32, children: impl IntoIterator<Item = u32>) {
-        for child in children.into_iter() {
-            self.append_child(parent, child);
-        }
+        let children: Vec<u32> = children.into_iter().collect();
+        self.append_children(parent, &children);
     }
 
     fn set_attribute(&mut self, id: u32, name: &'static str, value: &str) {
         let mut myself = self.0.borrow_mut();
+        let name = myself.intern(name);
         myself.channel.set_attribute(id, name, value);
     }
 
+    fn set_attributes(&mut self, id: u32, attributes: &[(&'static str, &str)]) {
+        let mut myself = self.0.borrow_mut();
+        let attributes = encode_attributes(attributes, |name| myself.intern(name));
+        myself.channel.set_attributes(id, &attributes);
+    }
+
     fn set_style(&mut self, id: u32, name: &'static str, value: &str) {
         let mut myself = self.0.borrow_mut();
+        let name = myself.intern(name);
         myself.channel.set_style(id, name, value);
     }
 
+    fn set_value(&mut self, id: u32, value: &str) {
+        let mut myself = self.0.borrow_mut();
+        myself.channel.set_value(id, value);
+    }
+
+    fn set_selected(&mut self, id: u32, values: &[&str]) {
+        let mut myself = self.0.borrow_mut();
+        myself.channel.set_selected(id, &values.join("\0"));
+    }
+
     fn create_element(&mut self, id: u32, tag: &'static str) {
         let mut myself = self.0.borrow_mut();
+        let tag = myself.intern(tag);
         myself.channel.create_element(id, tag);
     }
 
+    fn create_element_ns(&mut self, id: u32, tag: &'static str, namespace: &'static str) {
+        let mut myself = self.0.borrow_mut();
+        let tag = myself.intern(tag);
+        let namespace = myself.intern(namespace);
+        myself.channel.create_element_ns(id, tag, namespace);
+    }
+
     fn create_text(&mut self, id: u32, text: &str) {
         let mut myself = self.0.borrow_mut();
         myself.channel.create_text(id, text);
@@ -103,9 +334,48 @@ impl Renderer<WebRenderer> for WebRenderer {
         myself.channel.set_text(id, text);
     }
 
+    fn replace_data(&mut self, id: u32, offset: u32, count: u32, data: &str) -> bool {
+        let mut myself = self.0.borrow_mut();
+        myself.channel.replace_data(id, offset, count, data);
+        true
+    }
+
+    fn set_tag(&mut self, id: u32, tag: &str) {
+        let mut myself = self.0.borrow_mut();
+        myself.channel.set_tag(id, tag);
+        // listeners and observers stay on the old element, so they are attached to the new one in the next flush
+        if let Some(attached) = myself.attached.remove(&id) {
+            myself
+                .queued
+                .extend(attached.into_iter().map(|attached| (id, attached)));
+        }
+    }
+
     fn append_child(&mut self, parent: u32, child: u32) {
         let mut myself = self.0.borrow_mut();
-        myself.channel.append_child(parent, child);
+        myself.cancel_leave(child);
+        for (parent, child) in fragment_appends(&mut myself.ids, parent, child) {
+            myself.channel.append_child(parent, child);
+        }
+    }
+
+    fn append_children(&mut self, parent: u32, children: &[u32]) {
+        let mut myself = self.0.borrow_mut();
+        for &child in children {
+            myself.cancel_leave(child);
+        }
+        let appends: Vec<(u32, u32)> = children
+            .iter()
+            .flat_map(|&child| fragment_appends(&mut myself.ids, parent, child))
+            .collect();
+        for (parent, children) in group_appends(appends) {
+            match children[..] {
+                [child] => myself.channel.append_child(parent, child),
+                _ => myself
+                    .channel
+                    .append_children(parent, &encode_ids(&children)),
+            }
+        }
     }
 
     fn clone_node(&mut self, id: u32, new_id: u32) {
@@ -130,14 +400,58 @@ impl Renderer<WebRenderer> for WebRenderer {
 
     fn remove(&mut self, id: u32) {
         let mut myself = self.0.borrow_mut();
-        myself.channel.remove(id);
+        if myself.leaving.contains(&id) {
+            return;
+        }
+        match myself.leave_transitions.get(&id).cloned() {
+            Some(leave) => {
+                let (class, millis) = match leave {
+                    Leave::Class(class) => (class, 0),
+                    Leave::Millis(millis) => (String::new(), millis),
+                };
+                myself.channel.leave(id, &class, millis);
+                myself.leaving.push(id);
+            }
+            None => {
+                for id in fragment_removals(&mut myself.ids, id) {
+                    myself.channel.remove(id);
+                }
+            }
+        }
+    }
+
+    fn set_leave_transition(&mut self, id: u32, leave: Leave) {
+        self.0.borrow_mut().leave_transitions.insert(id, leave);
+    }
+
+    fn replace_with(&mut self, id: u32, with: u32) {
+        let mut myself = self.0.borrow_mut();
+        myself.channel.replace(id, with);
     }
 
     fn return_node(&mut self, id: u32) {
         let mut myself = self.0.borrow_mut();
+        // a new node with the id doesn't inherit the transition, and a pending removal finishes on its own
+        myself.leave_transitions.remove(&id);
+        myself.leaving.retain(|leaving| *leaving != id);
         myself.ids.recycle(id)
     }
 
+    fn create_template(&mut self, build: impl FnOnce(&mut Self) -> u32) -> TemplateId {
+        let root = build(self);
+        let template = self.node();
+        let mut myself = self.0.borrow_mut();
+        myself.channel.create_template(template, root);
+        TemplateId(template)
+    }
+
+    fn instantiate_template(&mut self, template: TemplateId) -> u32 {
+        let id = self.node();
+        let mut myself = self.0.borrow_mut();
+        myself.channel.instantiate_template(template.0, id);
+        id
+    }
+
     fn add_listener<E: EventDescription<WebRenderer>>(
         &mut self,
         id: u32,
@@ -158,21 +472,115 @@ impl Renderer<WebRenderer> for WebRenderer {
             }
             add_delegated_event_listener(event_name, E::ID as usize, listeners);
         } else {
-            myself.queued_listeners.push((id, event_name, callback));
+            let callback = Rc::new(RefCell::new(callback));
+            myself
+                .queued
+                .push((id, Attached::Listener(event_name, callback)));
         }
     }
 
+    fn delegate_handler<E: EventDescription<WebRenderer>>(
+        &mut self,
+        _: E,
+        selector: &'static str,
+        mut handler: Box<dyn FnMut(web_sys::Event, String)>,
+    ) -> Delegation {
+        // the root always exists, and rows added under it later bubble to the same listener
+        let root = get_node(0);
+        let listener = {
+            let root = root.clone();
+            move |event: web_sys::Event| {
+                let Some(target) = event
+                    .target()
+                    .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+                else {
+                    return;
+                };
+                // closest can walk past the root, so only elements inside it count
+                if let Ok(Some(matched)) = target.closest(selector) {
+                    if root.contains(Some(matched.as_ref())) {
+                        let key = matched.get_attribute("data-key").unwrap_or_default();
+                        handler(event, key);
+                    }
+                }
+            }
+        };
+        let listener = Closure::wrap(Box::new(listener) as Box<dyn FnMut(web_sys::Event)>);
+        _ = root.add_event_listener_with_callback(E::NAME, listener.as_ref().unchecked_ref());
+        Delegation::new(move || {
+            _ = root
+                .remove_event_listener_with_callback(E::NAME, listener.as_ref().unchecked_ref());
+        })
+    }
+
+    fn focus(&mut self, id: u32) {
+        // the node may not have been created or added to the page yet
+        self.0.borrow_mut().queued_focus = Some(id);
+    }
+
+    fn observe_attribute(&mut self, id: u32, name: &'static str, callback: Box<dyn FnMut(String)>) {
+        // the node may not have been created yet
+        let callback = Rc::new(RefCell::new(callback));
+        self.0
+            .borrow_mut()
+            .queued
+            .push((id, Attached::Attribute(name, callback)));
+    }
+
+    fn set_scroll_top(&mut self, id: u32, top: f64) {
+        let mut myself = self.0.borrow_mut();
+        myself.channel.set_scroll_top(id, &top.to_string());
+    }
+
+    fn observe_scroll(&mut self, id: u32, callback: Box<dyn FnMut(f64)>) {
+        // the node may not have been created yet
+        let callback = Rc::new(RefCell::new(callback));
+        self.0
+            .borrow_mut()
+            .queued
+            .push((id, Attached::Scroll(callback)));
+    }
+
+    fn observe_visible(&mut self, id: u32, callback: Box<dyn FnMut(bool)>) {
+        // the node may not have been created yet
+        let callback = Rc::new(RefCell::new(callback));
+        self.0
+            .borrow_mut()
+            .queued
+            .push((id, Attached::Visible(callback)));
+    }
+
+    fn measure(&mut self, id: u32) -> Option<(f64, f64)> {
+        // the node may not have been created yet
+        self.0.borrow_mut().channel.flush();
+        let node = get_node(id);
+        if !node.is_connected() {
+            return None;
+        }
+        let rect = node
+            .dyn_into::<web_sys::Element>()
+            .ok()?
+            .get_bounding_client_rect();
+        Some((rect.width(), rect.height()))
+    }
+
     fn flush(&mut self) {
         let mut myself = self.0.borrow_mut();
         myself.channel.flush();
 
-        for (id, event_name, callback) in myself.queued_listeners.drain(..) {
-            let cb = Closure::new(callback);
-            let cb_fn: &Function = cb.as_ref().unchecked_ref();
-            let node = get_node(id);
-            node.add_event_listener_with_callback(event_name, cb_fn)
-                .unwrap();
-            cb.forget();
+        if let Some(id) = myself.queued_focus.take() {
+            let element: web_sys::HtmlElement = get_node(id).unchecked_into();
+            if element.is_connected() {
+                let _ = element.focus();
+            } else {
+                // try again after the flush that adds it to the page
+                myself.queued_focus = Some(id);
+            }
+        }
+
+        for (id, attached) in std::mem::take(&mut myself.queued) {
+            attached.attach(id);
+            myself.attached.entry(id).or_default().push(attached);
         }
     }
 }
@@ -180,11 +588,19 @@ impl Renderer<WebRenderer> for WebRenderer {
 #[sledgehammer_bindgen::bindgen]
 mod js {
     const JS: &str = r#"const nodes = [document.getElementById("main")];
+    const strings = [];
+    const leaving = {};
     export function get_node(id){
         return nodes[id];
     }
     export function get_handler_id(id, event_id){
         return nodes[id].getAttribute("data"+event_id);
+    }
+    export function track_immediate_stop(e){
+        if(e.__qkImmediate===undefined){const s=e.stopImmediatePropagation;e.__qkImmediate=false;e.stopImmediatePropagation=function(){e.__qkImmediate=true;s.call(e);};}
+    }
+    export function immediate_stopped(e){
+        return e.__qkImmediate===true;
     }"#;
 
     extern "C" {
@@ -192,26 +608,50 @@ mod js {
         fn get_node(id: u32) -> web_sys::Node;
         #[wasm_bindgen]
         fn get_handler_id(id: u32, event_id: usize) -> Option<u32>;
+        #[wasm_bindgen]
+        fn track_immediate_stop(event: &web_sys::Event);
+        #[wasm_bindgen]
+        fn immediate_stopped(event: &web_sys::Event) -> bool;
+    }
+
+    fn intern(id: u32, value: &str) {
+        "strings[$id$]=$value$;"
     }
 
-    fn create_element(id: u32, name: &'static str<u8>) {
-        r#"nodes[$id$]=document.createElement($name$);"#
+    fn create_element(id: u32, name: u32) {
+        r#"nodes[$id$]=document.createElement(strings[$name$]);"#
     }
 
-    fn create_element_ns(id: u32, name: &'static str<u8>, ns: &'static str<u8>) {
-        "nodes[$id$]=document.createElementNS($ns$,$name$);"
+    fn create_element_ns(id: u32, name: u32, ns: u32) {
+        "nodes[$id$]=document.createElementNS(strings[$ns$],strings[$name$]);"
     }
 
     fn create_text(id: u32, text: &str) {
         "nodes[$id$]=document.createTextNode($text$);"
     }
 
-    fn set_style(id: u32, name: &'static str<u8>, val: &str) {
-        "nodes[$id$].style[$name$]=$val$;"
+    fn create_fragment(id: u32) {
+        "nodes[$id$]=document.createDocumentFragment();"
+    }
+
+    fn set_style(id: u32, name: u32, val: &str) {
+        "nodes[$id$].style[strings[$name$]]=$val$;"
     }
 
-    fn set_attribute(id: u32, name: &'static str<u8>, val: &str) {
-        "nodes[$id$].setAttribute($name$,$val$);"
+    fn set_value(id: u32, val: &str) {
+        "nodes[$id$].value=$val$;"
+    }
+
+    fn set_selected(id: u32, values: &str) {
+        r#"{const v=$values$;const s=new Set(v?v.split("\0"):[]);for(const o of nodes[$id$].options){o.selected=s.has(o.value);}}"#
+    }
+
+    fn set_attribute(id: u32, name: u32, val: &str) {
+        "nodes[$id$].setAttribute(strings[$name$],$val$);"
+    }
+
+    fn set_attributes(id: u32, attributes: &str) {
+        r#"{const a=$attributes$.split("\0");for(let i=0;i+1<a.length;i+=2){nodes[$id$].setAttribute(strings[a[i]],a[i+1]);}}"#
     }
 
     fn remove_attribute(id: u32, name: &'static str<u8>) {
@@ -222,14 +662,40 @@ mod js {
         "nodes[$id$].appendChild(nodes[$id2$]);"
     }
 
+    fn append_children(id: u32, children: &str) {
+        r#"nodes[$id$].append(...$children$.split(",").map(i=>nodes[i]));"#
+    }
+
+    fn set_scroll_top(id: u32, top: &str) {
+        // positions within a pixel of the current one are skipped, so a user scroll written back through the bound state doesn't scroll again
+        "{const n=nodes[$id$];const t=+$top$;if(Math.abs(n.scrollTop-t)>=1)n.scrollTop=t;}"
+    }
+
     fn set_text(id: u32, text: &str) {
         "nodes[$id$].textContent=$text$;"
     }
 
+    fn replace_data(id: u32, offset: u32, count: u32, data: &str) {
+        "nodes[$id$].replaceData($offset$,$count$,$data$);"
+    }
+
+    fn set_tag(id: u32, tag: &str) {
+        "{const o=nodes[$id$];const n=document.createElementNS(o.namespaceURI,$tag$);for(const a of o.attributes)n.setAttribute(a.name,a.value);while(o.firstChild)n.appendChild(o.firstChild);o.replaceWith(n);nodes[$id$]=n;}"
+    }
+
     fn remove(id: u32) {
         "nodes[$id$].remove();"
     }
 
+    fn leave(id: u32, class: &str, millis: u32) {
+        // a class is removed with the element when its animation ends, otherwise the element is removed after the delay. A class that starts no animation or transition would never end, so the element is removed right away
+        r#"{const n=nodes[$id$],c=$class$;let t;const off=()=>{n.removeEventListener("animationend",end);n.removeEventListener("transitionend",end);};const end=()=>{off();if(leaving[$id$]===cancel)delete leaving[$id$];n.remove();};const cancel=()=>{clearTimeout(t);off();if(c)n.classList.remove(c);};leaving[$id$]=cancel;if(c){n.addEventListener("animationend",end,{once:true});n.addEventListener("transitionend",end,{once:true});n.classList.add(c);if(n.getAnimations().length===0)end();}else{t=setTimeout(end,$millis$);}}"#
+    }
+
+    fn cancel_leave(id: u32) {
+        "{const c=leaving[$id$];if(c){delete leaving[$id$];c();}}"
+    }
+
     fn replace(id: u32, id2: u32) {
         "nodes[$id$].replaceWith(nodes[$id2$]);"
     }
@@ -250,11 +716,396 @@ mod js {
         "nodes[$id2$]=nodes[$id$];"
     }
 
+    fn create_template(id: u32, root: u32) {
+        r#"{const t=document.createElement("template");t.content.appendChild(nodes[$root$]);nodes[$id$]=t;}"#
+    }
+
+    fn instantiate_template(id: u32, id2: u32) {
+        "nodes[$id2$]=nodes[$id$].content.firstChild.cloneNode(true);"
+    }
+
     fn add_listener(id: u32, event_id: u16, handler_id: u16) {
-        r#"nodes[$id$].setAttribute("data-event-"+($event_id$), $handler_id$);"#
+        r#"{const n=nodes[$id$],k="data-event-"+($event_id$),h=n.getAttribute(k);n.setAttribute(k,h===null?$handler_id$:h+" "+$handler_id$);}"#
     }
 }
 
+/// Read the value of the input, textarea or select that an event was dispatched to
+pub fn event_value(event: &web_sys::Event) -> String {
+    event
+        .target()
+        .and_then(|target| js_sys::Reflect::get(&target, &JsValue::from_str("value")).ok())
+        .and_then(|value| value.as_string())
+        .unwrap_or_default()
+}
+
+/// Read the values of the selected options of the select with the multiple attribute that an event was dispatched to
+pub fn event_selected(event: &web_sys::Event) -> Vec<String> {
+    let Some(target) = event.target() else {
+        return Vec::new();
+    };
+    let Ok(options) = js_sys::Reflect::get(&target, &JsValue::from_str("selectedOptions")) else {
+        return Vec::new();
+    };
+    js_sys::Array::from(&options)
+        .iter()
+        .filter_map(|option| js_sys::Reflect::get(&option, &JsValue::from_str("value")).ok())
+        .filter_map(|value| value.as_string())
+        .collect()
+}
+
+/// Write the number typed into a number input to a bound state. Text that isn't a number yet, like "" or "-" while typing, is ignored so the state keeps its last valid value
+pub fn update_number<T: FromStr + PartialEq>(text: &str, value: &mut impl DerefMut<Target = T>) {
+    let Ok(parsed) = text.trim().parse::<T>() else {
+        return;
+    };
+    // writing the same number back would reformat what is being typed, like "1." into "1"
+    if **value != parsed {
+        **value = parsed;
+    }
+}
+
+#[test]
+fn typing_numbers() {
+    use crate::tracking::{DirtyTrackSet, RwTrack};
+
+    let mut count = 0i32;
+    let tracking: DirtyTrackSet<u8, u8> = DirtyTrackSet::default();
+    let mut bound = RwTrack {
+        data: &mut count,
+        tracking: tracking.track(0),
+    };
+    for text in ["-", "-4", "-42"] {
+        update_number(text, &mut bound);
+    }
+    assert_eq!(*bound, -42);
+
+    tracking.reset_write();
+    for text in ["", "abc", "-42"] {
+        update_number(text, &mut bound);
+    }
+    assert_eq!(*bound, -42);
+    assert!(!tracking.is_write(0));
+}
+
+/// A state that is true while the CSS media query matches, like `(max-width: 600px)` or `(prefers-color-scheme: dark)`. It starts with the current match and is updated when the match changes. The listener is removed when the scope is dropped
+///
+/// Outside of a browser window the query never matches
+pub fn media_query_signal(cx: &Scope, query: &str) -> State<bool> {
+    let list = web_sys::window().and_then(|window| window.match_media(query).ok().flatten());
+    let matches = cx.state(list.as_ref().is_some_and(web_sys::MediaQueryList::matches));
+    if let Some(list) = list {
+        ScopedListener::listen(cx, list.into(), "change", move |event| {
+            let event: web_sys::MediaQueryListEvent = event.unchecked_into();
+            let _ = matches.try_set(event.matches());
+        });
+    }
+    matches
+}
+
+/// A state that is loaded from `localStorage` under `key` as JSON, or `default` if nothing that deserializes is stored, and written back on every change. Changes made by other tabs arrive as `storage` events and are read back into the state until the scope is dropped
+///
+/// If `localStorage` isn't available the value is only kept in memory. If a write fails, for example because the storage is full, a warning is logged and the state keeps the new value
+pub fn local_storage_signal<T: Serialize + DeserializeOwned + 'static>(
+    cx: &Scope,
+    key: &str,
+    default: impl FnOnce() -> T,
+) -> State<T> {
+    let window = web_sys::window();
+    let Some(storage) = window
+        .as_ref()
+        .and_then(|window| window.local_storage().ok().flatten())
+    else {
+        return cx.stored(MemoryStorage::default(), key, default);
+    };
+    let state = cx.stored(storage, key, default);
+    if let Some(window) = window {
+        let key = key.to_string();
+        // the storage event is only sent to the other tabs of the page
+        ScopedListener::listen(cx, window.into(), "storage", move |event| {
+            let event: web_sys::StorageEvent = event.unchecked_into();
+            if event.key().as_deref() == Some(key.as_str()) {
+                crate::storage::reload(state, event.new_value().as_deref());
+            }
+        });
+    }
+    state
+}
+
+impl Storage for web_sys::Storage {
+    fn get_item(&self, key: &str) -> Option<String> {
+        web_sys::Storage::get_item(self, key).ok().flatten()
+    }
+
+    fn set_item(&self, key: &str, value: &str) -> Result<(), String> {
+        web_sys::Storage::set_item(self, key, value).map_err(|err| format!("{err:?}"))
+    }
+}
+
+// An event listener on something outside of the renderer's nodes that is removed when the scope that owns it is dropped
+struct ScopedListener {
+    target: web_sys::EventTarget,
+    event: &'static str,
+    listener: Closure<dyn FnMut(web_sys::Event)>,
+}
+
+impl ScopedListener {
+    fn listen(
+        cx: &Scope,
+        target: web_sys::EventTarget,
+        event: &'static str,
+        f: impl FnMut(web_sys::Event) + 'static,
+    ) {
+        let listener: Closure<dyn FnMut(web_sys::Event)> = Closure::new(f);
+        target
+            .add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())
+            .unwrap();
+        cx.state(Self {
+            target,
+            event,
+            listener,
+        });
+    }
+}
+
+impl Drop for ScopedListener {
+    fn drop(&mut self) {
+        let _ = self.target.remove_event_listener_with_callback(
+            self.event,
+            self.listener.as_ref().unchecked_ref(),
+        );
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[test]
+fn media_query_starts_with_the_current_match() {
+    use crate::copy::{claim_rt, Readable};
+
+    let cx = crate::scope!(claim_rt());
+    let window = web_sys::window().unwrap();
+    for query in [
+        "(prefers-color-scheme: dark)",
+        "(min-width: 1px)",
+        "(max-width: 1px)",
+    ] {
+        let expected = window.match_media(query).unwrap().unwrap().matches();
+        assert_eq!(media_query_signal(&cx, query).get(), expected);
+    }
+}
+
+/// Spawns tasks with `setTimeout`, so the browser can handle input and paint before they run. Outside of a browser window tasks run right away
+#[derive(Clone, Copy, Default)]
+pub struct WebSpawner;
+
+impl Spawner for WebSpawner {
+    fn spawn(&self, task: Box<dyn FnOnce()>) {
+        match web_sys::window() {
+            Some(window) => {
+                let task = Closure::once_into_js(move || task());
+                let _ = window.set_timeout_with_callback(task.unchecked_ref());
+            }
+            None => task(),
+        }
+    }
+}
+
+/// Log panics to the browser console. With the `debug-signals` feature the effect that was running when the panic happened is logged as well.
+///
+/// Only the first call installs the hook. Returns false if the hook was already installed
+#[cfg(feature = "web")]
+pub fn set_panic_hook() -> bool {
+    static INSTALL: std::sync::Once = std::sync::Once::new();
+    let mut installed = false;
+    INSTALL.call_once(|| {
+        std::panic::set_hook(Box::new(|info| {
+            console_error_panic_hook::hook(info);
+            #[cfg(feature = "debug-signals")]
+            if let Some(context) = crate::copy::signal_context() {
+                let message = format!("the panic happened {context}");
+                // console is only available in the browser
+                if cfg!(target_arch = "wasm32") {
+                    web_sys::console::error_1(&message.into());
+                } else {
+                    eprintln!("{message}");
+                }
+            }
+        }));
+        installed = true;
+    });
+    installed
+}
+
+#[cfg(feature = "web")]
+#[test]
+fn panic_hook_is_installed_once() {
+    assert!(set_panic_hook());
+    assert!(!set_panic_hook());
+}
+
+// Attributes are sent to js as a single string of null separated interned name ids and values
+fn encode_attributes(
+    attributes: &[(&'static str, &str)],
+    mut intern: impl FnMut(&'static str) -> u32,
+) -> String {
+    let mut encoded = String::new();
+    for (name, value) in attributes {
+        encoded.push_str(&intern(name).to_string());
+        encoded.push('\0');
+        encoded.push_str(value);
+        encoded.push('\0');
+    }
+    encoded
+}
+
+#[test]
+fn encodes_attributes() {
+    let ids = |name| match name {
+        "id" => 0,
+        _ => 1,
+    };
+    assert_eq!(
+        encode_attributes(&[("id", "main"), ("class", "a b")], ids),
+        "0\0main\01\0a b\0"
+    );
+    assert_eq!(encode_attributes(&[], ids), "");
+}
+
+/// The children of a node created with [`WebRenderer::create_fragment`]
+#[derive(Default)]
+struct Fragment {
+    children: Vec<u32>,
+    // Appending a DocumentFragment moves its children out of it, so once it is appended the children are tracked under the real parent
+    parent: Option<u32>,
+    emptied: bool,
+}
+
+// The appends that need to happen in the DOM to append the child to the parent. Appending to or appending an emptied fragment is redirected to the fragment's children and real parent
+fn fragment_appends(
+    ids: &mut IdSlab<Option<Fragment>>,
+    parent: u32,
+    child: u32,
+) -> Vec<(u32, u32)> {
+    let parent = match ids.get_mut(parent) {
+        Some(Some(fragment)) => {
+            fragment.children.push(child);
+            match fragment.parent {
+                Some(real_parent) if fragment.emptied => real_parent,
+                _ => parent,
+            }
+        }
+        _ => parent,
+    };
+
+    match ids.get_mut(child) {
+        Some(Some(fragment)) => {
+            fragment.parent = Some(parent);
+            if fragment.emptied {
+                fragment
+                    .children
+                    .iter()
+                    .map(|&child| (parent, child))
+                    .collect()
+            } else {
+                fragment.emptied = true;
+                vec![(parent, child)]
+            }
+        }
+        _ => vec![(parent, child)],
+    }
+}
+
+// Consecutive appends to the same parent are sent to js together
+fn group_appends(appends: impl IntoIterator<Item = (u32, u32)>) -> Vec<(u32, Vec<u32>)> {
+    let mut groups: Vec<(u32, Vec<u32>)> = Vec::new();
+    for (parent, child) in appends {
+        match groups.last_mut() {
+            Some((last, children)) if *last == parent => children.push(child),
+            _ => groups.push((parent, vec![child])),
+        }
+    }
+    groups
+}
+
+fn encode_ids(ids: &[u32]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[test]
+fn appended_children_cross_once() {
+    let mut ids = IdSlab::default();
+    let list = ids.id(None);
+    let rows: Vec<u32> = (0..100).map(|_| ids.id(None)).collect();
+    let appends: Vec<(u32, u32)> = rows
+        .iter()
+        .flat_map(|&row| fragment_appends(&mut ids, list, row))
+        .collect();
+    let groups = group_appends(appends);
+    assert_eq!(groups, [(list, rows.clone())]);
+    assert_eq!(encode_ids(&groups[0].1[..3]), "1,2,3");
+
+    // the children of an appended fragment are moved to the real parent
+    let fragment = ids.id(Some(Fragment::default()));
+    let inside = ids.id(None);
+    fragment_appends(&mut ids, fragment, inside);
+    fragment_appends(&mut ids, list, fragment);
+    let later = ids.id(None);
+    let appends = [later, fragment]
+        .into_iter()
+        .flat_map(|child| fragment_appends(&mut ids, 0, child))
+        .collect::<Vec<_>>();
+    assert_eq!(group_appends(appends), [(0, vec![later, inside])]);
+}
+
+// The nodes that need to be removed from the DOM to remove the node
+fn fragment_removals(ids: &mut IdSlab<Option<Fragment>>, id: u32) -> Vec<u32> {
+    match ids.get_mut(id) {
+        Some(Some(fragment)) => {
+            if fragment.emptied {
+                fragment.parent = None;
+                fragment.children.clone()
+            } else {
+                // the children are still inside the fragment which is not in the DOM
+                Vec::new()
+            }
+        }
+        _ => vec![id],
+    }
+}
+
+#[test]
+fn fragment_is_appended_once() {
+    let mut ids: IdSlab<Option<Fragment>> = IdSlab::default();
+    let root = ids.id(None);
+    let fragment = ids.id(Some(Fragment::default()));
+
+    for _ in 0..1000 {
+        let child = ids.id(None);
+        assert_eq!(
+            fragment_appends(&mut ids, fragment, child),
+            [(fragment, child)]
+        );
+    }
+    assert_eq!(
+        fragment_appends(&mut ids, root, fragment),
+        [(root, fragment)]
+    );
+
+    // the fragment is empty now, so later children go straight to the real parent
+    let late = ids.id(None);
+    assert_eq!(fragment_appends(&mut ids, fragment, late), [(root, late)]);
+
+    // removing or moving the fragment affects the children it was emptied into
+    assert_eq!(fragment_removals(&mut ids, fragment).len(), 1001);
+    let other = ids.id(None);
+    let moved = fragment_appends(&mut ids, other, fragment);
+    assert_eq!(moved.len(), 1001);
+    assert!(moved.iter().all(|&(parent, _)| parent == other));
+    assert_eq!(fragment_removals(&mut ids, root), [root]);
+}
+
 // A bitset of events that have been registered globally
 static EVENT_STATUS: [AtomicU64; EVENT_COUNT / 64] = [AtomicU64::new(0), AtomicU64::new(0)];
 
@@ -279,6 +1130,7 @@ fn add_delegated_event_listener(
 ) {
     if !get_event(event_id) {
         let handler = move |ev: web_sys::Event| {
+            track_immediate_stop(&ev);
             let target = ev.target();
             let node = ev.composed_path().get(0);
             let node = if node.is_truthy() {
@@ -290,11 +1142,17 @@ fn add_delegated_event_listener(
 
             while !node.is_null() {
                 // navigate up tree
-                if let Some(maybe_handler) = node.get_attribute(&format!("data-event-{event_id}")) {
-                    if let Ok(handler_id) = maybe_handler.parse::<u32>() {
-                        let mut handlers = listeners.event_handlers.borrow_mut();
-                        let handler = handlers.get_mut(handler_id).expect("handler not found");
-                        handler(ev.clone());
+                if let Some(handler_ids) = node.get_attribute(&format!("data-event-{event_id}")) {
+                    // every handler on the node runs in the order it was added unless one stops immediate propagation
+                    for handler_id in handler_ids.split(' ') {
+                        if let Ok(handler_id) = handler_id.parse::<u32>() {
+                            let mut handlers = listeners.event_handlers.borrow_mut();
+                            let handler = handlers.get_mut(handler_id).expect("handler not found");
+                            handler(ev.clone());
+                        }
+                        if immediate_stopped(&ev) {
+                            return;
+                        }
                     }
                     if ev.cancel_bubble() {
                         return;
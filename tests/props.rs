@@ -0,0 +1,56 @@
+use qk::component::{Component, ComponentState};
+use qk::mock::{MockOp, MockRenderer};
+use qk::prelude::*;
+
+#[component]
+fn Point(cx: Scope, x: i32, y: i32) {
+    rsx! {
+        <div x={x.to_string()}></div>
+        <div y={y.to_string()}></div>
+    }
+}
+
+#[test]
+fn update_only_reruns_the_changed_prop() {
+    let mut ui = MockRenderer::default();
+    let mut point = Point { x: 1, y: 2 }.create(&mut ui);
+    let y = point.borrow().roots()[1];
+    ui.take_ops();
+
+    Point { x: 1, y: 3 }.update(&mut point);
+    ui.flush();
+    assert_eq!(
+        ui.take_ops(),
+        [MockOp::SetAttribute {
+            id: y,
+            name: "y",
+            value: "3".to_string(),
+        }]
+    );
+
+    // the same props don't write anything
+    Point { x: 1, y: 3 }.update(&mut point);
+    ui.flush();
+    assert!(ui.take_ops().is_empty());
+}
+
+// Not PartialEq, so it can't be compared with the old value
+struct Label(&'static str);
+
+#[component]
+fn Labeled(cx: Scope, label: Label) {
+    rsx! {
+        <div title={label.0.to_string()}></div>
+    }
+}
+
+#[test]
+fn props_without_partial_eq_are_always_written() {
+    let mut ui = MockRenderer::default();
+    let mut labeled = Labeled { label: Label("a") }.create(&mut ui);
+    ui.take_ops();
+
+    Labeled { label: Label("a") }.update(&mut labeled);
+    ui.flush();
+    assert_eq!(ui.take_ops().len(), 1);
+}
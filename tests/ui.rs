@@ -0,0 +1,6 @@
+// Warnings can only be checked by denying them, so each case fails to compile with the warning as its error
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,12 @@
+#![deny(deprecated)]
+
+use qk::prelude::*;
+
+#[component]
+fn Greeting(cx: Scope, name: String, unused: u32) {
+    rsx! {
+        <div>"{name}"</div>
+    }
+}
+
+fn main() {}